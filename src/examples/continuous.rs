@@ -0,0 +1,385 @@
+// Example of continuous optimisation, minimising a benchmark function over a box.
+// A solution is a vector of real numbers within the bounds of the function, its cost is the value of the function.
+// Every function has a known global minimum of 0, so the precision a heuristic reaches can be checked with `ContinuousSolution::error`.
+// Input file is a text file with the name of the function on the first line and the dimension on the second line, e.g. "rastrigin" and "10".
+// An optional third line with "shifted" and/or "rotated" moves the optimum away from its usual location and/or rotates the coordinates, so the variables are no longer separable.
+
+use crate::heuristics::real_vector::standard_normal;
+use crate::solution::{InstanceReader, RealVectorSolution, Solution};
+use rand::{rngs::SmallRng, thread_rng, Rng, SeedableRng};
+use std::f64::consts::{E, PI};
+use std::io::Write;
+use std::sync::Arc;
+use strum_macros::{Display, EnumIter, EnumString};
+
+/// Seed of the shift and rotation, so an input file always describes the same instance
+const TRANSFORMATION_SEED: u64 = 42;
+
+/// Location of the optimum of the Schwefel function in every dimension
+const SCHWEFEL_OPTIMUM: f64 = 420.968_746_359_982;
+
+pub struct ContinuousInstance {
+    dataset_name: String,
+    function: BenchmarkFunction,
+    bounds: Vec<(f64, f64)>,
+    /// Location of the global minimum
+    optimum: Vec<f64>,
+    /// Orthogonal matrix applied to the offset from the optimum, none if not rotated
+    rotation: Option<Vec<Vec<f64>>>,
+}
+
+impl ContinuousInstance {
+    /// Value of the benchmark function at the given point.
+    /// The point is mapped to z = z* + R(x - o), with z* the usual optimum of the function, o the optimum of the instance and R the rotation.
+    /// z is clamped to the domain, so rotated points outside of it cannot beat the optimum.
+    pub fn evaluate(&self, x: &[f64]) -> f64 {
+        let offset: Vec<f64> = x
+            .iter()
+            .zip(&self.optimum)
+            .map(|(xi, oi)| xi - oi)
+            .collect();
+        let offset = match &self.rotation {
+            Some(rotation) => rotation
+                .iter()
+                .map(|row| row.iter().zip(&offset).map(|(r, d)| r * d).sum())
+                .collect(),
+            None => offset,
+        };
+        let (lower, upper) = self.function.domain();
+        let z: Vec<f64> = offset
+            .iter()
+            .map(|d| (self.function.optimum() + d).clamp(lower, upper))
+            .collect();
+        self.function.value(&z)
+    }
+
+    /// Location of the global minimum
+    pub fn optimal_values(&self) -> &[f64] {
+        &self.optimum
+    }
+
+    /// Value of the global minimum
+    pub fn optimal_cost(&self) -> f64 {
+        0.0
+    }
+}
+
+#[derive(Clone)]
+pub struct ContinuousSolution {
+    instance: Arc<ContinuousInstance>,
+    values: Vec<f64>,
+    cost: f64,
+}
+
+impl ContinuousSolution {
+    /// Difference between the cost and the known optimal cost
+    pub fn error(&self) -> f64 {
+        self.cost - self.instance.optimal_cost()
+    }
+
+    /// Euclidean distance to the location of the known optimum
+    pub fn distance_to_optimum(&self) -> f64 {
+        self.values
+            .iter()
+            .zip(self.instance.optimal_values())
+            .map(|(x, o)| (x - o).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+impl Solution for ContinuousSolution {
+    fn get_cost(&mut self) -> f64 {
+        self.cost
+    }
+
+    fn write_solution(&self, file_location: &str) {
+        // File name will be dataset name + cost + .out
+        let file_path = format!(
+            "{}/{}-{:.4}.out",
+            file_location, self.instance.dataset_name, self.cost
+        );
+
+        let mut file =
+            std::fs::File::create(file_path.clone()).expect("Could not save solution to file!!!");
+        for value in &self.values {
+            writeln!(file, "{}", value).expect("Could not write to file");
+        }
+
+        println!(
+            "Error: {:e}, distance to the optimum: {:e}",
+            self.error(),
+            self.distance_to_optimum()
+        );
+        println!("Solution written to {}", file_path);
+    }
+}
+
+impl RealVectorSolution for ContinuousSolution {
+    fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    fn bounds(&self) -> &[(f64, f64)] {
+        &self.instance.bounds
+    }
+
+    fn with_values(&self, values: Vec<f64>) -> Self {
+        ContinuousSolution {
+            instance: self.instance.clone(),
+            cost: self.instance.evaluate(&values),
+            values,
+        }
+    }
+}
+
+/// Benchmark functions, all with a global minimum of 0
+#[derive(EnumIter, EnumString, Display, Clone, Copy, Debug)]
+#[strum(serialize_all = "lowercase")]
+pub enum BenchmarkFunction {
+    /// Sum of squares, unimodal
+    Sphere,
+    /// Narrow curved valley, unimodal in low dimensions but hard to follow
+    Rosenbrock,
+    /// Sphere with a cosine modulation, highly multimodal
+    Rastrigin,
+    /// Nearly flat outer region with a deep hole at the centre, multimodal
+    Ackley,
+    /// Sphere with a product of cosines, many regularly spaced local minima
+    Griewank,
+    /// Deceptive, the second best local minimum is far from the global one
+    Schwefel,
+    /// Multimodal with a rugged landscape, all minima lie on a grid
+    Levy,
+}
+
+impl BenchmarkFunction {
+    /// Usual search domain of the function, the same in every dimension
+    pub fn domain(&self) -> (f64, f64) {
+        match self {
+            BenchmarkFunction::Sphere => (-5.12, 5.12),
+            BenchmarkFunction::Rosenbrock => (-5.0, 10.0),
+            BenchmarkFunction::Rastrigin => (-5.12, 5.12),
+            BenchmarkFunction::Ackley => (-32.768, 32.768),
+            BenchmarkFunction::Griewank => (-600.0, 600.0),
+            BenchmarkFunction::Schwefel => (-500.0, 500.0),
+            BenchmarkFunction::Levy => (-10.0, 10.0),
+        }
+    }
+
+    /// Location of the global minimum of the untransformed function, the same in every dimension
+    pub fn optimum(&self) -> f64 {
+        match self {
+            BenchmarkFunction::Rosenbrock | BenchmarkFunction::Levy => 1.0,
+            BenchmarkFunction::Schwefel => SCHWEFEL_OPTIMUM,
+            _ => 0.0,
+        }
+    }
+
+    /// Value of the untransformed function
+    pub fn value(&self, x: &[f64]) -> f64 {
+        let n = x.len() as f64;
+        match self {
+            BenchmarkFunction::Sphere => x.iter().map(|xi| xi * xi).sum(),
+            BenchmarkFunction::Rosenbrock => x
+                .windows(2)
+                .map(|w| 100.0 * (w[1] - w[0] * w[0]).powi(2) + (1.0 - w[0]).powi(2))
+                .sum(),
+            BenchmarkFunction::Rastrigin => {
+                10.0 * n
+                    + x.iter()
+                        .map(|xi| xi * xi - 10.0 * (2.0 * PI * xi).cos())
+                        .sum::<f64>()
+            }
+            BenchmarkFunction::Ackley => {
+                let squares = x.iter().map(|xi| xi * xi).sum::<f64>() / n;
+                let cosines = x.iter().map(|xi| (2.0 * PI * xi).cos()).sum::<f64>() / n;
+                // Can end up a rounding error below zero at the optimum
+                (-20.0 * (-0.2 * squares.sqrt()).exp() - cosines.exp() + 20.0 + E).max(0.0)
+            }
+            BenchmarkFunction::Griewank => {
+                let squares = x.iter().map(|xi| xi * xi).sum::<f64>() / 4000.0;
+                let cosines = x
+                    .iter()
+                    .enumerate()
+                    .map(|(i, xi)| (xi / ((i + 1) as f64).sqrt()).cos())
+                    .product::<f64>();
+                1.0 + squares - cosines
+            }
+            BenchmarkFunction::Schwefel => {
+                // The constant is the value of a single term at the optimum, so the minimum is exactly 0
+                let term = |xi: f64| xi * xi.abs().sqrt().sin();
+                x.iter().map(|&xi| term(SCHWEFEL_OPTIMUM) - term(xi)).sum()
+            }
+            BenchmarkFunction::Levy => {
+                let w: Vec<f64> = x.iter().map(|xi| 1.0 + (xi - 1.0) / 4.0).collect();
+                let last = w[w.len() - 1];
+                (PI * w[0]).sin().powi(2)
+                    + w[..w.len() - 1]
+                        .iter()
+                        .map(|wi| (wi - 1.0).powi(2) * (1.0 + 10.0 * (PI * wi + 1.0).sin().powi(2)))
+                        .sum::<f64>()
+                    + (last - 1.0).powi(2) * (1.0 + (2.0 * PI * last).sin().powi(2))
+            }
+        }
+    }
+}
+
+/// Uniformly random rotation matrix, by Gram-Schmidt orthonormalisation of a Gaussian matrix
+fn random_rotation(dimension: usize, rng: &mut SmallRng) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = Vec::with_capacity(dimension);
+    while rows.len() < dimension {
+        let mut row: Vec<f64> = (0..dimension).map(|_| standard_normal(rng)).collect();
+        for previous in &rows {
+            let dot: f64 = row.iter().zip(previous).map(|(a, b)| a * b).sum();
+            for (a, b) in row.iter_mut().zip(previous) {
+                *a -= dot * b;
+            }
+        }
+        let norm = row.iter().map(|a| a * a).sum::<f64>().sqrt();
+        // Try again in the unlikely case the row is (nearly) dependent on the previous ones
+        if norm > 1e-8 {
+            rows.push(row.iter().map(|a| a / norm).collect());
+        }
+    }
+    rows
+}
+
+pub struct ContinuousInstanceReader {}
+impl ContinuousInstanceReader {
+    /// Creates the instance described by the contents of an input file, starting from a random point
+    pub fn parse_instance(
+        &self,
+        contents: &str,
+        instance_name: Option<&str>,
+    ) -> ContinuousSolution {
+        // First line is the name of the function, second line the dimension, third line the optional transformations
+        let mut lines = contents.lines();
+        let function: BenchmarkFunction = lines
+            .next()
+            .unwrap()
+            .trim()
+            .to_lowercase()
+            .parse()
+            .expect("Unknown benchmark function");
+        let dimension = lines.next().unwrap().trim().parse::<usize>().unwrap();
+        assert!(dimension > 0, "Dimension must be at least 1");
+        let transformations = lines.next().unwrap_or("").to_lowercase();
+
+        // Shift the optimum to a random point well within the domain, rotate with a random rotation
+        let (lower, upper) = function.domain();
+        let mut transformation_rng = SmallRng::seed_from_u64(TRANSFORMATION_SEED);
+        let optimum = if transformations.contains("shifted") {
+            (0..dimension)
+                .map(|_| transformation_rng.gen_range(0.8 * lower..=0.8 * upper))
+                .collect()
+        } else {
+            vec![function.optimum(); dimension]
+        };
+        let rotation = transformations
+            .contains("rotated")
+            .then(|| random_rotation(dimension, &mut transformation_rng));
+
+        // Initialize the solution with a uniformly random point in the domain
+        let bounds = vec![(lower, upper); dimension];
+        let mut rng = thread_rng();
+        let values = bounds
+            .iter()
+            .map(|&(lower, upper)| rng.gen_range(lower..=upper))
+            .collect::<Vec<f64>>();
+        let instance = ContinuousInstance {
+            dataset_name: instance_name.unwrap_or("unknown").to_string(),
+            function,
+            bounds,
+            optimum,
+            rotation,
+        };
+        ContinuousSolution {
+            cost: instance.evaluate(&values),
+            instance: Arc::new(instance),
+            values,
+        }
+    }
+}
+
+impl InstanceReader<ContinuousSolution> for ContinuousInstanceReader {
+    fn read_instance(&self, file_path: &str, instance_name: Option<&str>) -> ContinuousSolution {
+        let contents = std::fs::read_to_string(file_path).expect("Could not read file");
+        self.parse_instance(&contents, instance_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    /// Largest value accepted at the optimum, for rounding errors
+    const TOLERANCE: f64 = 1e-9;
+
+    #[test]
+    fn every_function_is_zero_at_its_optimum() {
+        for function in BenchmarkFunction::iter() {
+            for dimension in [1, 2, 10] {
+                let value = function.value(&vec![function.optimum(); dimension]);
+                assert!(
+                    value.abs() < TOLERANCE,
+                    "{} in {}D: {}",
+                    function,
+                    dimension,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn transformed_instances_are_zero_at_their_optimum() {
+        let reader = ContinuousInstanceReader {};
+        for function in BenchmarkFunction::iter() {
+            for transformations in ["shifted", "rotated", "shifted rotated"] {
+                let solution =
+                    reader.parse_instance(&format!("{}\n10\n{}", function, transformations), None);
+                let value = solution
+                    .with_values(solution.instance.optimal_values().to_vec())
+                    .error();
+                assert!(
+                    value.abs() < TOLERANCE,
+                    "{} {}: {}",
+                    transformations,
+                    function,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn random_rotation_is_orthonormal() {
+        let mut rng = SmallRng::seed_from_u64(TRANSFORMATION_SEED);
+        for dimension in [1, 2, 10, 30] {
+            let rotation = random_rotation(dimension, &mut rng);
+            for (i, row) in rotation.iter().enumerate() {
+                for (j, other) in rotation.iter().enumerate() {
+                    let dot: f64 = row.iter().zip(other).map(|(a, b)| a * b).sum();
+                    let expected = f64::from(u8::from(i == j));
+                    assert!(
+                        (dot - expected).abs() < TOLERANCE,
+                        "{}D: row {} . row {} = {}",
+                        dimension,
+                        i,
+                        j,
+                        dot
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension must be at least 1")]
+    fn zero_dimension_is_rejected() {
+        ContinuousInstanceReader {}.parse_instance("sphere\n0", None);
+    }
+}
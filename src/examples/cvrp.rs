@@ -0,0 +1,1091 @@
+// Example for the Capacitated Vehicle Routing Problem (CVRP).
+// Vehicles with the same capacity leave from and return to a single depot, every customer is visited by exactly one vehicle
+// and the demand of the customers on a route may not exceed the capacity. The cost of a solution is the total length of the routes.
+// Input is an instance in CVRPLIB format (.vrp) with EUC_2D distances rounded to the nearest integer, the depot has to be node 1.
+// Output is a solution in CVRPLIB format (.sol), customers are numbered from 1 in the order of the instance, leaving out the depot.
+//
+// A solution is a list of routes, some of which may be empty so a move can open a new route.
+// All moves keep the solution within capacity and compute the change in cost from the changed edges only.
+// Distances are assumed to be symmetric, so reversing part of a route does not change its length.
+
+use crate::moves::{CombinatorSolution, CombinatorState};
+use crate::solution::{InstanceReader, LocalRandomMove, MoveGenerator, Solution};
+use rand::{rngs::ThreadRng, thread_rng, Rng};
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// Number of empty routes added to the starting solution, so moves can open new routes
+const EXTRA_ROUTES: usize = 2;
+
+/// Number of tries a random move gets to find a move that respects the capacity, it does nothing otherwise
+const RANDOM_MOVE_ATTEMPTS: u32 = 100;
+
+/// Longest segment exchanged by cross-exchange
+const MAX_CROSS_SEGMENT_LENGTH: usize = 3;
+
+pub struct CvrpInstance {
+    dataset_name: String,
+    capacity: u32,
+    /// Demand of every node, the depot is node 0 and has no demand
+    demands: Vec<u32>,
+    /// Distance between every pair of nodes, row by row
+    distances: Vec<f64>,
+}
+
+impl CvrpInstance {
+    /// Distance between two nodes, node 0 being the depot
+    #[inline(always)]
+    pub fn distance(&self, a: usize, b: usize) -> f64 {
+        self.distances[a * self.demands.len() + b]
+    }
+}
+
+#[derive(Clone)]
+pub struct CvrpSolution {
+    instance: Arc<CvrpInstance>,
+    /// Customers of every route in the order they are visited, without the depot at either end
+    routes: Vec<Vec<usize>>,
+    /// Total demand of every route
+    loads: Vec<u32>,
+    /// Demand of every route up to every position, position 0 being the depot at the start
+    prefix_loads: Vec<Vec<u32>>,
+    cost: f64,
+    // Routes changed by the last random move as they were before it, and the cost before it
+    last_routes: Vec<(usize, Vec<usize>)>,
+    last_cost: f64,
+    combinator_state: CombinatorState,
+}
+
+impl CvrpSolution {
+    /// Node at a position of a route, position 0 and the position after the last customer are the depot
+    #[inline(always)]
+    fn node(&self, route: usize, position: usize) -> usize {
+        if position == 0 || position > self.routes[route].len() {
+            0
+        } else {
+            self.routes[route][position - 1]
+        }
+    }
+
+    #[inline(always)]
+    fn distance(&self, a: usize, b: usize) -> f64 {
+        self.instance.distance(a, b)
+    }
+
+    #[inline(always)]
+    fn demand(&self, node: usize) -> u32 {
+        self.instance.demands[node]
+    }
+
+    /// Recomputes the load and prefix loads of the route after it changed
+    fn refresh_route(&mut self, route: usize) {
+        let mut prefix = Vec::with_capacity(self.routes[route].len() + 1);
+        let mut load = 0;
+        prefix.push(load);
+        for &customer in &self.routes[route] {
+            load += self.instance.demands[customer];
+            prefix.push(load);
+        }
+        self.loads[route] = load;
+        self.prefix_loads[route] = prefix;
+    }
+
+    pub fn recompute_cost_from_scratch(&mut self) -> f64 {
+        let mut new_cost = 0.0;
+        for route in 0..self.routes.len() {
+            for position in 0..=self.routes[route].len() {
+                new_cost +=
+                    self.distance(self.node(route, position), self.node(route, position + 1));
+            }
+        }
+        self.cost = new_cost;
+        new_cost
+    }
+
+    /// Number of routes that visit at least one customer
+    pub fn num_vehicles(&self) -> usize {
+        self.routes.iter().filter(|route| !route.is_empty()).count()
+    }
+
+    /// Applies the move, updating the cost, loads and prefix loads
+    fn apply(&mut self, m: &CvrpMove) {
+        self.cost += m.delta(self);
+        m.apply_routes(&mut self.routes);
+        let (a, b) = m.routes();
+        self.refresh_route(a);
+        if b != a {
+            self.refresh_route(b);
+        }
+    }
+
+    /// Applies the move, remembering the changed routes so it can be undone
+    fn apply_undoable(&mut self, m: &CvrpMove) {
+        let (a, b) = m.routes();
+        self.last_routes.clear();
+        self.last_routes.push((a, self.routes[a].clone()));
+        if b != a {
+            self.last_routes.push((b, self.routes[b].clone()));
+        }
+        self.last_cost = self.cost;
+        self.apply(m);
+    }
+
+    /// Undoes the last random move, if it did anything
+    fn undo(&mut self) {
+        for (route, customers) in std::mem::take(&mut self.last_routes) {
+            self.routes[route] = customers;
+            self.refresh_route(route);
+        }
+        self.cost = self.last_cost;
+    }
+
+    /// Tries random moves of the neighbourhood until one respects the capacity and applies it, so it can be undone
+    fn do_random_move(&mut self, random_move: impl Fn(&Self, &mut ThreadRng) -> Option<CvrpMove>) {
+        let mut rng = thread_rng();
+        self.last_routes.clear();
+        self.last_cost = self.cost;
+        for _ in 0..RANDOM_MOVE_ATTEMPTS {
+            if let Some(m) = random_move(self, &mut rng) {
+                if m.fits(self) {
+                    self.apply_undoable(&m);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Random route with at least the given number of customers
+    fn random_route(&self, min_length: usize, rng: &mut ThreadRng) -> Option<usize> {
+        let route = rng.gen_range(0..self.routes.len());
+        (self.routes[route].len() >= min_length).then_some(route)
+    }
+}
+
+impl Solution for CvrpSolution {
+    fn get_cost(&mut self) -> f64 {
+        self.cost
+    }
+
+    fn write_solution(&self, file_location: &str) {
+        // File name will be dataset name + cost + .sol
+        let file_path = format!(
+            "{}/{}-{:.0}.sol",
+            file_location, self.instance.dataset_name, self.cost
+        );
+
+        let mut file =
+            std::fs::File::create(file_path.clone()).expect("Could not save solution to file!!!");
+        for (number, route) in self.routes.iter().filter(|r| !r.is_empty()).enumerate() {
+            let customers: Vec<String> = route.iter().map(|c| c.to_string()).collect();
+            writeln!(file, "Route #{}: {}", number + 1, customers.join(" "))
+                .expect("Could not write to file");
+        }
+        writeln!(file, "Cost {}", self.cost).expect("Could not write to file");
+
+        println!(
+            "Solution with {} vehicles written to {}",
+            self.num_vehicles(),
+            file_path
+        );
+    }
+
+    /// Demand above the capacity summed over the routes
+    fn constraint_violation(&mut self) -> f64 {
+        self.loads
+            .iter()
+            .map(|&load| f64::from(load.saturating_sub(self.instance.capacity)))
+            .sum()
+    }
+}
+
+impl CombinatorSolution for CvrpSolution {
+    fn combinator_state(&mut self) -> &mut CombinatorState {
+        &mut self.combinator_state
+    }
+}
+
+/// Move of one of the CVRP neighbourhoods. Positions are within a route, the first customer being at position 1.
+#[derive(Clone, Copy, Debug)]
+pub enum CvrpMove {
+    /// Moves the customer to the given position of the other route, or of the same route after taking it out
+    Relocate {
+        from_route: usize,
+        from: usize,
+        to_route: usize,
+        to: usize,
+    },
+    /// Exchanges two customers, if in the same route a comes before b
+    Swap {
+        route_a: usize,
+        a: usize,
+        route_b: usize,
+        b: usize,
+    },
+    /// Reverses the customers from position from up to and including position to within a route
+    TwoOpt {
+        route: usize,
+        from: usize,
+        to: usize,
+    },
+    /// Cuts two routes after the given positions and exchanges their ends
+    TwoOptStar {
+        route_a: usize,
+        a: usize,
+        route_b: usize,
+        b: usize,
+    },
+    /// Exchanges segments of two routes, starting at the given positions
+    CrossExchange {
+        route_a: usize,
+        a: usize,
+        length_a: usize,
+        route_b: usize,
+        b: usize,
+        length_b: usize,
+    },
+}
+
+impl CvrpMove {
+    /// Routes the move changes, the same route twice for moves within a route
+    fn routes(&self) -> (usize, usize) {
+        match *self {
+            CvrpMove::Relocate {
+                from_route,
+                to_route,
+                ..
+            } => (from_route, to_route),
+            CvrpMove::TwoOpt { route, .. } => (route, route),
+            CvrpMove::Swap {
+                route_a, route_b, ..
+            }
+            | CvrpMove::TwoOptStar {
+                route_a, route_b, ..
+            }
+            | CvrpMove::CrossExchange {
+                route_a, route_b, ..
+            } => (route_a, route_b),
+        }
+    }
+
+    /// Change in cost, from the edges that are removed and added
+    fn delta(&self, s: &CvrpSolution) -> f64 {
+        match *self {
+            CvrpMove::Relocate {
+                from_route,
+                from,
+                to_route,
+                to,
+            } => {
+                let customer = s.node(from_route, from);
+                let (before, after) = (s.node(from_route, from - 1), s.node(from_route, from + 1));
+                let removal = s.distance(before, after)
+                    - s.distance(before, customer)
+                    - s.distance(customer, after);
+
+                // Positions in the target route once the customer is taken out
+                let at = |position: usize| {
+                    if from_route == to_route && position >= from {
+                        s.node(to_route, position + 1)
+                    } else {
+                        s.node(to_route, position)
+                    }
+                };
+                let (a, b) = (at(to - 1), at(to));
+                removal + s.distance(a, customer) + s.distance(customer, b) - s.distance(a, b)
+            }
+            CvrpMove::Swap {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => {
+                let (x, y) = (s.node(route_a, a), s.node(route_b, b));
+                if route_a == route_b && b == a + 1 {
+                    let (before, after) = (s.node(route_a, a - 1), s.node(route_a, b + 1));
+                    s.distance(before, y) + s.distance(y, x) + s.distance(x, after)
+                        - s.distance(before, x)
+                        - s.distance(x, y)
+                        - s.distance(y, after)
+                } else {
+                    let replace = |route: usize, position: usize, old: usize, new: usize| {
+                        let (before, after) =
+                            (s.node(route, position - 1), s.node(route, position + 1));
+                        s.distance(before, new) + s.distance(new, after)
+                            - s.distance(before, old)
+                            - s.distance(old, after)
+                    };
+                    replace(route_a, a, x, y) + replace(route_b, b, y, x)
+                }
+            }
+            CvrpMove::TwoOpt { route, from, to } => {
+                let (before, first) = (s.node(route, from - 1), s.node(route, from));
+                let (last, after) = (s.node(route, to), s.node(route, to + 1));
+                s.distance(before, last) + s.distance(first, after)
+                    - s.distance(before, first)
+                    - s.distance(last, after)
+            }
+            CvrpMove::TwoOptStar {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => {
+                let (end_a, start_a) = (s.node(route_a, a), s.node(route_a, a + 1));
+                let (end_b, start_b) = (s.node(route_b, b), s.node(route_b, b + 1));
+                s.distance(end_a, start_b) + s.distance(end_b, start_a)
+                    - s.distance(end_a, start_a)
+                    - s.distance(end_b, start_b)
+            }
+            CvrpMove::CrossExchange {
+                route_a,
+                a,
+                length_a,
+                route_b,
+                b,
+                length_b,
+            } => {
+                let (before_a, first_a) = (s.node(route_a, a - 1), s.node(route_a, a));
+                let (last_a, after_a) = (
+                    s.node(route_a, a + length_a - 1),
+                    s.node(route_a, a + length_a),
+                );
+                let (before_b, first_b) = (s.node(route_b, b - 1), s.node(route_b, b));
+                let (last_b, after_b) = (
+                    s.node(route_b, b + length_b - 1),
+                    s.node(route_b, b + length_b),
+                );
+                s.distance(before_a, first_b)
+                    + s.distance(last_b, after_a)
+                    + s.distance(before_b, first_a)
+                    + s.distance(last_a, after_b)
+                    - s.distance(before_a, first_a)
+                    - s.distance(last_a, after_a)
+                    - s.distance(before_b, first_b)
+                    - s.distance(last_b, after_b)
+            }
+        }
+    }
+
+    /// Whether both routes respect the capacity after the move, from the loads and prefix loads
+    fn fits(&self, s: &CvrpSolution) -> bool {
+        let capacity = s.instance.capacity;
+        match *self {
+            CvrpMove::Relocate {
+                from_route,
+                from,
+                to_route,
+                ..
+            } => {
+                from_route == to_route
+                    || s.loads[to_route] + s.demand(s.node(from_route, from)) <= capacity
+            }
+            CvrpMove::Swap {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => {
+                let (x, y) = (s.demand(s.node(route_a, a)), s.demand(s.node(route_b, b)));
+                route_a == route_b
+                    || (s.loads[route_a] - x + y <= capacity
+                        && s.loads[route_b] - y + x <= capacity)
+            }
+            CvrpMove::TwoOpt { .. } => true,
+            CvrpMove::TwoOptStar {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => {
+                let (head_a, head_b) = (s.prefix_loads[route_a][a], s.prefix_loads[route_b][b]);
+                head_a + s.loads[route_b] - head_b <= capacity
+                    && head_b + s.loads[route_a] - head_a <= capacity
+            }
+            CvrpMove::CrossExchange {
+                route_a,
+                a,
+                length_a,
+                route_b,
+                b,
+                length_b,
+            } => {
+                let segment_a =
+                    s.prefix_loads[route_a][a + length_a - 1] - s.prefix_loads[route_a][a - 1];
+                let segment_b =
+                    s.prefix_loads[route_b][b + length_b - 1] - s.prefix_loads[route_b][b - 1];
+                s.loads[route_a] - segment_a + segment_b <= capacity
+                    && s.loads[route_b] - segment_b + segment_a <= capacity
+            }
+        }
+    }
+
+    /// Changes the routes, the cost and loads are updated by the solution
+    fn apply_routes(&self, routes: &mut [Vec<usize>]) {
+        match *self {
+            CvrpMove::Relocate {
+                from_route,
+                from,
+                to_route,
+                to,
+            } => {
+                let customer = routes[from_route].remove(from - 1);
+                routes[to_route].insert(to - 1, customer);
+            }
+            CvrpMove::Swap {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => {
+                let x = routes[route_a][a - 1];
+                routes[route_a][a - 1] = routes[route_b][b - 1];
+                routes[route_b][b - 1] = x;
+            }
+            CvrpMove::TwoOpt { route, from, to } => routes[route][from - 1..to].reverse(),
+            CvrpMove::TwoOptStar {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => {
+                let end_a = routes[route_a].split_off(a);
+                let end_b = routes[route_b].split_off(b);
+                routes[route_a].extend(end_b);
+                routes[route_b].extend(end_a);
+            }
+            CvrpMove::CrossExchange {
+                route_a,
+                a,
+                length_a,
+                route_b,
+                b,
+                length_b,
+            } => {
+                let segment_b: Vec<usize> = routes[route_b][b - 1..b - 1 + length_b].to_vec();
+                let segment_a: Vec<usize> = routes[route_a]
+                    .splice(a - 1..a - 1 + length_a, segment_b)
+                    .collect();
+                routes[route_b].splice(b - 1..b - 1 + length_b, segment_a);
+            }
+        }
+    }
+}
+
+/// Moves a customer to another position, in the same or another route
+pub struct CvrpRelocate;
+impl CvrpRelocate {
+    fn random(s: &CvrpSolution, rng: &mut ThreadRng) -> Option<CvrpMove> {
+        let from_route = s.random_route(1, rng)?;
+        let to_route = s.random_route(0, rng)?;
+        let from = rng.gen_range(1..=s.routes[from_route].len());
+        let target_length = if from_route == to_route {
+            s.routes[to_route].len() - 1
+        } else {
+            s.routes[to_route].len()
+        };
+        let to = rng.gen_range(1..=target_length + 1);
+        (from_route != to_route || from != to).then_some(CvrpMove::Relocate {
+            from_route,
+            from,
+            to_route,
+            to,
+        })
+    }
+}
+
+impl LocalRandomMove<CvrpSolution> for CvrpRelocate {
+    fn do_random_move(solution: &mut CvrpSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut CvrpSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<CvrpSolution> for CvrpRelocate {
+    type Move = CvrpMove;
+
+    fn for_each_move(
+        solution: &CvrpSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let from_route = (start + k) % num_routes;
+            for from in 1..=solution.routes[from_route].len() {
+                // Moving to an empty route only once, they are all the same
+                let mut tried_empty = false;
+                for to_route in 0..solution.routes.len() {
+                    let target_length = solution.routes[to_route].len();
+                    if target_length == 0 && std::mem::replace(&mut tried_empty, true) {
+                        continue;
+                    }
+                    let target_length = target_length - usize::from(from_route == to_route);
+                    for to in 1..=target_length + 1 {
+                        let m = CvrpMove::Relocate {
+                            from_route,
+                            from,
+                            to_route,
+                            to,
+                        };
+                        if (from_route != to_route || from != to) && m.fits(solution) {
+                            visit(m)?;
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &CvrpSolution, m: &Self::Move) -> f64 {
+        m.delta(solution)
+    }
+
+    fn apply_move(solution: &mut CvrpSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Exchanges two customers, in the same or different routes
+pub struct CvrpSwap;
+impl CvrpSwap {
+    fn random(s: &CvrpSolution, rng: &mut ThreadRng) -> Option<CvrpMove> {
+        let route_a = s.random_route(1, rng)?;
+        let route_b = s.random_route(1, rng)?;
+        let a = rng.gen_range(1..=s.routes[route_a].len());
+        let b = rng.gen_range(1..=s.routes[route_b].len());
+        if route_a == route_b && a == b {
+            return None;
+        }
+        let ((route_a, a), (route_b, b)) = if route_a == route_b && b < a {
+            ((route_b, b), (route_a, a))
+        } else {
+            ((route_a, a), (route_b, b))
+        };
+        Some(CvrpMove::Swap {
+            route_a,
+            a,
+            route_b,
+            b,
+        })
+    }
+}
+
+impl LocalRandomMove<CvrpSolution> for CvrpSwap {
+    fn do_random_move(solution: &mut CvrpSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut CvrpSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<CvrpSolution> for CvrpSwap {
+    type Move = CvrpMove;
+
+    fn for_each_move(
+        solution: &CvrpSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let route_a = (start + k) % num_routes;
+            for a in 1..=solution.routes[route_a].len() {
+                for route_b in route_a..solution.routes.len() {
+                    let first = if route_a == route_b { a + 1 } else { 1 };
+                    for b in first..=solution.routes[route_b].len() {
+                        let m = CvrpMove::Swap {
+                            route_a,
+                            a,
+                            route_b,
+                            b,
+                        };
+                        if m.fits(solution) {
+                            visit(m)?;
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &CvrpSolution, m: &Self::Move) -> f64 {
+        m.delta(solution)
+    }
+
+    fn apply_move(solution: &mut CvrpSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Reverses part of a route
+pub struct CvrpTwoOpt;
+impl CvrpTwoOpt {
+    fn random(s: &CvrpSolution, rng: &mut ThreadRng) -> Option<CvrpMove> {
+        let route = s.random_route(2, rng)?;
+        let from = rng.gen_range(1..s.routes[route].len());
+        let to = rng.gen_range(from + 1..=s.routes[route].len());
+        Some(CvrpMove::TwoOpt { route, from, to })
+    }
+}
+
+impl LocalRandomMove<CvrpSolution> for CvrpTwoOpt {
+    fn do_random_move(solution: &mut CvrpSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut CvrpSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<CvrpSolution> for CvrpTwoOpt {
+    type Move = CvrpMove;
+
+    fn for_each_move(
+        solution: &CvrpSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let route = (start + k) % num_routes;
+            for from in 1..solution.routes[route].len() {
+                for to in from + 1..=solution.routes[route].len() {
+                    visit(CvrpMove::TwoOpt { route, from, to })?;
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &CvrpSolution, m: &Self::Move) -> f64 {
+        m.delta(solution)
+    }
+
+    fn apply_move(solution: &mut CvrpSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Exchanges the ends of two routes
+pub struct CvrpTwoOptStar;
+impl CvrpTwoOptStar {
+    fn random(s: &CvrpSolution, rng: &mut ThreadRng) -> Option<CvrpMove> {
+        let route_a = s.random_route(0, rng)?;
+        let route_b = s.random_route(0, rng)?;
+        let a = rng.gen_range(0..=s.routes[route_a].len());
+        let b = rng.gen_range(0..=s.routes[route_b].len());
+        Self::is_useful(s, route_a, a, route_b, b).then_some(CvrpMove::TwoOptStar {
+            route_a,
+            a,
+            route_b,
+            b,
+        })
+    }
+
+    /// Whether the exchange changes anything, i.e. not within a route and not exchanging whole routes or nothing
+    fn is_useful(s: &CvrpSolution, route_a: usize, a: usize, route_b: usize, b: usize) -> bool {
+        route_a != route_b
+            && !(a == 0 && b == 0)
+            && !(a == s.routes[route_a].len() && b == s.routes[route_b].len())
+    }
+}
+
+impl LocalRandomMove<CvrpSolution> for CvrpTwoOptStar {
+    fn do_random_move(solution: &mut CvrpSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut CvrpSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<CvrpSolution> for CvrpTwoOptStar {
+    type Move = CvrpMove;
+
+    fn for_each_move(
+        solution: &CvrpSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let route_a = (start + k) % num_routes;
+            for route_b in route_a + 1..solution.routes.len() {
+                for a in 0..=solution.routes[route_a].len() {
+                    for b in 0..=solution.routes[route_b].len() {
+                        let m = CvrpMove::TwoOptStar {
+                            route_a,
+                            a,
+                            route_b,
+                            b,
+                        };
+                        if Self::is_useful(solution, route_a, a, route_b, b) && m.fits(solution) {
+                            visit(m)?;
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &CvrpSolution, m: &Self::Move) -> f64 {
+        m.delta(solution)
+    }
+
+    fn apply_move(solution: &mut CvrpSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Exchanges short segments of two routes, keeping their direction
+pub struct CvrpCrossExchange;
+impl CvrpCrossExchange {
+    fn random(s: &CvrpSolution, rng: &mut ThreadRng) -> Option<CvrpMove> {
+        let route_a = s.random_route(1, rng)?;
+        let route_b = s.random_route(1, rng)?;
+        if route_a == route_b {
+            return None;
+        }
+        let length_a = rng.gen_range(1..=MAX_CROSS_SEGMENT_LENGTH.min(s.routes[route_a].len()));
+        let length_b = rng.gen_range(1..=MAX_CROSS_SEGMENT_LENGTH.min(s.routes[route_b].len()));
+        let a = rng.gen_range(1..=s.routes[route_a].len() - length_a + 1);
+        let b = rng.gen_range(1..=s.routes[route_b].len() - length_b + 1);
+        Some(CvrpMove::CrossExchange {
+            route_a,
+            a,
+            length_a,
+            route_b,
+            b,
+            length_b,
+        })
+    }
+}
+
+impl LocalRandomMove<CvrpSolution> for CvrpCrossExchange {
+    fn do_random_move(solution: &mut CvrpSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut CvrpSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<CvrpSolution> for CvrpCrossExchange {
+    type Move = CvrpMove;
+
+    fn for_each_move(
+        solution: &CvrpSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let routes = &solution.routes;
+        let num_routes = routes.len();
+        for k in 0..num_routes {
+            let route_a = (start + k) % num_routes;
+            for route_b in route_a + 1..routes.len() {
+                for length_a in 1..=MAX_CROSS_SEGMENT_LENGTH.min(routes[route_a].len()) {
+                    for length_b in 1..=MAX_CROSS_SEGMENT_LENGTH.min(routes[route_b].len()) {
+                        for a in 1..=routes[route_a].len() - length_a + 1 {
+                            for b in 1..=routes[route_b].len() - length_b + 1 {
+                                let m = CvrpMove::CrossExchange {
+                                    route_a,
+                                    a,
+                                    length_a,
+                                    route_b,
+                                    b,
+                                    length_b,
+                                };
+                                if m.fits(solution) {
+                                    visit(m)?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &CvrpSolution, m: &Self::Move) -> f64 {
+        m.delta(solution)
+    }
+
+    fn apply_move(solution: &mut CvrpSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+pub struct CvrpInstanceReader {}
+impl CvrpInstanceReader {
+    /// Creates the instance described by the contents of an input file, starting from the sweep heuristic
+    pub fn parse_instance(&self, contents: &str, instance_name: Option<&str>) -> CvrpSolution {
+        // Header lines are "KEY : VALUE", followed by the sections
+        let mut capacity = 0;
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        let mut demands: Vec<u32> = Vec::new();
+        let mut depots: Vec<usize> = Vec::new();
+        let mut section = "";
+        for line in contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            if let Some((key, value)) = line.split_once(':') {
+                match key.trim() {
+                    "DIMENSION" => {
+                        let dimension = value.trim().parse::<usize>().unwrap();
+                        points = vec![(0.0, 0.0); dimension];
+                        demands = vec![0; dimension];
+                    }
+                    "CAPACITY" => capacity = value.trim().parse::<u32>().unwrap(),
+                    "EDGE_WEIGHT_TYPE" => assert_eq!(
+                        value.trim(),
+                        "EUC_2D",
+                        "Only EUC_2D distances are supported"
+                    ),
+                    _ => {}
+                }
+                continue;
+            }
+            if line.ends_with("_SECTION") || line == "EOF" {
+                section = line;
+                continue;
+            }
+
+            // Nodes are numbered from 1 in the file
+            let values: Vec<&str> = line.split_whitespace().collect();
+            match section {
+                "NODE_COORD_SECTION" => {
+                    let node = values[0].parse::<usize>().unwrap() - 1;
+                    points[node] = (values[1].parse().unwrap(), values[2].parse().unwrap());
+                }
+                "DEMAND_SECTION" => {
+                    let node = values[0].parse::<usize>().unwrap() - 1;
+                    demands[node] = values[1].parse().unwrap();
+                }
+                "DEPOT_SECTION" => {
+                    // The list of depots ends with -1, which does not parse
+                    if let Ok(depot) = values[0].parse::<usize>() {
+                        depots.push(depot);
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            depots,
+            vec![1],
+            "Only a single depot at node 1 is supported"
+        );
+
+        // Rounded Euclidean distances, as in CVRPLIB
+        let n = points.len();
+        let mut distances = vec![0.0; n * n];
+        for a in 0..n {
+            for b in 0..n {
+                let (x1, y1) = points[a];
+                let (x2, y2) = points[b];
+                distances[a * n + b] = ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt().round();
+            }
+        }
+        let instance = CvrpInstance {
+            dataset_name: instance_name.unwrap_or("unknown").to_string(),
+            capacity,
+            demands,
+            distances,
+        };
+
+        // Initialize the solution with the sweep heuristic: customers by angle around the depot, a new route when the vehicle is full
+        let (depot_x, depot_y) = points[0];
+        let mut customers: Vec<usize> = (1..n).collect();
+        customers.sort_by(|&a, &b| {
+            let angle = |c: usize| (points[c].1 - depot_y).atan2(points[c].0 - depot_x);
+            angle(a).total_cmp(&angle(b))
+        });
+        let mut routes: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut load = 0;
+        for customer in customers {
+            let demand = instance.demands[customer];
+            assert!(
+                demand <= capacity,
+                "Demand of customer {} exceeds the capacity",
+                customer
+            );
+            if load + demand > capacity {
+                routes.push(Vec::new());
+                load = 0;
+            }
+            load += demand;
+            routes.last_mut().unwrap().push(customer);
+        }
+        routes.extend((0..EXTRA_ROUTES).map(|_| Vec::new()));
+
+        let num_routes = routes.len();
+        let mut solution = CvrpSolution {
+            instance: Arc::new(instance),
+            routes,
+            loads: vec![0; num_routes],
+            prefix_loads: vec![Vec::new(); num_routes],
+            cost: 0.0, // will get overriden by recompute_cost_from_scratch
+            last_routes: Vec::new(),
+            last_cost: 0.0,
+            combinator_state: CombinatorState::default(),
+        };
+        for route in 0..num_routes {
+            solution.refresh_route(route);
+        }
+        solution.recompute_cost_from_scratch();
+        solution
+    }
+}
+
+impl InstanceReader<CvrpSolution> for CvrpInstanceReader {
+    fn read_instance(&self, file_path: &str, instance_name: Option<&str>) -> CvrpSolution {
+        let contents = std::fs::read_to_string(file_path).expect("Could not read file");
+        self.parse_instance(&contents, instance_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Eight customers around the depot with a small capacity, so many moves would exceed it
+    const SMALL_INSTANCE: &str = "NAME : small
+TYPE : CVRP
+DIMENSION : 9
+EDGE_WEIGHT_TYPE : EUC_2D
+CAPACITY : 10
+NODE_COORD_SECTION
+1 50 50
+2 80 50
+3 70 75
+4 50 90
+5 25 70
+6 15 50
+7 30 20
+8 50 10
+9 75 25
+DEMAND_SECTION
+1 0
+2 4
+3 3
+4 3
+5 5
+6 2
+7 3
+8 4
+9 2
+DEPOT_SECTION
+1
+-1
+EOF
+";
+
+    /// Applies every move the generator produces to a copy of the solution and checks the change in cost
+    /// against the recomputed cost, and that the routes stay within capacity and visit every customer once.
+    /// Returns the moves.
+    fn check_moves<G: MoveGenerator<CvrpSolution, Move = CvrpMove>>(
+        solution: &CvrpSolution,
+    ) -> Vec<CvrpMove> {
+        let mut moves = Vec::new();
+        let _ = G::for_each_move(solution, 0, |m| {
+            moves.push(m);
+            let mut moved = solution.clone();
+            let delta = G::evaluate_move(solution, &m);
+            G::apply_move(&mut moved, &m);
+            let recomputed = moved.clone().recompute_cost_from_scratch();
+            assert!((solution.cost + delta - recomputed).abs() < 1e-6);
+            assert!((moved.get_cost() - recomputed).abs() < 1e-6);
+            check_routes(&mut moved);
+            ControlFlow::Continue(())
+        });
+        moves
+    }
+
+    /// Checks the loads against the routes, the capacity and that every customer is visited once
+    fn check_routes(solution: &mut CvrpSolution) {
+        assert_eq!(solution.constraint_violation(), 0.0);
+        let mut customers: Vec<usize> = Vec::new();
+        for (route, load) in solution.routes.iter().zip(&solution.loads) {
+            let demand: u32 = route.iter().map(|&c| solution.instance.demands[c]).sum();
+            assert_eq!(demand, *load);
+            assert!(demand <= solution.instance.capacity);
+            customers.extend(route);
+        }
+        customers.sort_unstable();
+        assert_eq!(
+            customers,
+            (1..solution.instance.demands.len()).collect::<Vec<_>>()
+        );
+    }
+
+    /// Applies random moves and checks the solution after every move and undo
+    fn check_random_moves<M: LocalRandomMove<CvrpSolution>>(solution: &CvrpSolution) {
+        let mut solution = solution.clone();
+        for _ in 0..200 {
+            let cost_before = solution.get_cost();
+            M::do_random_move(&mut solution);
+            check_routes(&mut solution);
+            assert!(
+                (solution.get_cost() - solution.clone().recompute_cost_from_scratch()).abs() < 1e-6
+            );
+            M::undo_last_move(&mut solution);
+            check_routes(&mut solution);
+            assert_eq!(solution.get_cost(), cost_before);
+            M::do_random_move(&mut solution);
+        }
+    }
+
+    #[test]
+    fn moves_are_evaluated_exactly_and_respect_the_capacity() {
+        let solution = CvrpInstanceReader {}.parse_instance(SMALL_INSTANCE, None);
+        assert_eq!(
+            solution.routes,
+            vec![vec![6, 7, 8], vec![1, 2, 3], vec![4, 5], vec![], vec![]]
+        );
+        assert_eq!(solution.loads, vec![9, 10, 7, 0, 0]);
+
+        let relocates = check_moves::<CvrpRelocate>(&solution);
+        let swaps = check_moves::<CvrpSwap>(&solution);
+        assert!(!check_moves::<CvrpTwoOpt>(&solution).is_empty());
+        assert!(!check_moves::<CvrpTwoOptStar>(&solution).is_empty());
+        assert!(!check_moves::<CvrpCrossExchange>(&solution).is_empty());
+
+        // The second route is full, so nothing can be moved into it and no customer swapped in for one with a lower demand
+        assert!(!relocates.is_empty());
+        assert!(!relocates.iter().any(|m| matches!(
+            m,
+            CvrpMove::Relocate { from_route, to_route: 1, .. } if *from_route != 1
+        )));
+        assert!(!swaps.is_empty());
+        assert!(!swaps.iter().any(|m| matches!(
+            m,
+            CvrpMove::Swap {
+                route_a: 1,
+                a: 1,
+                route_b: 2,
+                b: 1
+            }
+        )));
+
+        check_random_moves::<CvrpRelocate>(&solution);
+        check_random_moves::<CvrpSwap>(&solution);
+        check_random_moves::<CvrpTwoOpt>(&solution);
+        check_random_moves::<CvrpTwoOptStar>(&solution);
+        check_random_moves::<CvrpCrossExchange>(&solution);
+    }
+}
@@ -0,0 +1,168 @@
+// Example of a maximisation problem, the maximum cut of a graph.
+// Every vertex is put on one of two sides, the cost of a solution is the total weight of the edges between the sides, which is maximised.
+// Input file is a text file where the first line is the number of vertices, followed by one line per edge with its two vertices (numbered from 0) and weight separated by a space.
+// Output has one line per vertex with its side, 0 or 1.
+
+use crate::solution::{InstanceReader, LocalRandomMove, OptimisationSense, Solution};
+use rand::{thread_rng, Rng};
+use std::io::Write;
+use std::sync::Arc;
+
+pub struct MaxCutInstance {
+    dataset_name: String,
+    /// Neighbours of every vertex together with the weight of the edge to them
+    neighbours: Vec<Vec<(usize, f64)>>,
+}
+
+#[derive(Clone)]
+pub struct MaxCutSolution {
+    instance: Arc<MaxCutInstance>,
+    /// Side of every vertex
+    sides: Vec<bool>,
+    cost: f64,
+    last_flip: usize,
+}
+
+impl MaxCutSolution {
+    /// Change in the weight of the cut when the vertex moves to the other side
+    fn flip_delta(&self, vertex: usize) -> f64 {
+        self.instance.neighbours[vertex]
+            .iter()
+            .map(|&(other, weight)| {
+                if self.sides[other] == self.sides[vertex] {
+                    weight
+                } else {
+                    -weight
+                }
+            })
+            .sum()
+    }
+
+    /// Moves the vertex to the other side, updating the cost
+    fn flip(&mut self, vertex: usize) {
+        self.cost += self.flip_delta(vertex);
+        self.sides[vertex] = !self.sides[vertex];
+        self.last_flip = vertex;
+    }
+}
+
+impl Solution for MaxCutSolution {
+    fn get_cost(&mut self) -> f64 {
+        self.cost
+    }
+
+    fn write_solution(&self, file_location: &str) {
+        // File name will be dataset name + cost + .out
+        let file_path = format!(
+            "{}/{}-{:.4}.out",
+            file_location, self.instance.dataset_name, self.cost
+        );
+
+        let mut file =
+            std::fs::File::create(file_path.clone()).expect("Could not save solution to file!!!");
+        for &side in &self.sides {
+            writeln!(file, "{}", u8::from(side)).expect("Could not write to file");
+        }
+
+        println!("Solution written to {}", file_path);
+    }
+
+    fn sense(&self) -> OptimisationSense {
+        OptimisationSense::Maximise
+    }
+}
+
+/// Moves a random vertex to the other side
+pub struct MaxCutFlipMove;
+impl LocalRandomMove<MaxCutSolution> for MaxCutFlipMove {
+    fn do_random_move(solution: &mut MaxCutSolution) {
+        if solution.sides.is_empty() {
+            return;
+        }
+        let vertex = thread_rng().gen_range(0..solution.sides.len());
+        solution.flip(vertex);
+    }
+
+    fn undo_last_move(solution: &mut MaxCutSolution) {
+        if solution.sides.is_empty() {
+            return;
+        }
+        solution.flip(solution.last_flip);
+    }
+}
+
+pub struct MaxCutInstanceReader {}
+impl MaxCutInstanceReader {
+    /// Creates the instance described by the contents of an input file, starting with every vertex on the same side
+    pub fn parse_instance(&self, contents: &str, instance_name: Option<&str>) -> MaxCutSolution {
+        // First line is the number of vertices, then one edge per line
+        let mut lines = contents.lines();
+        let num_vertices = lines.next().unwrap().trim().parse::<usize>().unwrap();
+        let mut neighbours = vec![Vec::new(); num_vertices];
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let a = values[0].parse::<usize>().unwrap();
+            let b = values[1].parse::<usize>().unwrap();
+            let weight = values[2].parse::<f64>().unwrap();
+            neighbours[a].push((b, weight));
+            neighbours[b].push((a, weight));
+        }
+
+        let instance = MaxCutInstance {
+            dataset_name: instance_name.unwrap_or("unknown").to_string(),
+            neighbours,
+        };
+        MaxCutSolution {
+            instance: Arc::new(instance),
+            sides: vec![false; num_vertices],
+            cost: 0.0,
+            last_flip: 0,
+        }
+    }
+}
+
+impl InstanceReader<MaxCutSolution> for MaxCutInstanceReader {
+    fn read_instance(&self, file_path: &str, instance_name: Option<&str>) -> MaxCutSolution {
+        let contents = std::fs::read_to_string(file_path).expect("Could not read file");
+        self.parse_instance(&contents, instance_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use crate::heuristics::simulated_annealing::{simulated_annealing, CoolingSchedule};
+
+    use super::*;
+
+    /// Cycle of the given even length with unit weights, its maximum cut contains every edge
+    fn even_cycle(num_vertices: usize) -> MaxCutSolution {
+        let edges: String = (0..num_vertices)
+            .map(|i| format!("{} {} 1\n", i, (i + 1) % num_vertices))
+            .collect();
+        MaxCutInstanceReader {}.parse_instance(&format!("{}\n{}", num_vertices, edges), None)
+    }
+
+    #[test]
+    fn simulated_annealing_increases_the_cut() {
+        let mut solution = even_cycle(10);
+        let starting_cost = solution.get_cost();
+        simulated_annealing::<MaxCutFlipMove, _>(
+            &mut solution,
+            10_000,
+            1_000,
+            CoolingSchedule::Exponential,
+            false,
+            "test",
+            Arc::new(AtomicBool::new(false)),
+        );
+        assert!(solution.get_cost() > starting_cost);
+
+        // The incrementally updated cost matches the cut of the final sides
+        let cut: f64 = (0..10)
+            .filter(|&i| solution.sides[i] != solution.sides[(i + 1) % 10])
+            .count() as f64;
+        assert_eq!(solution.get_cost(), cut);
+    }
+}
@@ -1 +1,5 @@
-pub mod tsp;
\ No newline at end of file
+pub mod continuous;
+pub mod cvrp;
+pub mod max_cut;
+pub mod tsp;
+pub mod vrptw;
\ No newline at end of file
@@ -1,16 +1,52 @@
 // Example for the Traveling Salesman Problem (TSP).
 // Input is defined as a number of points in the plane representing cities. The output is a permutation of the cities. The cost of a solution is the sum of the distances between consecutive cities in the permutation.
 // Input file is a text file where the first line is an integer the number of cities, followed by one line per city with the integer x and y coordinates separated by a space.
+// Alternatively the input is a TSPLIB file with a full distance matrix, such as an asymmetric (ATSP) instance, where the distance from a to b can differ from b to a.
 
+use crate::moves::{CombinatorSolution, CombinatorState};
 use crate::solution;
-use rand::{seq::SliceRandom, thread_rng};
-use solution::{InstanceReader, LocalRandomMove, Solution};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use solution::{
+    Crossover, DestroyOperator, GreedyConstruction, InstanceReader, LocalRandomMove, MoveGenerator,
+    MultiObjectiveSolution, PartialSolution, RelinkingMoves, RepairOperator, Solution,
+    SolutionDistance, TourSolution,
+};
 use std::sync::Arc;
 use std::io::Write;
+use std::ops::ControlFlow;
 
 pub struct TspInstance {
     dataset_name: String,
+    num_cities: usize,
+    /// Coordinates of the cities, empty when the instance is given as a distance matrix
     points: Vec<(f64, f64)>,
+    /// Distance from every city to every other city, row by row, empty when the instance is given as points
+    distances: Vec<f64>,
+    /// Whether the distance between two cities is the same in both directions
+    symmetric: bool,
+}
+
+impl TspInstance {
+    /// Distance from city a to city b, Euclidean unless the instance is given as a distance matrix
+    #[inline(always)]
+    pub fn distance(&self, a: usize, b: usize) -> f64 {
+        if !self.distances.is_empty() {
+            return self.distances[a * self.num_cities + b];
+        }
+        let (x1, y1) = self.points[a];
+        let (x2, y2) = self.points[b];
+        ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt()
+    }
+
+    /// Change in length of the path through the cities when it is travelled backwards, always 0 for symmetric distances
+    pub fn reversal_delta(&self, path: &[usize]) -> f64 {
+        if self.symmetric {
+            return 0.0;
+        }
+        path.windows(2)
+            .map(|edge| self.distance(edge[1], edge[0]) - self.distance(edge[0], edge[1]))
+            .sum()
+    }
 }
 
 #[derive(Clone)]
@@ -18,35 +54,88 @@ pub struct TspSolution {
     instance: Arc<TspInstance>,
     perm: Vec<usize>,
     cost: f64,
-    // These two below exact meaning depends on the move.
+    // These three below exact meaning depends on the move.
     last_swap: (usize, usize),
+    last_rotation: usize,
     last_cost: f64,
+    // Cities taken out of the tour by a destroy operator, waiting to be reinserted
+    removed: Vec<usize>,
+    combinator_state: CombinatorState,
 }
 
 impl TspSolution {
     pub fn recompute_cost_from_scratch(&mut self) -> f64 {
         let mut new_cost = 0.0;
         for i in 0..self.perm.len() {
-            let (x1, y1) = self.instance.points[self.perm[i]];
-            let (x2, y2) = self.instance.points[self.perm[(i + 1) % self.perm.len()]];
-            new_cost += ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt();
+            new_cost += self
+                .instance
+                .distance(self.perm[i], self.perm[(i + 1) % self.perm.len()]);
         }
         self.cost = new_cost;
         new_cost
     }
 
-    // /// Get the length of the edge between the i-th and (i+1)-th city in the permutation
-    // fn get_edge_length_next(&self, i: usize) -> f64 {
-    //     let (x1, y1) = self.instance.points[self.perm[i]];
-    //     let (x2, y2) = self.instance.points[self.perm[(i + 1) % self.perm.len()]];
-    //     ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt()
-    // }
+    /// Creates a solution for the same instance with the given permutation
+    fn with_perm(&self, perm: Vec<usize>) -> TspSolution {
+        let mut solution = TspSolution {
+            instance: self.instance.clone(),
+            perm,
+            cost: 0.0, // will get overriden by recompute_cost_from_scratch
+            last_swap: (0, 0),
+            last_rotation: 0,
+            last_cost: 0.0,
+            removed: Vec::new(),
+            combinator_state: CombinatorState::default(),
+        };
+        solution.recompute_cost_from_scratch();
+        solution
+    }
+
+    /// Takes the city at the given position out of the tour, to be reinserted later
+    fn remove_at(&mut self, position: usize) {
+        let n = self.perm.len();
+        let city = self.perm[position];
+        if n > 1 {
+            let prev = self.perm[(position + n - 1) % n];
+            let next = self.perm[(position + 1) % n];
+            self.cost += self.instance.distance(prev, next)
+                - self.instance.distance(prev, city)
+                - self.instance.distance(city, next);
+        }
+        self.perm.remove(position);
+        self.removed.push(city);
+    }
+
+    /// Change in cost when inserting the city between the given position and the next
+    fn insertion_cost(&self, city: usize, position: usize) -> f64 {
+        if self.perm.is_empty() {
+            return 0.0;
+        }
+        let prev = self.perm[position];
+        let next = self.perm[(position + 1) % self.perm.len()];
+        self.instance.distance(prev, city) + self.instance.distance(city, next)
+            - self.instance.distance(prev, next)
+    }
 
-    // fn get_edge_length_prev(&self, i: usize) -> f64 {
-    //     let (x1, y1) = self.instance.points[self.perm[i]];
-    //     let (x2, y2) = self.instance.points[self.perm[(i + self.perm.len() - 1) % self.perm.len()]];
-    //     ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt()
-    // }
+    /// Inserts the city between the given position and the next
+    fn insert_after(&mut self, city: usize, position: usize) {
+        self.cost += self.insertion_cost(city, position);
+        if self.perm.is_empty() {
+            self.perm.push(city);
+        } else {
+            self.perm.insert(position + 1, city);
+        }
+    }
+
+    /// Up to k cheapest insertions of the city as (cost, position), cheapest first
+    fn best_insertions(&self, city: usize, k: usize) -> Vec<(f64, usize)> {
+        let mut insertions: Vec<(f64, usize)> = (0..self.perm.len().max(1))
+            .map(|position| (self.insertion_cost(city, position), position))
+            .collect();
+        insertions.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        insertions.truncate(k);
+        insertions
+    }
 }
 
 impl Solution for TspSolution {
@@ -60,9 +149,16 @@ impl Solution for TspSolution {
 
         let mut file = std::fs::File::create(file_path.clone()).expect("Could not save solution to file!!!");
         for city in &self.perm {
-            // Get index of city in the instance
-            let (x, y) = self.instance.points[*city];
-            let index = self.instance.points.iter().position(|&p| p == (x, y)).unwrap();
+            // Get index of city in the instance, cities of a distance matrix are their index
+            let index = match self.instance.points.get(*city) {
+                Some(&point) => self
+                    .instance
+                    .points
+                    .iter()
+                    .position(|&p| p == point)
+                    .unwrap(),
+                None => *city,
+            };
             writeln!(file, "{}", index).expect("Could not write to file");
         }    
 
@@ -70,6 +166,66 @@ impl Solution for TspSolution {
     }
 }
 
+impl CombinatorSolution for TspSolution {
+    fn combinator_state(&mut self) -> &mut CombinatorState {
+        &mut self.combinator_state
+    }
+}
+
+impl SolutionDistance for TspSolution {
+    /// Number of edges of this tour that are not in the other tour
+    fn distance_to(&self, other: &Self) -> f64 {
+        let n = self.perm.len();
+
+        // Neighbours of every city in the other tour
+        let mut other_next = vec![0; n];
+        let mut other_prev = vec![0; n];
+        for k in 0..n {
+            other_next[other.perm[k]] = other.perm[(k + 1) % n];
+            other_prev[other.perm[(k + 1) % n]] = other.perm[k];
+        }
+
+        let mut different_edges = 0;
+        for k in 0..n {
+            let (a, b) = (self.perm[k], self.perm[(k + 1) % n]);
+            if other_next[a] != b && other_prev[a] != b {
+                different_edges += 1;
+            }
+        }
+        different_edges as f64
+    }
+}
+
+impl TourSolution for TspSolution {
+    fn tour(&self) -> &[usize] {
+        &self.perm
+    }
+
+    fn edge_length(&self, a: usize, b: usize) -> f64 {
+        self.instance.distance(a, b)
+    }
+
+    fn is_symmetric(&self) -> bool {
+        self.instance.symmetric
+    }
+
+    fn with_tour(&self, tour: Vec<usize>) -> Self {
+        self.with_perm(tour)
+    }
+}
+
+impl MultiObjectiveSolution for TspSolution {
+    /// Length of the tour and length of its longest edge, e.g. the longest a driver goes without a break.
+    /// The longest edge is not maintained by the moves, every call rescans the tour in time linear in the number of cities.
+    fn get_costs(&mut self) -> Vec<f64> {
+        let n = self.perm.len();
+        let longest_edge = (0..n)
+            .map(|k| self.instance.distance(self.perm[k], self.perm[(k + 1) % n]))
+            .fold(0.0, f64::max);
+        vec![self.cost, longest_edge]
+    }
+}
+
 pub struct TspNaiveMove;
 impl LocalRandomMove<TspSolution> for TspNaiveMove {
     fn do_random_move(solution: &mut TspSolution) {
@@ -78,15 +234,13 @@ impl LocalRandomMove<TspSolution> for TspNaiveMove {
         let j = rand::random::<usize>() % solution.perm.len();
         solution.perm.swap(i, j);
 
-        // Update last swap
+        // Update last swap and cost
         solution.last_swap = (i, j);
+        solution.last_cost = solution.cost;
 
         // Update cost by recomputing it from scratch
         // Not efficient!!!
         solution.recompute_cost_from_scratch();
-
-        // Update last cost
-        solution.last_cost = solution.cost;
     }
 
     fn undo_last_move(solution: &mut TspSolution) {
@@ -99,6 +253,66 @@ impl LocalRandomMove<TspSolution> for TspNaiveMove {
     }
 }
 
+impl MoveGenerator<TspSolution> for TspNaiveMove {
+    /// Swaps the cities at positions i and j
+    type Move = (usize, usize);
+
+    fn for_each_move(
+        solution: &TspSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let n = solution.perm.len();
+        for k in 0..n {
+            let i = (start + k) % n;
+            for j in (i + 1)..n {
+                visit((i, j))?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &TspSolution, &(i, j): &Self::Move) -> f64 {
+        let n = solution.perm.len();
+        // With fewer than 4 cities every symmetric tour has the same length
+        if i == j || (n < 4 && solution.instance.symmetric) {
+            return 0.0;
+        }
+
+        // City at a position after the swap
+        let city_at = |k: usize| {
+            if k == i {
+                solution.perm[j]
+            } else if k == j {
+                solution.perm[i]
+            } else {
+                solution.perm[k]
+            }
+        };
+
+        // Edges starting at these positions change, skip duplicates when i and j are neighbours
+        let mut changed_edges = [(i + n - 1) % n, i, (j + n - 1) % n, j];
+        changed_edges.sort_unstable();
+        let mut delta = 0.0;
+        for (index, &k) in changed_edges.iter().enumerate() {
+            if index > 0 && changed_edges[index - 1] == k {
+                continue;
+            }
+            let next = (k + 1) % n;
+            delta += solution.instance.distance(city_at(k), city_at(next))
+                - solution
+                    .instance
+                    .distance(solution.perm[k], solution.perm[next]);
+        }
+        delta
+    }
+
+    fn apply_move(solution: &mut TspSolution, m: &Self::Move) {
+        solution.cost += Self::evaluate_move(solution, m);
+        solution.perm.swap(m.0, m.1);
+    }
+}
+
 pub struct Tsp2OptMove; // Note: currently not _really_ 2Opt as it does not check all possible swaps
 impl LocalRandomMove<TspSolution> for Tsp2OptMove {
     fn do_random_move(solution: &mut TspSolution) {
@@ -109,24 +323,12 @@ impl LocalRandomMove<TspSolution> for Tsp2OptMove {
         // Make sure i < j
         let (i, j) = if i < j { (i, j) } else { (j, i) };
 
-        // Get lengths of edges to be removed
-        // let removed_i = solution.get_edge_length_next(i);
-        // let removed_j = solution.get_edge_length_prev(j);
-
-        // Do the swap
-        solution.perm[i..j].reverse();
-
-        // Get lengths of edges that were added
-        // let added_i = solution.get_edge_length_next(i);
-        // let added_j = solution.get_edge_length_prev(j);
-
         // Update last swap and cost
         solution.last_swap = (i, j);
         solution.last_cost = solution.cost;
 
-        // Update cost
-        // solution.cost += added_i + added_j - removed_i - removed_j;
-        solution.cost = solution.recompute_cost_from_scratch(); // todo fix properly
+        // Do the swap, updating the cost based on the changed edges
+        Self::apply_move(solution, &(i, j));
     }
 
     fn undo_last_move(solution: &mut TspSolution) {
@@ -139,23 +341,719 @@ impl LocalRandomMove<TspSolution> for Tsp2OptMove {
     }
 }
 
-pub struct TspInstanceReader {}
-impl InstanceReader<TspSolution> for TspInstanceReader {
-    fn read_instance(&self, file_path: &str, instance_name: Option<&str>) -> TspSolution {
-        // TSP instance first reads the number of cities
-        let contents = std::fs::read_to_string(file_path).expect("Could not read file");
-        let mut lines = contents.lines();
-        let num_cities = lines.next().unwrap().parse::<usize>().unwrap();
+impl MoveGenerator<TspSolution> for Tsp2OptMove {
+    /// Reverses the cities in perm[i..j]
+    type Move = (usize, usize);
+
+    fn for_each_move(
+        solution: &TspSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        // Skip reversals that do not change the tour: single cities and the (almost) complete tour.
+        // Reversing all cities but one changes the direction of the tour, which only matters for asymmetric distances.
+        let n = solution.perm.len();
+        for k in 0..n {
+            let i = (start + k) % n;
+            for j in (i + 2)..=n {
+                if j - i < n - 1 || (!solution.instance.symmetric && j - i < n) {
+                    visit((i, j))?;
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Only the edges at the ends of the reversed subsequence change, unless the distances are asymmetric.
+    /// Then every edge inside it is travelled the other way as well, which takes time linear in its length.
+    fn evaluate_move(solution: &TspSolution, &(i, j): &Self::Move) -> f64 {
+        let n = solution.perm.len();
+        let instance = &solution.instance;
+        if j - i < 2 || (j - i >= n - 1 && instance.symmetric) {
+            return 0.0;
+        }
+
+        let a = solution.perm[(i + n - 1) % n];
+        let b = solution.perm[i];
+        let c = solution.perm[j - 1];
+        let d = solution.perm[j % n];
+        instance.distance(a, c) + instance.distance(b, d)
+            - instance.distance(a, b)
+            - instance.distance(c, d)
+            + instance.reversal_delta(&solution.perm[i..j])
+    }
 
-        // Then reads the coordinates of each city, where each line has its x and y as integers
-        let mut points = Vec::new();
-        for _ in 0..num_cities {
-            let line = lines.next().unwrap();
-            let mut coords = line.split_whitespace();
-            let x = coords.next().unwrap().parse::<f64>().unwrap();
-            let y = coords.next().unwrap().parse::<f64>().unwrap();
-            points.push((x, y));
+    fn apply_move(solution: &mut TspSolution, m: &Self::Move) {
+        solution.cost += Self::evaluate_move(solution, m);
+        solution.perm[m.0..m.1].reverse();
+    }
+}
+
+impl RelinkingMoves<TspSolution> for Tsp2OptMove {
+    /// Reversals that create an edge of the guide, without removing more edges of the guide than they create
+    fn moves_towards(solution: &TspSolution, guide: &TspSolution) -> Vec<Self::Move> {
+        let n = solution.perm.len();
+        let perm = &solution.perm;
+        let mut position = vec![0; n];
+        for (index, &city) in perm.iter().enumerate() {
+            position[city] = index;
         }
+        let mut guide_neighbours = vec![[0; 2]; n];
+        for k in 0..n {
+            let (a, b) = (guide.perm[k], guide.perm[(k + 1) % n]);
+            guide_neighbours[a][1] = b;
+            guide_neighbours[b][0] = a;
+        }
+        let in_guide = |a: usize, b: usize| guide_neighbours[a].contains(&b);
+
+        let mut moves = Vec::new();
+        for u in 0..n {
+            for v in guide_neighbours[u] {
+                // Every edge of the guide once, from the city that comes first in the tour
+                let (p, q) = (position[u], position[v]);
+                if p >= q {
+                    continue;
+                }
+
+                // Reversals making u and v adjacent, either as the new (a, c) or the new (b, d) edge
+                for (i, j) in [(p + 1, q + 1), (p, q)] {
+                    if j - i < 2 || j - i >= n - 1 {
+                        continue;
+                    }
+                    let a = perm[(i + n - 1) % n];
+                    let b = perm[i];
+                    let c = perm[j - 1];
+                    let d = perm[j % n];
+                    let removed = usize::from(!in_guide(a, b)) + usize::from(!in_guide(c, d));
+                    let added = usize::from(!in_guide(a, c)) + usize::from(!in_guide(b, d));
+                    if removed > added {
+                        moves.push((i, j));
+                    }
+                }
+            }
+        }
+        moves
+    }
+}
+
+/// Double bridge kick, cuts the tour into A B C D and reconnects it as A C B D.
+/// Cannot be undone by a single 2-opt move, which makes it a good perturbation for iterated local search.
+pub struct TspDoubleBridgeMove;
+impl LocalRandomMove<TspSolution> for TspDoubleBridgeMove {
+    fn do_random_move(solution: &mut TspSolution) {
+        let n = solution.perm.len();
+        solution.last_cost = solution.cost;
+        if n < 4 {
+            solution.last_swap = (0, 0);
+            solution.last_rotation = 0;
+            return;
+        }
+
+        // Pick three distinct cut points in 1..n, B = perm[p1..p2], C = perm[p2..p3]
+        let mut cuts = rand::seq::index::sample(&mut thread_rng(), n - 1, 3).into_vec();
+        cuts.sort_unstable();
+        let (p1, p2, p3) = (cuts[0] + 1, cuts[1] + 1, cuts[2] + 1);
+
+        // Update cost based on the three changed edges
+        let a_end = solution.perm[p1 - 1];
+        let b_start = solution.perm[p1];
+        let b_end = solution.perm[p2 - 1];
+        let c_start = solution.perm[p2];
+        let c_end = solution.perm[p3 - 1];
+        let d_start = solution.perm[p3 % n];
+        let instance = &solution.instance;
+        solution.cost += instance.distance(a_end, c_start)
+            + instance.distance(c_end, b_start)
+            + instance.distance(b_end, d_start)
+            - instance.distance(a_end, b_start)
+            - instance.distance(b_end, c_start)
+            - instance.distance(c_end, d_start);
+
+        // Swap B and C
+        solution.perm[p1..p3].rotate_left(p2 - p1);
+
+        // Update last swap
+        solution.last_swap = (p1, p3);
+        solution.last_rotation = p2 - p1;
+    }
+
+    fn undo_last_move(solution: &mut TspSolution) {
+        // Swap C and B back
+        let (p1, p3) = solution.last_swap;
+        solution.perm[p1..p3].rotate_right(solution.last_rotation);
+
+        // Update cost
+        solution.cost = solution.last_cost;
+    }
+}
+
+/// Higher values make the worst and related removals pick the worst or most related city more often
+const REMOVAL_DETERMINISM: i32 = 3;
+
+/// Removes random cities from the tour
+pub struct TspRandomRemoval;
+impl DestroyOperator<TspSolution> for TspRandomRemoval {
+    fn destroy(solution: &mut TspSolution, degree: usize) {
+        let mut rng = thread_rng();
+        for _ in 0..degree.min(solution.perm.len().saturating_sub(1)) {
+            let position = rng.gen_range(0..solution.perm.len());
+            solution.remove_at(position);
+        }
+    }
+}
+
+/// Removes cities that add the most to the tour length, with some randomness
+pub struct TspWorstRemoval;
+impl DestroyOperator<TspSolution> for TspWorstRemoval {
+    fn destroy(solution: &mut TspSolution, degree: usize) {
+        let mut rng = thread_rng();
+        for _ in 0..degree.min(solution.perm.len().saturating_sub(1)) {
+            // Sort positions by how much removing them saves
+            let n = solution.perm.len();
+            let mut savings: Vec<(f64, usize)> = (0..n)
+                .map(|position| {
+                    let city = solution.perm[position];
+                    let prev = solution.perm[(position + n - 1) % n];
+                    let next = solution.perm[(position + 1) % n];
+                    let saving = solution.instance.distance(prev, city)
+                        + solution.instance.distance(city, next)
+                        - solution.instance.distance(prev, next);
+                    (saving, position)
+                })
+                .collect();
+            savings.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+
+            // Pick one of the worst, biased towards the start
+            let index = (rng.gen::<f64>().powi(REMOVAL_DETERMINISM) * n as f64) as usize;
+            solution.remove_at(savings[index].1);
+        }
+    }
+}
+
+/// Shaw removal, removes cities that are related, i.e. close to each other
+pub struct TspShawRemoval;
+impl DestroyOperator<TspSolution> for TspShawRemoval {
+    fn destroy(solution: &mut TspSolution, degree: usize) {
+        let mut rng = thread_rng();
+        let degree = degree.min(solution.perm.len().saturating_sub(1));
+        if degree == 0 {
+            return;
+        }
+
+        // Start with a random city
+        let first_removed = solution.removed.len();
+        let position = rng.gen_range(0..solution.perm.len());
+        solution.remove_at(position);
+
+        for _ in 1..degree {
+            // Sort the cities in the tour by distance to a random city removed before
+            let related_to = solution.removed[rng.gen_range(first_removed..solution.removed.len())];
+            let mut relatedness: Vec<(f64, usize)> = (0..solution.perm.len())
+                .map(|position| {
+                    let distance = solution
+                        .instance
+                        .distance(related_to, solution.perm[position]);
+                    (distance, position)
+                })
+                .collect();
+            relatedness.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+            // Pick one of the most related, biased towards the start
+            let index =
+                (rng.gen::<f64>().powi(REMOVAL_DETERMINISM) * relatedness.len() as f64) as usize;
+            solution.remove_at(relatedness[index].1);
+        }
+    }
+}
+
+/// Repeatedly inserts the removed city that is cheapest to insert, at its cheapest position
+pub struct TspCheapestInsertion;
+impl RepairOperator<TspSolution> for TspCheapestInsertion {
+    fn repair(solution: &mut TspSolution) {
+        while !solution.removed.is_empty() {
+            let mut best = (f64::INFINITY, 0, 0);
+            for (index, &city) in solution.removed.iter().enumerate() {
+                let (cost, position) = solution.best_insertions(city, 1)[0];
+                if cost < best.0 {
+                    best = (cost, index, position);
+                }
+            }
+
+            let city = solution.removed.swap_remove(best.1);
+            solution.insert_after(city, best.2);
+        }
+    }
+}
+
+/// Regret-k insertion, repeatedly inserts the removed city with the largest difference between its best and k - 1 next best insertions.
+/// Cities that lose the most by waiting are inserted first.
+pub struct TspRegretInsertion<const K: usize>;
+impl<const K: usize> RepairOperator<TspSolution> for TspRegretInsertion<K> {
+    fn repair(solution: &mut TspSolution) {
+        while !solution.removed.is_empty() {
+            // (regret, cheapest cost, index in removed, position)
+            let mut best = (f64::NEG_INFINITY, f64::INFINITY, 0, 0);
+            for (index, &city) in solution.removed.iter().enumerate() {
+                let insertions = solution.best_insertions(city, K.max(1));
+                let (cheapest, position) = insertions[0];
+                let regret: f64 = insertions.iter().skip(1).map(|(c, _)| c - cheapest).sum();
+                if regret > best.0 || (regret == best.0 && cheapest < best.1) {
+                    best = (regret, cheapest, index, position);
+                }
+            }
+
+            let city = solution.removed.swap_remove(best.2);
+            solution.insert_after(city, best.3);
+        }
+    }
+}
+
+/// Two distinct random cut points i < j, so the segment i..j has at least one city. Empty for a tour without cities.
+fn random_segment(n: usize) -> (usize, usize) {
+    if n == 0 {
+        return (0, 0);
+    }
+    let mut rng = thread_rng();
+    let i = rng.gen_range(0..=n);
+    // The second cut point is drawn from the other cut points
+    let mut j = rng.gen_range(0..n);
+    if j >= i {
+        j += 1;
+    }
+    (i.min(j), i.max(j))
+}
+
+/// Order crossover (OX), copies a segment of the first parent and fills the rest in the order of the second parent
+pub struct TspOrderCrossover;
+impl Crossover<TspSolution> for TspOrderCrossover {
+    fn crossover(parent_a: &TspSolution, parent_b: &TspSolution) -> TspSolution {
+        let n = parent_a.perm.len();
+        let (i, j) = random_segment(n);
+
+        // Copy the segment
+        let mut perm = vec![usize::MAX; n];
+        let mut used = vec![false; n];
+        for k in i..j {
+            perm[k] = parent_a.perm[k];
+            used[perm[k]] = true;
+        }
+
+        // Fill the other positions after the segment, in the order of the second parent starting after the segment
+        let mut position = j % n;
+        for k in 0..n {
+            let city = parent_b.perm[(j + k) % n];
+            if !used[city] {
+                perm[position] = city;
+                position = (position + 1) % n;
+            }
+        }
+        parent_a.with_perm(perm)
+    }
+}
+
+/// Partially mapped crossover (PMX), copies a segment of the first parent and places the conflicting cities of the second parent through the mapping of the segment
+pub struct TspPartiallyMappedCrossover;
+impl Crossover<TspSolution> for TspPartiallyMappedCrossover {
+    fn crossover(parent_a: &TspSolution, parent_b: &TspSolution) -> TspSolution {
+        let n = parent_a.perm.len();
+        let (i, j) = random_segment(n);
+
+        // Position of every city in the second parent
+        let mut position_b = vec![0; n];
+        for (position, &city) in parent_b.perm.iter().enumerate() {
+            position_b[city] = position;
+        }
+
+        // Copy the segment
+        let mut perm = vec![usize::MAX; n];
+        let mut used = vec![false; n];
+        for k in i..j {
+            perm[k] = parent_a.perm[k];
+            used[perm[k]] = true;
+        }
+
+        // Cities of the second parent in the segment that were not copied follow the mapping to a free position
+        for k in i..j {
+            let city = parent_b.perm[k];
+            if used[city] {
+                continue;
+            }
+            let mut position = k;
+            while (i..j).contains(&position) {
+                position = position_b[parent_a.perm[position]];
+            }
+            perm[position] = city;
+            used[city] = true;
+        }
+
+        // Everything else comes from the second parent
+        for (city, &city_b) in perm.iter_mut().zip(parent_b.perm.iter()) {
+            if *city == usize::MAX {
+                *city = city_b;
+            }
+        }
+        parent_a.with_perm(perm)
+    }
+}
+
+/// Cycle crossover (CX), every city keeps the position it has in one of the parents, alternating parents per cycle
+pub struct TspCycleCrossover;
+impl Crossover<TspSolution> for TspCycleCrossover {
+    fn crossover(parent_a: &TspSolution, parent_b: &TspSolution) -> TspSolution {
+        let n = parent_a.perm.len();
+
+        // Position of every city in the first parent
+        let mut position_a = vec![0; n];
+        for (position, &city) in parent_a.perm.iter().enumerate() {
+            position_a[city] = position;
+        }
+
+        let mut perm = vec![usize::MAX; n];
+        let mut from_a = true;
+        for start in 0..n {
+            if perm[start] != usize::MAX {
+                continue;
+            }
+
+            // Follow the cycle, taking the cities from the parent of this cycle
+            let mut position = start;
+            loop {
+                perm[position] = if from_a {
+                    parent_a.perm[position]
+                } else {
+                    parent_b.perm[position]
+                };
+                position = position_a[parent_b.perm[position]];
+                if position == start {
+                    break;
+                }
+            }
+            from_a = !from_a;
+        }
+        parent_a.with_perm(perm)
+    }
+}
+
+/// Edge recombination crossover (ERX), builds a tour mostly out of edges of the parents.
+/// Always continues to the neighbouring city that has the fewest remaining neighbours itself.
+pub struct TspEdgeRecombinationCrossover;
+impl Crossover<TspSolution> for TspEdgeRecombinationCrossover {
+    fn crossover(parent_a: &TspSolution, parent_b: &TspSolution) -> TspSolution {
+        let n = parent_a.perm.len();
+        let mut rng = thread_rng();
+
+        // Edge table, the neighbours of every city in both parents
+        let mut neighbours: Vec<Vec<usize>> = vec![Vec::with_capacity(4); n];
+        for parent in [parent_a, parent_b] {
+            for k in 0..n {
+                let city = parent.perm[k];
+                for neighbour in [parent.perm[(k + 1) % n], parent.perm[(k + n - 1) % n]] {
+                    if !neighbours[city].contains(&neighbour) {
+                        neighbours[city].push(neighbour);
+                    }
+                }
+            }
+        }
+
+        // Cities not yet in the tour, with their index in the list for constant time removal
+        let mut unvisited: Vec<usize> = (0..n).collect();
+        let mut index_in_unvisited: Vec<usize> = (0..n).collect();
+        let mut visit = |city: usize, unvisited: &mut Vec<usize>| {
+            let index = index_in_unvisited[city];
+            let last = *unvisited.last().unwrap();
+            unvisited.swap_remove(index);
+            if last != city {
+                index_in_unvisited[last] = index;
+            }
+        };
+
+        let mut perm = Vec::with_capacity(n);
+        let mut city = parent_a.perm[0];
+        loop {
+            perm.push(city);
+            visit(city, &mut unvisited);
+            // The edge table is symmetric, so only the neighbours of the city list it
+            for neighbour in neighbours[city].clone() {
+                neighbours[neighbour].retain(|&c| c != city);
+            }
+            if unvisited.is_empty() {
+                break;
+            }
+
+            // Neighbour with the fewest remaining neighbours, random city if there are none
+            city = match neighbours[city]
+                .iter()
+                .min_by_key(|&&c| (neighbours[c].len(), rng.gen::<u32>()))
+            {
+                Some(&next) => next,
+                None => unvisited[rng.gen_range(0..unvisited.len())],
+            };
+        }
+        parent_a.with_perm(perm)
+    }
+}
+
+/// Randomised nearest neighbour construction, the next city is picked at random among the unvisited cities
+/// at most alpha of the way from the nearest to the furthest one.
+pub struct TspNearestNeighbourConstruction;
+impl GreedyConstruction<TspSolution> for TspNearestNeighbourConstruction {
+    fn construct(solution: &TspSolution, alpha: f64) -> TspSolution {
+        let mut rng = thread_rng();
+        let mut unvisited: Vec<usize> = (0..solution.perm.len()).collect();
+        let mut perm = Vec::with_capacity(unvisited.len());
+        let start = rng.gen_range(0..unvisited.len());
+        perm.push(unvisited.swap_remove(start));
+
+        let mut restricted = Vec::new();
+        while !unvisited.is_empty() {
+            let last = perm[perm.len() - 1];
+            let distances: Vec<f64> = unvisited
+                .iter()
+                .map(|&city| solution.instance.distance(last, city))
+                .collect();
+            let nearest = distances.iter().cloned().fold(f64::INFINITY, f64::min);
+            let furthest = distances.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let threshold = nearest + alpha * (furthest - nearest);
+            restricted.clear();
+            restricted.extend((0..unvisited.len()).filter(|&k| distances[k] <= threshold));
+            let k = *restricted.choose(&mut rng).unwrap();
+            perm.push(unvisited.swap_remove(k));
+        }
+        solution.with_perm(perm)
+    }
+}
+
+/// Tour under construction, starting at the first city. Every extension is the next city to visit.
+/// Scores are the length so far plus the distance of every city still to be reached to its nearest other city.
+#[derive(Clone)]
+pub struct TspPartialTour {
+    instance: Arc<TspInstance>,
+    /// Distance from the nearest other city to every city, shared by all partial tours of the instance
+    nearest: Arc<Vec<f64>>,
+    path: Vec<usize>,
+    visited: Vec<bool>,
+    /// Length of the path, including the edge back to the first city once complete
+    length: f64,
+    /// Sum of the nearest distances of the unvisited cities
+    remaining_nearest: f64,
+}
+
+impl TspPartialTour {
+    /// Lower bound on the length still to be added to a path of the given number of cities:
+    /// every unvisited city and, to close the tour, the first city still have to be reached once
+    fn remaining_estimate(&self, path_length: usize, remaining_nearest: f64) -> f64 {
+        if path_length == self.visited.len() {
+            0.0
+        } else {
+            remaining_nearest + self.nearest[self.path[0]]
+        }
+    }
+}
+
+impl PartialSolution<TspSolution> for TspPartialTour {
+    type Extension = usize;
+
+    fn empty(solution: &TspSolution) -> Self {
+        let instance = solution.instance.clone();
+        let n = instance.num_cities;
+        let nearest: Vec<f64> = (0..n)
+            .map(|b| {
+                (0..n)
+                    .filter(|&a| a != b)
+                    .map(|a| instance.distance(a, b))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .map(|distance| if distance.is_finite() { distance } else { 0.0 })
+            .collect();
+        // Without cities the empty tour is already complete
+        let path: Vec<usize> = (0..n.min(1)).collect();
+        let mut visited = vec![false; n];
+        for &city in &path {
+            visited[city] = true;
+        }
+        TspPartialTour {
+            instance,
+            remaining_nearest: nearest.iter().skip(1).sum(),
+            nearest: Arc::new(nearest),
+            path,
+            visited,
+            length: 0.0,
+        }
+    }
+
+    fn extensions(&self) -> Vec<Self::Extension> {
+        (0..self.visited.len())
+            .filter(|&city| !self.visited[city])
+            .collect()
+    }
+
+    fn extend(&mut self, &city: &Self::Extension) {
+        let last = self.path[self.path.len() - 1];
+        self.length += self.instance.distance(last, city);
+        self.remaining_nearest -= self.nearest[city];
+        self.visited[city] = true;
+        self.path.push(city);
+        if self.is_complete() {
+            self.length += self.instance.distance(city, self.path[0]);
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.path.len() == self.visited.len()
+    }
+
+    fn score(&self) -> f64 {
+        self.length + self.remaining_estimate(self.path.len(), self.remaining_nearest)
+    }
+
+    fn score_extension(&self, &city: &Self::Extension) -> f64 {
+        let last = self.path[self.path.len() - 1];
+        let mut length = self.length + self.instance.distance(last, city);
+        if self.path.len() + 1 == self.visited.len() {
+            length += self.instance.distance(city, self.path[0]);
+        }
+        length
+            + self.remaining_estimate(
+                self.path.len() + 1,
+                self.remaining_nearest - self.nearest[city],
+            )
+    }
+
+    /// Every city still to be left needs an edge to a city still to be reached and the other way around,
+    /// the larger of the two sums of the cheapest such edges is the bound
+    fn lower_bound(&self) -> f64 {
+        if self.is_complete() {
+            return self.length;
+        }
+        let unvisited = self.extensions();
+        let first = self.path[0];
+        let last = self.path[self.path.len() - 1];
+
+        // Cities the remaining edges start from and end at
+        let mut sources = unvisited.clone();
+        sources.push(last);
+        let mut targets = unvisited;
+        targets.push(first);
+
+        let leaving: f64 = sources
+            .iter()
+            .map(|&from| {
+                targets
+                    .iter()
+                    .filter(|&&to| to != from)
+                    .map(|&to| self.instance.distance(from, to))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum();
+        let reaching: f64 = targets
+            .iter()
+            .map(|&to| {
+                sources
+                    .iter()
+                    .filter(|&&from| from != to)
+                    .map(|&from| self.instance.distance(from, to))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum();
+        self.length + leaving.max(reaching)
+    }
+
+    fn to_solution(&self) -> TspSolution {
+        TspSolution {
+            instance: self.instance.clone(),
+            perm: self.path.clone(),
+            cost: self.length,
+            last_swap: (0, 0),
+            last_rotation: 0,
+            last_cost: 0.0,
+            removed: Vec::new(),
+            combinator_state: CombinatorState::default(),
+        }
+    }
+
+    /// The edge from the last city of the path to the next city
+    fn extension_code(&self, &city: &Self::Extension) -> u64 {
+        let last = self.path[self.path.len() - 1];
+        (last * self.visited.len() + city) as u64
+    }
+}
+
+/// Reads a TSPLIB file with an explicit full distance matrix, as used for asymmetric instances.
+/// Header lines are "KEY : VALUE", the distances follow EDGE_WEIGHT_SECTION row by row and may be spread over any number of lines.
+/// Returns the number of cities and the distances.
+fn read_distance_matrix(contents: &str) -> (usize, Vec<f64>) {
+    let mut num_cities = 0;
+    let mut distances = Vec::new();
+    let mut in_weights = false;
+    for line in contents.lines().map(str::trim) {
+        if in_weights {
+            if line == "EOF" || line.ends_with("_SECTION") {
+                break;
+            }
+            distances.extend(line.split_whitespace().map(|w| w.parse::<f64>().unwrap()));
+        } else if line == "EDGE_WEIGHT_SECTION" {
+            in_weights = true;
+        } else if let Some((key, value)) = line.split_once(':') {
+            match key.trim() {
+                "DIMENSION" => num_cities = value.trim().parse::<usize>().unwrap(),
+                "EDGE_WEIGHT_TYPE" => {
+                    assert_eq!(
+                        value.trim(),
+                        "EXPLICIT",
+                        "Only explicit distances are supported"
+                    )
+                }
+                "EDGE_WEIGHT_FORMAT" => {
+                    assert_eq!(
+                        value.trim(),
+                        "FULL_MATRIX",
+                        "Only full distance matrices are supported"
+                    )
+                }
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(
+        distances.len(),
+        num_cities * num_cities,
+        "Expected {} distances for {} cities",
+        num_cities * num_cities,
+        num_cities
+    );
+    (num_cities, distances)
+}
+
+pub struct TspInstanceReader {}
+impl TspInstanceReader {
+    /// Creates the instance described by the contents of an input file, starting from a random tour
+    pub fn parse_instance(&self, contents: &str, instance_name: Option<&str>) -> TspSolution {
+        // TSP instance first reads the number of cities, a TSPLIB file starts with its name instead
+        let mut lines = contents.lines();
+        let (num_cities, points, distances) = match lines.next().unwrap().trim().parse::<usize>() {
+            Ok(num_cities) => {
+                // Then reads the coordinates of each city, where each line has its x and y as integers
+                let mut points = Vec::new();
+                for _ in 0..num_cities {
+                    let line = lines.next().unwrap();
+                    let mut coords = line.split_whitespace();
+                    let x = coords.next().unwrap().parse::<f64>().unwrap();
+                    let y = coords.next().unwrap().parse::<f64>().unwrap();
+                    points.push((x, y));
+                }
+                (num_cities, points, Vec::new())
+            }
+            Err(_) => {
+                let (num_cities, distances) = read_distance_matrix(contents);
+                (num_cities, Vec::new(), distances)
+            }
+        };
+        let symmetric = (0..num_cities).all(|a| {
+            (0..a).all(|b| {
+                distances.is_empty()
+                    || distances[a * num_cities + b] == distances[b * num_cities + a]
+            })
+        });
 
         // Initialize the solution with a random permutation of the cities
         let mut perm = (0..num_cities).collect::<Vec<usize>>();
@@ -166,14 +1064,148 @@ impl InstanceReader<TspSolution> for TspInstanceReader {
         let mut solution = TspSolution {
             instance: Arc::new(TspInstance {
                 dataset_name: instance_name.unwrap_or("unknown").to_string(),
+                num_cities,
                 points,
+                distances,
+                symmetric,
             }),
             perm,
             cost: 0.0, // will get overriden by recompute_cost_from_scratch
             last_swap: (0, 0),
+            last_rotation: 0,
             last_cost: 0.0,
+            removed: Vec::new(),
+            combinator_state: CombinatorState::default(),
         };
         solution.recompute_cost_from_scratch();
         solution
     }
 }
+
+impl InstanceReader<TspSolution> for TspInstanceReader {
+    fn read_instance(&self, file_path: &str, instance_name: Option<&str>) -> TspSolution {
+        let contents = std::fs::read_to_string(file_path).expect("Could not read file");
+        self.parse_instance(&contents, instance_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use crate::heuristics::constructive_search::{beam_search, pilot_method};
+
+    use super::*;
+
+    /// Instance with the cities on the diagonal, the optimal tour goes there and back
+    fn diagonal_instance(num_cities: usize) -> TspSolution {
+        let points: String = (0..num_cities).map(|i| format!("{} {}\n", i, i)).collect();
+        TspInstanceReader {}.parse_instance(&format!("{}\n{}", num_cities, points), None)
+    }
+
+    #[test]
+    fn constructive_search_handles_tiny_instances() {
+        for num_cities in 0..4 {
+            let solution = diagonal_instance(num_cities);
+            let optimum = 2.0 * 2.0_f64.sqrt() * num_cities.saturating_sub(1) as f64;
+            let stop_signal = Arc::new(AtomicBool::new(false));
+            let mut beam =
+                beam_search::<TspPartialTour, _>(&solution, 3, 2, "test", stop_signal.clone());
+            let mut pilot = pilot_method::<TspPartialTour, _>(&solution, 3, 2, "test", stop_signal);
+            assert_eq!(beam.perm.len(), num_cities);
+            assert_eq!(pilot.perm.len(), num_cities);
+            assert!((beam.get_cost() - optimum).abs() < 1e-9);
+            assert!((pilot.get_cost() - optimum).abs() < 1e-9);
+        }
+    }
+
+    /// Checks the change in cost of every move against the cost recomputed after applying it
+    fn check_move_deltas<G: MoveGenerator<TspSolution>>(solution: &TspSolution) {
+        let _ = G::for_each_move(solution, 0, |m| {
+            let mut moved = solution.clone();
+            G::apply_move(&mut moved, &m);
+            let incremental = moved.get_cost();
+            let recomputed = moved.recompute_cost_from_scratch();
+            assert!((incremental - recomputed).abs() < 1e-6);
+            ControlFlow::Continue(())
+        });
+    }
+
+    /// All moves from the given start, sorted to compare neighbourhoods
+    fn sorted_moves<G: MoveGenerator<TspSolution, Move = (usize, usize)>>(
+        solution: &TspSolution,
+        start: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut moves = Vec::new();
+        let _ = G::for_each_move(solution, start, |m| {
+            moves.push(m);
+            ControlFlow::Continue(())
+        });
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn every_start_generates_the_same_moves() {
+        let solution = diagonal_instance(7);
+        for start in [1, 6, 7, 100] {
+            assert_eq!(
+                sorted_moves::<Tsp2OptMove>(&solution, 0),
+                sorted_moves::<Tsp2OptMove>(&solution, start)
+            );
+            assert_eq!(
+                sorted_moves::<TspNaiveMove>(&solution, 0),
+                sorted_moves::<TspNaiveMove>(&solution, start)
+            );
+        }
+    }
+
+    #[test]
+    fn random_segments_contain_a_city() {
+        for n in 1..6 {
+            for _ in 0..100 {
+                let (i, j) = random_segment(n);
+                assert!(i < j && j <= n);
+            }
+        }
+    }
+
+    /// Checks that the child of two random tours visits every city once, with its cost up to date
+    fn check_crossover<C: Crossover<TspSolution>>() {
+        for num_cities in 1..8 {
+            let parent_a = diagonal_instance(num_cities);
+            let parent_b = diagonal_instance(num_cities);
+            let mut child = C::crossover(&parent_a, &parent_b);
+            let mut cities = child.perm.clone();
+            cities.sort();
+            assert_eq!(cities, (0..num_cities).collect::<Vec<_>>());
+            let cost = child.get_cost();
+            assert!((cost - child.recompute_cost_from_scratch()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn crossovers_produce_tours() {
+        check_crossover::<TspOrderCrossover>();
+        check_crossover::<TspPartiallyMappedCrossover>();
+        check_crossover::<TspCycleCrossover>();
+        check_crossover::<TspEdgeRecombinationCrossover>();
+    }
+
+    #[test]
+    fn asymmetric_move_deltas_match_recomputed_costs() {
+        let solution = TspInstanceReader {}.read_instance("input/atsp_test.atsp", None);
+        assert!(!solution.is_symmetric());
+        check_move_deltas::<Tsp2OptMove>(&solution);
+        check_move_deltas::<TspNaiveMove>(&solution);
+
+        // Tours of 3 cities differ in length when the distances are asymmetric
+        let small = TspInstanceReader {}.parse_instance(
+            "NAME: small\nDIMENSION: 3\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: FULL_MATRIX\n\
+             EDGE_WEIGHT_SECTION\n0 1 10\n10 0 1\n1 10 0\nEOF",
+            None,
+        );
+        check_move_deltas::<Tsp2OptMove>(&small);
+        check_move_deltas::<TspNaiveMove>(&small);
+    }
+}
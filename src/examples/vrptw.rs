@@ -0,0 +1,1092 @@
+// Example for the Vehicle Routing Problem with Time Windows (VRPTW).
+// Like the CVRP, but every customer has a time window in which service has to start and takes some service time.
+// A vehicle arriving early waits until the window opens, arriving late is not allowed, and all vehicles have to be back at the depot before it closes.
+// The objective is hierarchical: first the number of vehicles, then the total distance.
+// Input is a Solomon or Homberger instance, with unrounded Euclidean distances and travel times. The depot is customer 0.
+// Output lists the customers of every route, followed by the number of vehicles and the distance.
+//
+// Moves describe the routes they create as segments of the current routes. Times along a route are kept as the start of service
+// and the latest start that keeps the rest of the route on time, so a move only walks the customers whose times change
+// and checks the first unchanged part of a route by its slack.
+// The move generators also generate infeasible moves, their change in cost is infinite so local searches never apply them.
+
+use crate::moves::{CombinatorSolution, CombinatorState};
+use crate::solution::{InstanceReader, LocalRandomMove, MoveGenerator, Solution};
+use rand::{rngs::ThreadRng, thread_rng, Rng};
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// Number of empty routes added to the starting solution, so moves can open new routes
+const EXTRA_ROUTES: usize = 2;
+
+/// Number of tries a random move gets to find a feasible move, it does nothing otherwise
+const RANDOM_MOVE_ATTEMPTS: u32 = 100;
+
+/// Tolerance when comparing times, so rounding errors do not make a schedule infeasible
+const TIME_PRECISION: f64 = 1e-9;
+
+pub struct VrptwInstance {
+    dataset_name: String,
+    /// Number of vehicles available
+    fleet_size: usize,
+    capacity: u32,
+    /// Demand, time window and service time of every customer, the depot is customer 0
+    demands: Vec<u32>,
+    ready_times: Vec<f64>,
+    due_dates: Vec<f64>,
+    service_times: Vec<f64>,
+    /// Distance, and travel time, between every pair of customers, row by row
+    distances: Vec<f64>,
+    /// Cost of a vehicle, larger than the distance of any solution so fewer vehicles always win
+    vehicle_cost: f64,
+}
+
+impl VrptwInstance {
+    #[inline(always)]
+    pub fn distance(&self, a: usize, b: usize) -> f64 {
+        self.distances[a * self.demands.len() + b]
+    }
+}
+
+/// Loads, distances and times along a route by position, position 0 being the depot at the start
+/// and the position after the last customer the depot at the end
+#[derive(Clone, Default)]
+struct RouteSchedule {
+    /// Demand up to every position, up to the last customer
+    prefix_loads: Vec<u32>,
+    /// Distance from the depot to every position
+    prefix_distances: Vec<f64>,
+    /// Start of service at every position, after waiting for the time window to open
+    starts: Vec<f64>,
+    /// Latest start of service at every position that keeps the rest of the route on time
+    latest_starts: Vec<f64>,
+}
+
+/// Customers of a route from position from up to and including position to, visited backwards if reversed.
+/// Empty when to is before from.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    route: usize,
+    from: usize,
+    to: usize,
+    reversed: bool,
+}
+
+impl Segment {
+    fn forward(route: usize, from: usize, to: usize) -> Self {
+        Segment {
+            route,
+            from,
+            to,
+            reversed: false,
+        }
+    }
+
+    fn backward(route: usize, from: usize, to: usize) -> Self {
+        Segment {
+            route,
+            from,
+            to,
+            reversed: true,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.to < self.from
+    }
+
+    /// Positions in the order they are visited
+    fn positions(&self) -> impl Iterator<Item = usize> {
+        let (from, to, reversed) = (self.from, self.to, self.reversed);
+        (0..(to + 1).saturating_sub(from)).map(move |k| if reversed { to - k } else { from + k })
+    }
+}
+
+#[derive(Clone)]
+pub struct VrptwSolution {
+    instance: Arc<VrptwInstance>,
+    /// Customers of every route in the order they are visited, without the depot at either end
+    routes: Vec<Vec<usize>>,
+    schedules: Vec<RouteSchedule>,
+    distance: f64,
+    /// Number of routes that visit at least one customer
+    vehicles: usize,
+    // Routes changed by the last random move as they were before it, and the objective before it
+    last_routes: Vec<(usize, Vec<usize>)>,
+    last_objective: (usize, f64),
+    combinator_state: CombinatorState,
+}
+
+impl VrptwSolution {
+    /// Customer at a position of a route, position 0 and the position after the last customer are the depot
+    #[inline(always)]
+    fn node(&self, route: usize, position: usize) -> usize {
+        if position == 0 || position > self.routes[route].len() {
+            0
+        } else {
+            self.routes[route][position - 1]
+        }
+    }
+
+    #[inline(always)]
+    fn distance(&self, a: usize, b: usize) -> f64 {
+        self.instance.distance(a, b)
+    }
+
+    /// Start of service at the next customer, when the previous one started service at the given time
+    #[inline(always)]
+    fn next_start(&self, previous: usize, start: f64, next: usize) -> f64 {
+        let arrival = start + self.instance.service_times[previous] + self.distance(previous, next);
+        arrival.max(self.instance.ready_times[next])
+    }
+
+    /// Recomputes the schedule of the route after it changed, forwards for the loads, distances and starts
+    /// and backwards for the latest starts
+    fn refresh_route(&mut self, route: usize) {
+        let length = self.routes[route].len();
+        let mut schedule = RouteSchedule {
+            prefix_loads: vec![0; length + 1],
+            prefix_distances: vec![0.0; length + 2],
+            starts: vec![self.instance.ready_times[0]; length + 2],
+            latest_starts: vec![self.instance.due_dates[0]; length + 2],
+        };
+        for position in 1..=length + 1 {
+            let (previous, node) = (self.node(route, position - 1), self.node(route, position));
+            if position <= length {
+                schedule.prefix_loads[position] =
+                    schedule.prefix_loads[position - 1] + self.instance.demands[node];
+            }
+            schedule.prefix_distances[position] =
+                schedule.prefix_distances[position - 1] + self.distance(previous, node);
+            schedule.starts[position] =
+                self.next_start(previous, schedule.starts[position - 1], node);
+        }
+        for position in (1..=length).rev() {
+            let (node, next) = (self.node(route, position), self.node(route, position + 1));
+            schedule.latest_starts[position] = self.instance.due_dates[node].min(
+                schedule.latest_starts[position + 1]
+                    - self.instance.service_times[node]
+                    - self.distance(node, next),
+            );
+        }
+        self.schedules[route] = schedule;
+    }
+
+    /// Distance of the route, from the depot back to the depot
+    fn route_length(&self, route: usize) -> f64 {
+        self.schedules[route].prefix_distances[self.routes[route].len() + 1]
+    }
+
+    pub fn recompute_cost_from_scratch(&mut self) -> f64 {
+        self.distance = (0..self.routes.len())
+            .map(|route| {
+                (0..=self.routes[route].len())
+                    .map(|position| {
+                        self.distance(self.node(route, position), self.node(route, position + 1))
+                    })
+                    .sum::<f64>()
+            })
+            .sum();
+        self.vehicles = self.routes.iter().filter(|route| !route.is_empty()).count();
+        self.get_cost()
+    }
+
+    /// Number of vehicles used and total distance, the objectives in order of importance
+    pub fn objective(&self) -> (usize, f64) {
+        (self.vehicles, self.distance)
+    }
+
+    /// Distance of the route the segments would form, None if it would break the capacity or a time window.
+    /// The first segment starting the route of the same route is taken as is, from its starts,
+    /// and the last segment ending a route is checked against its latest start, only the customers in between are walked.
+    fn route_distance(&self, segments: &[Segment]) -> Option<f64> {
+        let segments: Vec<&Segment> = segments.iter().filter(|s| !s.is_empty()).collect();
+        let load: u32 = segments
+            .iter()
+            .map(|s| {
+                let prefix_loads = &self.schedules[s.route].prefix_loads;
+                prefix_loads[s.to] - prefix_loads[s.from - 1]
+            })
+            .sum();
+        if load > self.instance.capacity {
+            return None;
+        }
+
+        let mut distance = 0.0;
+        let mut start = self.instance.ready_times[0];
+        let mut previous = 0;
+        for (k, segment) in segments.iter().enumerate() {
+            let schedule = &self.schedules[segment.route];
+            if k == 0 && !segment.reversed && segment.from == 1 {
+                distance = schedule.prefix_distances[segment.to];
+                start = schedule.starts[segment.to];
+                previous = self.node(segment.route, segment.to);
+                continue;
+            }
+
+            let first = self.node(segment.route, segment.positions().next().unwrap());
+            distance += self.distance(previous, first) + schedule.prefix_distances[segment.to]
+                - schedule.prefix_distances[segment.from];
+            if k == segments.len() - 1
+                && !segment.reversed
+                && segment.to == self.routes[segment.route].len()
+            {
+                let first_start = self.next_start(previous, start, first);
+                let length = self.routes[segment.route].len();
+                return (first_start <= schedule.latest_starts[segment.from] + TIME_PRECISION)
+                    .then(|| {
+                        distance + schedule.prefix_distances[length + 1]
+                            - schedule.prefix_distances[length]
+                    });
+            }
+
+            for position in segment.positions() {
+                let node = self.node(segment.route, position);
+                start = self.next_start(previous, start, node);
+                if start > self.instance.due_dates[node] + TIME_PRECISION {
+                    return None;
+                }
+                previous = node;
+            }
+        }
+
+        // Back at the depot in time
+        let end = self.next_start(previous, start, 0);
+        (end <= self.instance.due_dates[0] + TIME_PRECISION)
+            .then(|| distance + self.distance(previous, 0))
+    }
+
+    /// Applies the move, the routes it creates are built from the current routes before any is replaced
+    fn apply(&mut self, m: &VrptwMove) {
+        let new_routes: Vec<(usize, Vec<usize>)> = m
+            .new_routes(self)
+            .into_iter()
+            .map(|(route, segments)| {
+                let customers = segments
+                    .iter()
+                    .filter(|s| !s.is_empty())
+                    .flat_map(|s| s.positions().map(|position| self.node(s.route, position)))
+                    .collect();
+                (route, customers)
+            })
+            .collect();
+        for (route, customers) in new_routes {
+            let (old_length, was_used) = (self.route_length(route), !self.routes[route].is_empty());
+            self.routes[route] = customers;
+            self.refresh_route(route);
+            self.distance += self.route_length(route) - old_length;
+            self.vehicles =
+                self.vehicles + usize::from(!self.routes[route].is_empty()) - usize::from(was_used);
+        }
+    }
+
+    /// Tries random moves of the neighbourhood until a feasible one is found and applies it, so it can be undone
+    fn do_random_move(&mut self, random_move: impl Fn(&Self, &mut ThreadRng) -> Option<VrptwMove>) {
+        let mut rng = thread_rng();
+        self.last_routes.clear();
+        self.last_objective = self.objective();
+        for _ in 0..RANDOM_MOVE_ATTEMPTS {
+            if let Some(m) = random_move(self, &mut rng) {
+                if m.evaluate(self).is_some() {
+                    let (a, b) = m.routes();
+                    self.last_routes.push((a, self.routes[a].clone()));
+                    if b != a {
+                        self.last_routes.push((b, self.routes[b].clone()));
+                    }
+                    self.apply(&m);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Undoes the last random move, if it did anything
+    fn undo(&mut self) {
+        for (route, customers) in std::mem::take(&mut self.last_routes) {
+            self.routes[route] = customers;
+            self.refresh_route(route);
+        }
+        (self.vehicles, self.distance) = self.last_objective;
+    }
+
+    /// Random route with at least the given number of customers
+    fn random_route(&self, min_length: usize, rng: &mut ThreadRng) -> Option<usize> {
+        let route = rng.gen_range(0..self.routes.len());
+        (self.routes[route].len() >= min_length).then_some(route)
+    }
+
+    /// Checks the solution from scratch, returns a description of every violated constraint, empty if it is feasible
+    pub fn feasibility_report(&self) -> Vec<String> {
+        let instance = &self.instance;
+        let mut violations = Vec::new();
+        if self.vehicles > instance.fleet_size {
+            violations.push(format!(
+                "Uses {} vehicles, only {} available",
+                self.vehicles, instance.fleet_size
+            ));
+        }
+
+        let mut visits = vec![0; instance.demands.len()];
+        for (number, route) in self.routes.iter().filter(|r| !r.is_empty()).enumerate() {
+            let load: u32 = route.iter().map(|&c| instance.demands[c]).sum();
+            if load > instance.capacity {
+                violations.push(format!(
+                    "Route {} carries {}, capacity is {}",
+                    number + 1,
+                    load,
+                    instance.capacity
+                ));
+            }
+            let mut start = instance.ready_times[0];
+            let mut previous = 0;
+            for &customer in route.iter().chain(std::iter::once(&0)) {
+                start = self.next_start(previous, start, customer);
+                if start > instance.due_dates[customer] + TIME_PRECISION {
+                    violations.push(format!(
+                        "Route {} reaches customer {} at {:.2}, due at {:.2}",
+                        number + 1,
+                        customer,
+                        start,
+                        instance.due_dates[customer]
+                    ));
+                }
+                visits[customer] += 1;
+                previous = customer;
+            }
+        }
+        for (customer, &count) in visits.iter().enumerate().skip(1) {
+            if count != 1 {
+                violations.push(format!("Customer {} is visited {} times", customer, count));
+            }
+        }
+        violations
+    }
+}
+
+impl Solution for VrptwSolution {
+    fn get_cost(&mut self) -> f64 {
+        self.vehicles as f64 * self.instance.vehicle_cost + self.distance
+    }
+
+    fn write_solution(&self, file_location: &str) {
+        // File name will be dataset name + vehicles + distance + .sol
+        let file_path = format!(
+            "{}/{}-{}-{:.2}.sol",
+            file_location, self.instance.dataset_name, self.vehicles, self.distance
+        );
+
+        let mut file =
+            std::fs::File::create(file_path.clone()).expect("Could not save solution to file!!!");
+        for (number, route) in self.routes.iter().filter(|r| !r.is_empty()).enumerate() {
+            let customers: Vec<String> = route.iter().map(|c| c.to_string()).collect();
+            writeln!(file, "Route {} : {}", number + 1, customers.join(" "))
+                .expect("Could not write to file");
+        }
+        writeln!(file, "Vehicles {}", self.vehicles).expect("Could not write to file");
+        writeln!(file, "Distance {:.2}", self.distance).expect("Could not write to file");
+
+        println!(
+            "Solution with {} vehicles and distance {:.2} written to {}",
+            self.vehicles, self.distance, file_path
+        );
+        let violations = self.feasibility_report();
+        if violations.is_empty() {
+            println!("Solution is feasible");
+        }
+        for violation in violations {
+            println!("Infeasible: {}", violation);
+        }
+    }
+
+    /// Vehicles above the fleet size, the other constraints are kept by the moves
+    fn constraint_violation(&mut self) -> f64 {
+        self.vehicles.saturating_sub(self.instance.fleet_size) as f64
+    }
+}
+
+impl CombinatorSolution for VrptwSolution {
+    fn combinator_state(&mut self) -> &mut CombinatorState {
+        &mut self.combinator_state
+    }
+}
+
+/// Move of one of the VRPTW neighbourhoods. Positions are within a route, the first customer being at position 1.
+#[derive(Clone, Copy, Debug)]
+pub enum VrptwMove {
+    /// Moves the customer to the given position of the other route, or of the same route after taking it out
+    Relocate {
+        from_route: usize,
+        from: usize,
+        to_route: usize,
+        to: usize,
+    },
+    /// Exchanges two customers, if in the same route a comes before b
+    Swap {
+        route_a: usize,
+        a: usize,
+        route_b: usize,
+        b: usize,
+    },
+    /// Reverses the customers from position from up to and including position to within a route
+    TwoOpt {
+        route: usize,
+        from: usize,
+        to: usize,
+    },
+    /// Cuts two routes after the given positions and exchanges their ends
+    TwoOptStar {
+        route_a: usize,
+        a: usize,
+        route_b: usize,
+        b: usize,
+    },
+}
+
+impl VrptwMove {
+    /// Routes the move changes, the same route twice for moves within a route
+    fn routes(&self) -> (usize, usize) {
+        match *self {
+            VrptwMove::Relocate {
+                from_route,
+                to_route,
+                ..
+            } => (from_route, to_route),
+            VrptwMove::TwoOpt { route, .. } => (route, route),
+            VrptwMove::Swap {
+                route_a, route_b, ..
+            }
+            | VrptwMove::TwoOptStar {
+                route_a, route_b, ..
+            } => (route_a, route_b),
+        }
+    }
+
+    /// Every route the move changes, as the segments of the current routes it will consist of
+    fn new_routes(&self, s: &VrptwSolution) -> Vec<(usize, Vec<Segment>)> {
+        let length = |route: usize| s.routes[route].len();
+        let forward = Segment::forward;
+        match *self {
+            VrptwMove::Relocate {
+                from_route,
+                from,
+                to_route,
+                to,
+            } if from_route == to_route => {
+                let route = from_route;
+                let segments = if to < from {
+                    vec![
+                        forward(route, 1, to - 1),
+                        forward(route, from, from),
+                        forward(route, to, from - 1),
+                        forward(route, from + 1, length(route)),
+                    ]
+                } else {
+                    vec![
+                        forward(route, 1, from - 1),
+                        forward(route, from + 1, to),
+                        forward(route, from, from),
+                        forward(route, to + 1, length(route)),
+                    ]
+                };
+                vec![(route, segments)]
+            }
+            VrptwMove::Relocate {
+                from_route,
+                from,
+                to_route,
+                to,
+            } => vec![
+                (
+                    from_route,
+                    vec![
+                        forward(from_route, 1, from - 1),
+                        forward(from_route, from + 1, length(from_route)),
+                    ],
+                ),
+                (
+                    to_route,
+                    vec![
+                        forward(to_route, 1, to - 1),
+                        forward(from_route, from, from),
+                        forward(to_route, to, length(to_route)),
+                    ],
+                ),
+            ],
+            VrptwMove::Swap {
+                route_a,
+                a,
+                route_b,
+                b,
+            } if route_a == route_b => vec![(
+                route_a,
+                vec![
+                    forward(route_a, 1, a - 1),
+                    forward(route_a, b, b),
+                    forward(route_a, a + 1, b - 1),
+                    forward(route_a, a, a),
+                    forward(route_a, b + 1, length(route_a)),
+                ],
+            )],
+            VrptwMove::Swap {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => vec![
+                (
+                    route_a,
+                    vec![
+                        forward(route_a, 1, a - 1),
+                        forward(route_b, b, b),
+                        forward(route_a, a + 1, length(route_a)),
+                    ],
+                ),
+                (
+                    route_b,
+                    vec![
+                        forward(route_b, 1, b - 1),
+                        forward(route_a, a, a),
+                        forward(route_b, b + 1, length(route_b)),
+                    ],
+                ),
+            ],
+            VrptwMove::TwoOpt { route, from, to } => vec![(
+                route,
+                vec![
+                    forward(route, 1, from - 1),
+                    Segment::backward(route, from, to),
+                    forward(route, to + 1, length(route)),
+                ],
+            )],
+            VrptwMove::TwoOptStar {
+                route_a,
+                a,
+                route_b,
+                b,
+            } => vec![
+                (
+                    route_a,
+                    vec![
+                        forward(route_a, 1, a),
+                        forward(route_b, b + 1, length(route_b)),
+                    ],
+                ),
+                (
+                    route_b,
+                    vec![
+                        forward(route_b, 1, b),
+                        forward(route_a, a + 1, length(route_a)),
+                    ],
+                ),
+            ],
+        }
+    }
+
+    /// Change in cost, None if the move breaks the capacity or a time window
+    fn evaluate(&self, s: &VrptwSolution) -> Option<f64> {
+        let mut delta = 0.0;
+        for (route, segments) in self.new_routes(s) {
+            let new_distance = s.route_distance(&segments)?;
+            let vehicle_change = match (
+                !s.routes[route].is_empty(),
+                segments.iter().any(|segment| !segment.is_empty()),
+            ) {
+                (false, true) => 1.0,
+                (true, false) => -1.0,
+                _ => 0.0,
+            };
+            delta +=
+                new_distance - s.route_length(route) + vehicle_change * s.instance.vehicle_cost;
+        }
+        Some(delta)
+    }
+}
+
+/// Moves a customer to another position, in the same or another route
+pub struct VrptwRelocate;
+impl VrptwRelocate {
+    fn random(s: &VrptwSolution, rng: &mut ThreadRng) -> Option<VrptwMove> {
+        let from_route = s.random_route(1, rng)?;
+        let to_route = s.random_route(0, rng)?;
+        let from = rng.gen_range(1..=s.routes[from_route].len());
+        let target_length = s.routes[to_route].len() - usize::from(from_route == to_route);
+        let to = rng.gen_range(1..=target_length + 1);
+        (from_route != to_route || from != to).then_some(VrptwMove::Relocate {
+            from_route,
+            from,
+            to_route,
+            to,
+        })
+    }
+}
+
+impl LocalRandomMove<VrptwSolution> for VrptwRelocate {
+    fn do_random_move(solution: &mut VrptwSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut VrptwSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<VrptwSolution> for VrptwRelocate {
+    type Move = VrptwMove;
+
+    fn for_each_move(
+        solution: &VrptwSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let from_route = (start + k) % num_routes;
+            for from in 1..=solution.routes[from_route].len() {
+                // Moving to an empty route only once, they are all the same
+                let mut tried_empty = false;
+                for to_route in 0..solution.routes.len() {
+                    let target_length = solution.routes[to_route].len();
+                    if target_length == 0 && std::mem::replace(&mut tried_empty, true) {
+                        continue;
+                    }
+                    let target_length = target_length - usize::from(from_route == to_route);
+                    for to in 1..=target_length + 1 {
+                        if from_route != to_route || from != to {
+                            visit(VrptwMove::Relocate {
+                                from_route,
+                                from,
+                                to_route,
+                                to,
+                            })?;
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &VrptwSolution, m: &Self::Move) -> f64 {
+        m.evaluate(solution).unwrap_or(f64::INFINITY)
+    }
+
+    fn apply_move(solution: &mut VrptwSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Exchanges two customers, in the same or different routes
+pub struct VrptwSwap;
+impl VrptwSwap {
+    fn random(s: &VrptwSolution, rng: &mut ThreadRng) -> Option<VrptwMove> {
+        let route_a = s.random_route(1, rng)?;
+        let route_b = s.random_route(1, rng)?;
+        let a = rng.gen_range(1..=s.routes[route_a].len());
+        let b = rng.gen_range(1..=s.routes[route_b].len());
+        if route_a == route_b && a == b {
+            return None;
+        }
+        let ((route_a, a), (route_b, b)) = if route_a == route_b && b < a {
+            ((route_b, b), (route_a, a))
+        } else {
+            ((route_a, a), (route_b, b))
+        };
+        Some(VrptwMove::Swap {
+            route_a,
+            a,
+            route_b,
+            b,
+        })
+    }
+}
+
+impl LocalRandomMove<VrptwSolution> for VrptwSwap {
+    fn do_random_move(solution: &mut VrptwSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut VrptwSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<VrptwSolution> for VrptwSwap {
+    type Move = VrptwMove;
+
+    fn for_each_move(
+        solution: &VrptwSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let route_a = (start + k) % num_routes;
+            for a in 1..=solution.routes[route_a].len() {
+                for route_b in route_a..solution.routes.len() {
+                    let first = if route_a == route_b { a + 1 } else { 1 };
+                    for b in first..=solution.routes[route_b].len() {
+                        visit(VrptwMove::Swap {
+                            route_a,
+                            a,
+                            route_b,
+                            b,
+                        })?;
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &VrptwSolution, m: &Self::Move) -> f64 {
+        m.evaluate(solution).unwrap_or(f64::INFINITY)
+    }
+
+    fn apply_move(solution: &mut VrptwSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Reverses part of a route
+pub struct VrptwTwoOpt;
+impl VrptwTwoOpt {
+    fn random(s: &VrptwSolution, rng: &mut ThreadRng) -> Option<VrptwMove> {
+        let route = s.random_route(2, rng)?;
+        let from = rng.gen_range(1..s.routes[route].len());
+        let to = rng.gen_range(from + 1..=s.routes[route].len());
+        Some(VrptwMove::TwoOpt { route, from, to })
+    }
+}
+
+impl LocalRandomMove<VrptwSolution> for VrptwTwoOpt {
+    fn do_random_move(solution: &mut VrptwSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut VrptwSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<VrptwSolution> for VrptwTwoOpt {
+    type Move = VrptwMove;
+
+    fn for_each_move(
+        solution: &VrptwSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let route = (start + k) % num_routes;
+            for from in 1..solution.routes[route].len() {
+                for to in from + 1..=solution.routes[route].len() {
+                    visit(VrptwMove::TwoOpt { route, from, to })?;
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &VrptwSolution, m: &Self::Move) -> f64 {
+        m.evaluate(solution).unwrap_or(f64::INFINITY)
+    }
+
+    fn apply_move(solution: &mut VrptwSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+/// Exchanges the ends of two routes
+pub struct VrptwTwoOptStar;
+impl VrptwTwoOptStar {
+    fn random(s: &VrptwSolution, rng: &mut ThreadRng) -> Option<VrptwMove> {
+        let route_a = s.random_route(0, rng)?;
+        let route_b = s.random_route(0, rng)?;
+        let a = rng.gen_range(0..=s.routes[route_a].len());
+        let b = rng.gen_range(0..=s.routes[route_b].len());
+        Self::is_useful(s, route_a, a, route_b, b).then_some(VrptwMove::TwoOptStar {
+            route_a,
+            a,
+            route_b,
+            b,
+        })
+    }
+
+    /// Whether the exchange changes anything, i.e. not within a route and not exchanging whole routes or nothing
+    fn is_useful(s: &VrptwSolution, route_a: usize, a: usize, route_b: usize, b: usize) -> bool {
+        route_a != route_b
+            && !(a == 0 && b == 0)
+            && !(a == s.routes[route_a].len() && b == s.routes[route_b].len())
+    }
+}
+
+impl LocalRandomMove<VrptwSolution> for VrptwTwoOptStar {
+    fn do_random_move(solution: &mut VrptwSolution) {
+        solution.do_random_move(Self::random);
+    }
+
+    fn undo_last_move(solution: &mut VrptwSolution) {
+        solution.undo();
+    }
+}
+
+impl MoveGenerator<VrptwSolution> for VrptwTwoOptStar {
+    type Move = VrptwMove;
+
+    fn for_each_move(
+        solution: &VrptwSolution,
+        start: usize,
+        mut visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move> {
+        let num_routes = solution.routes.len();
+        for k in 0..num_routes {
+            let route_a = (start + k) % num_routes;
+            for route_b in route_a + 1..solution.routes.len() {
+                for a in 0..=solution.routes[route_a].len() {
+                    for b in 0..=solution.routes[route_b].len() {
+                        if Self::is_useful(solution, route_a, a, route_b, b) {
+                            visit(VrptwMove::TwoOptStar {
+                                route_a,
+                                a,
+                                route_b,
+                                b,
+                            })?;
+                        }
+                    }
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn evaluate_move(solution: &VrptwSolution, m: &Self::Move) -> f64 {
+        m.evaluate(solution).unwrap_or(f64::INFINITY)
+    }
+
+    fn apply_move(solution: &mut VrptwSolution, m: &Self::Move) {
+        solution.apply(m);
+    }
+}
+
+pub struct VrptwInstanceReader {}
+impl VrptwInstanceReader {
+    /// Whether the contents are an instance in Solomon format, which has a VEHICLE section followed by a CUSTOMER section
+    pub fn recognises(&self, contents: &str) -> bool {
+        let mut sections = contents.lines().map(str::trim);
+        sections.any(|line| line == "VEHICLE") && sections.any(|line| line == "CUSTOMER")
+    }
+}
+
+impl InstanceReader<VrptwSolution> for VrptwInstanceReader {
+    fn read_instance(&self, file_path: &str, instance_name: Option<&str>) -> VrptwSolution {
+        // The vehicle line has the number of vehicles and the capacity, every customer line has
+        // number, x, y, demand, ready time, due date and service time. Other lines are headers.
+        let contents = std::fs::read_to_string(file_path).expect("Could not read file");
+        let mut fleet_size = 0;
+        let mut capacity = 0;
+        let mut customers: Vec<Vec<f64>> = Vec::new();
+        for line in contents.lines() {
+            let values: Vec<f64> = match line.split_whitespace().map(str::parse).collect() {
+                Ok(values) => values,
+                Err(_) => continue,
+            };
+            match values.len() {
+                2 => (fleet_size, capacity) = (values[0] as usize, values[1] as u32),
+                7 => customers.push(values),
+                _ => {}
+            }
+        }
+        assert_eq!(customers[0][0], 0.0, "The depot should be customer 0");
+
+        // Euclidean distances, not rounded
+        let n = customers.len();
+        let mut distances = vec![0.0; n * n];
+        for a in 0..n {
+            for b in 0..n {
+                let (dx, dy) = (
+                    customers[a][1] - customers[b][1],
+                    customers[a][2] - customers[b][2],
+                );
+                distances[a * n + b] = (dx * dx + dy * dy).sqrt();
+            }
+        }
+        // A solution has at most twice as many edges as customers, so a vehicle costs more than any distance
+        let longest_distance = distances.iter().cloned().fold(0.0, f64::max);
+        let instance = VrptwInstance {
+            dataset_name: instance_name.unwrap_or("unknown").to_string(),
+            fleet_size,
+            capacity,
+            demands: customers.iter().map(|c| c[3] as u32).collect(),
+            ready_times: customers.iter().map(|c| c[4]).collect(),
+            due_dates: customers.iter().map(|c| c[5]).collect(),
+            service_times: customers.iter().map(|c| c[6]).collect(),
+            distances,
+            vehicle_cost: (2.0 * n as f64 * longest_distance).max(1.0),
+        };
+
+        // Initialize the solution by customers in order of due date, each appended to the route where it adds the least distance.
+        // A new route is opened when it fits in none of them.
+        let mut order: Vec<usize> = (1..n).collect();
+        order.sort_by(|&a, &b| instance.due_dates[a].total_cmp(&instance.due_dates[b]));
+        let mut routes: Vec<Vec<usize>> = Vec::new();
+        // Last customer, its start of service and the load of every route
+        let mut ends: Vec<(usize, f64, u32)> = Vec::new();
+        let next_start = |previous: usize, start: f64, next: usize| {
+            (start + instance.service_times[previous] + instance.distance(previous, next))
+                .max(instance.ready_times[next])
+        };
+        for customer in order {
+            let fits = |&(last, start, load): &(usize, f64, u32)| {
+                let customer_start = next_start(last, start, customer);
+                load + instance.demands[customer] <= instance.capacity
+                    && customer_start <= instance.due_dates[customer]
+                    && next_start(customer, customer_start, 0) <= instance.due_dates[0]
+            };
+            let best = (0..ends.len())
+                .filter(|&r| fits(&ends[r]))
+                .min_by(|&a, &b| {
+                    let added = |r: usize| instance.distance(ends[r].0, customer);
+                    added(a).total_cmp(&added(b))
+                });
+            let depot = (0, instance.ready_times[0], 0);
+            assert!(
+                fits(&depot),
+                "Customer {} can not be served in time",
+                customer
+            );
+            let route = best.unwrap_or_else(|| {
+                routes.push(Vec::new());
+                ends.push(depot);
+                routes.len() - 1
+            });
+            let (last, start, load) = ends[route];
+            ends[route] = (
+                customer,
+                next_start(last, start, customer),
+                load + instance.demands[customer],
+            );
+            routes[route].push(customer);
+        }
+        routes.extend((0..EXTRA_ROUTES).map(|_| Vec::new()));
+
+        let num_routes = routes.len();
+        let mut solution = VrptwSolution {
+            instance: Arc::new(instance),
+            routes,
+            schedules: vec![RouteSchedule::default(); num_routes],
+            distance: 0.0, // will get overriden by recompute_cost_from_scratch
+            vehicles: 0,
+            last_routes: Vec::new(),
+            last_objective: (0, 0.0),
+            combinator_state: CombinatorState::default(),
+        };
+        for route in 0..num_routes {
+            solution.refresh_route(route);
+        }
+        solution.recompute_cost_from_scratch();
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use crate::heuristics::hill_climbing::{hill_climbing_step, HillClimbingStrategy};
+
+    use super::*;
+
+    #[test]
+    fn recognises_solomon_instances_only() {
+        let reader = VrptwInstanceReader {};
+        let solomon = std::fs::read_to_string("input/vrptw_test.txt").unwrap();
+        let tsp = std::fs::read_to_string("input/tsp_test.in").unwrap();
+        assert!(reader.recognises(&solomon));
+        assert!(!reader.recognises(&tsp));
+    }
+
+    /// Applies every move the generator produces to a copy of the solution. Finite changes in cost must match
+    /// the recomputed cost and keep the solution feasible, infinite ones must break a constraint.
+    /// Returns the number of feasible and infeasible moves.
+    fn check_moves<G: MoveGenerator<VrptwSolution>>(solution: &mut VrptwSolution) -> (u32, u32) {
+        let cost = solution.get_cost();
+        let (mut feasible, mut infeasible) = (0, 0);
+        let _ = G::for_each_move(solution, 0, |m| {
+            let delta = G::evaluate_move(solution, &m);
+            let mut moved = solution.clone();
+            G::apply_move(&mut moved, &m);
+            if delta.is_finite() {
+                let recomputed = moved.clone().recompute_cost_from_scratch();
+                assert!((cost + delta - recomputed).abs() < 1e-6);
+                assert!((moved.get_cost() - recomputed).abs() < 1e-6);
+                assert!(moved.feasibility_report().is_empty());
+                feasible += 1;
+            } else {
+                assert_eq!(delta, f64::INFINITY);
+                assert!(!moved.feasibility_report().is_empty());
+                infeasible += 1;
+            }
+            ControlFlow::Continue(())
+        });
+        (feasible, infeasible)
+    }
+
+    #[test]
+    fn moves_are_evaluated_exactly_and_infeasible_ones_are_infinite() {
+        let mut solution = VrptwInstanceReader {}.read_instance("input/vrptw_test.txt", None);
+        assert!(solution.feasibility_report().is_empty());
+        for (feasible, infeasible) in [
+            check_moves::<VrptwSwap>(&mut solution),
+            check_moves::<VrptwTwoOpt>(&mut solution),
+            check_moves::<VrptwTwoOptStar>(&mut solution),
+        ] {
+            assert!(feasible > 0 && infeasible > 0);
+        }
+
+        // Customer 14 is due at 41, so it can not follow a customer that is only ready later
+        let (route_b, b) = (0..solution.routes.len())
+            .find_map(|route| {
+                let position = solution.routes[route].iter().position(|&c| c == 14)?;
+                Some((route, position))
+            })
+            .unwrap();
+        let (route_a, a) = (0..solution.routes.len())
+            .filter(|&route| route != route_b)
+            .find_map(|route| {
+                let position = solution.routes[route]
+                    .iter()
+                    .position(|&c| solution.instance.ready_times[c] > 41.0)?;
+                Some((route, position + 1))
+            })
+            .unwrap();
+        let m = VrptwMove::TwoOptStar {
+            route_a,
+            a,
+            route_b,
+            b,
+        };
+        assert_eq!(VrptwTwoOptStar::evaluate_move(&solution, &m), f64::INFINITY);
+        VrptwTwoOptStar::apply_move(&mut solution, &m);
+        assert!(!solution.feasibility_report().is_empty());
+    }
+
+    #[test]
+    fn local_search_only_applies_feasible_moves() {
+        let mut solution = VrptwInstanceReader {}.read_instance("input/vrptw_test.txt", None);
+        let mut small_rng = SmallRng::seed_from_u64(0);
+        let mut previous_cost = solution.get_cost();
+        for strategy in [
+            HillClimbingStrategy::FirstImprovement,
+            HillClimbingStrategy::BestImprovement,
+        ] {
+            while hill_climbing_step::<VrptwRelocate, _>(&mut solution, strategy, &mut small_rng) {
+                let cost = solution.get_cost();
+                assert!(cost < previous_cost);
+                assert!(solution.feasibility_report().is_empty());
+                previous_cost = cost;
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::{any::type_name, ops::ControlFlow};
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::{
+    heuristics::check_early_return,
+    solution::{DestroyOperator, RepairOperator, Solution},
+};
+
+use super::{
+    adaptive_weights::{AdaptiveWeights, SCORE_ACCEPTED, SCORE_IMPROVEMENT, SCORE_NEW_BEST},
+    simulated_annealing::{get_cooling_schedule, metropolis_rule, CoolingSchedule},
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 10_000;
+
+/// Number of iterations after which the weights are updated
+const SEGMENT_LENGTH: u32 = 100;
+
+/// A solution this much worse than the starting solution is accepted with probability one half at the start
+const STARTING_WORSENING_FRACTION: f64 = 0.05;
+
+/// Fraction of the starting temperature reached at the end of the run
+const ENDING_TEMPERATURE_FRACTION: f64 = 10e-4;
+
+/// List of destroy operators, implemented for tuples of destroy operators.
+pub trait DestroyOperators<T: Solution> {
+    /// Number of operators in the list
+    const COUNT: usize;
+
+    /// Applies the k-th destroy operator
+    fn destroy(k: usize, solution: &mut T, degree: usize);
+
+    /// Name of the k-th destroy operator
+    fn name(k: usize) -> &'static str;
+}
+
+/// List of repair operators, implemented for tuples of repair operators.
+pub trait RepairOperators<T: Solution> {
+    /// Number of operators in the list
+    const COUNT: usize;
+
+    /// Applies the k-th repair operator
+    fn repair(k: usize, solution: &mut T);
+
+    /// Name of the k-th repair operator
+    fn name(k: usize) -> &'static str;
+}
+
+macro_rules! impl_operators {
+    ($count:expr; $($index:tt => $name:ident),+) => {
+        impl<T: Solution, $($name: DestroyOperator<T>),+> DestroyOperators<T> for ($($name,)+) {
+            const COUNT: usize = $count;
+
+            fn destroy(k: usize, solution: &mut T, degree: usize) {
+                match k {
+                    $($index => $name::destroy(solution, degree),)+
+                    _ => panic!("Destroy operator {} does not exist", k),
+                }
+            }
+
+            fn name(k: usize) -> &'static str {
+                match k {
+                    $($index => type_name::<$name>(),)+
+                    _ => panic!("Destroy operator {} does not exist", k),
+                }
+            }
+        }
+
+        impl<T: Solution, $($name: RepairOperator<T>),+> RepairOperators<T> for ($($name,)+) {
+            const COUNT: usize = $count;
+
+            fn repair(k: usize, solution: &mut T) {
+                match k {
+                    $($index => $name::repair(solution),)+
+                    _ => panic!("Repair operator {} does not exist", k),
+                }
+            }
+
+            fn name(k: usize) -> &'static str {
+                match k {
+                    $($index => type_name::<$name>(),)+
+                    _ => panic!("Repair operator {} does not exist", k),
+                }
+            }
+        }
+    };
+}
+
+impl_operators!(1; 0 => A);
+impl_operators!(2; 0 => A, 1 => B);
+impl_operators!(3; 0 => A, 1 => B, 2 => C);
+impl_operators!(4; 0 => A, 1 => B, 2 => C, 3 => D);
+impl_operators!(5; 0 => A, 1 => B, 2 => C, 3 => D, 4 => E);
+
+/// Prints the weights of a list of operators next to their names
+fn report_weights(
+    weights: &AdaptiveWeights,
+    process_name: &str,
+    name: impl Fn(usize) -> &'static str,
+) {
+    for (k, (weight, probability)) in weights
+        .weights()
+        .iter()
+        .zip(weights.selection_probabilities())
+        .enumerate()
+    {
+        // Strip the module path, but not from generic parameters
+        let full_name = name(k);
+        let path_end = full_name.find('<').unwrap_or(full_name.len());
+        let short_name = match full_name[..path_end].rfind("::") {
+            Some(start) => &full_name[start + 2..],
+            None => full_name,
+        };
+        println!(
+            "{} - {}: weight {:.3}, selection probability {:.3}",
+            process_name, short_name, weight, probability
+        );
+    }
+}
+
+/// Adaptive large neighbourhood search, destroys part of the solution and repairs it again with operators picked by roulette wheel selection.
+/// The weights of the operators adapt to how well they perform, new solutions are accepted using the metropolis rule of simulated annealing.
+pub fn adaptive_large_neighbourhood_search<D, R, T>(
+    solution: &mut T,
+    num_iterations: u32,
+    max_destroy_degree: usize,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    D: DestroyOperators<T>,
+    R: RepairOperators<T>,
+    T: Solution,
+{
+    // Determine temperatures relative to the size of the cost of the starting solution
+    let starting_temperature =
+        -(STARTING_WORSENING_FRACTION * solution.get_cost().abs()) / 0.5_f64.ln();
+    let ending_temperature = starting_temperature * ENDING_TEMPERATURE_FRACTION;
+    let cooling_schedule = get_cooling_schedule(
+        CoolingSchedule::Exponential,
+        starting_temperature,
+        ending_temperature,
+        num_iterations,
+    );
+
+    // Setup early return
+    let mut early_return_counter = 0;
+    let mut last_status_check_cost = solution.get_cost();
+
+    // Print some info
+    println!(
+        "{} - Running adaptive large neighbourhood search for {} iterations with {} destroy and {} repair operators, starting temperature: {}",
+        process_name, num_iterations, D::COUNT, R::COUNT, starting_temperature
+    );
+
+    // Main loop
+    let mut destroy_weights = AdaptiveWeights::new(D::COUNT, SEGMENT_LENGTH);
+    let mut repair_weights = AdaptiveWeights::new(R::COUNT, SEGMENT_LENGTH);
+    let mut temperature = starting_temperature;
+    let mut small_rng = SmallRng::from_entropy();
+    let mut best_solution = solution.clone();
+    let max_destroy_degree = max_destroy_degree.max(1);
+    for it in 0..num_iterations {
+        // Destroy and repair a copy of the current solution
+        let d = destroy_weights.select(&mut small_rng);
+        let r = repair_weights.select(&mut small_rng);
+        let degree = small_rng.gen_range(1..=max_destroy_degree);
+        let mut candidate = solution.clone();
+        D::destroy(d, &mut candidate, degree);
+        R::repair(r, &mut candidate);
+
+        // Accept using the metropolis rule, always accept if it is better in the sense of the problem
+        let sense = solution.sense();
+        let cost_diff = sense.improvement(solution.get_cost(), candidate.get_cost());
+        let score = if sense.is_better(candidate.get_cost(), best_solution.get_cost()) {
+            best_solution = candidate.clone();
+            *solution = candidate;
+            SCORE_NEW_BEST
+        } else if cost_diff > 0.0 {
+            *solution = candidate;
+            SCORE_IMPROVEMENT
+        } else if small_rng.gen::<f64>() <= metropolis_rule(cost_diff, temperature) {
+            *solution = candidate;
+            SCORE_ACCEPTED
+        } else {
+            0.0
+        };
+        destroy_weights.add_score(d, score);
+        repair_weights.add_score(r, score);
+
+        // Update temperature
+        temperature = cooling_schedule(temperature);
+
+        // print cost every so often, check for early return
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            let percentage = (it as f64 / num_iterations as f64) * 100.0;
+            println!(
+                " {} - {:.0}% - Best cost: {:.4} Current cost: {:.4} Temp: {:.4} ",
+                process_name,
+                percentage,
+                best_solution.get_cost(),
+                solution.get_cost(),
+                temperature,
+            );
+
+            if let ControlFlow::Break(_) = check_early_return(
+                &stop_signal,
+                process_name,
+                solution,
+                &mut last_status_check_cost,
+                &mut early_return_counter,
+                it,
+                num_iterations,
+            ) {
+                break;
+            }
+        }
+    }
+
+    // Set the best solution
+    *solution = best_solution;
+
+    // Print final cost and operator weights
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    report_weights(&destroy_weights, process_name, D::name);
+    report_weights(&repair_weights, process_name, R::name);
+}
@@ -0,0 +1,96 @@
+use rand::Rng;
+
+/// Score of a choice that led to a new best solution
+pub const SCORE_NEW_BEST: f64 = 33.0;
+
+/// Score of a choice that improved the current solution
+pub const SCORE_IMPROVEMENT: f64 = 9.0;
+
+/// Score of a choice that did not improve but was accepted
+pub const SCORE_ACCEPTED: f64 = 13.0;
+
+/// How much the scores of the last segment count in the new weights, the rest is the old weight
+const REACTION_FACTOR: f64 = 0.1;
+
+/// Lower bound on the weights, so every choice keeps being tried now and then
+const MINIMUM_WEIGHT: f64 = 0.01;
+
+/// Selection weights of a list of choices, such as moves or operators, that adapt to how well each choice performs.
+/// Every segment the weights move towards the average score of the choice in that segment.
+#[derive(Clone)]
+pub struct AdaptiveWeights {
+    weights: Vec<f64>,
+    segment_scores: Vec<f64>,
+    segment_uses: Vec<u32>,
+    total_uses: Vec<u64>,
+    /// Number of scores after which the weights are updated
+    segment_length: u32,
+    scores_in_segment: u32,
+}
+
+impl AdaptiveWeights {
+    pub fn new(count: usize, segment_length: u32) -> Self {
+        AdaptiveWeights {
+            weights: vec![1.0; count],
+            segment_scores: vec![0.0; count],
+            segment_uses: vec![0; count],
+            total_uses: vec![0; count],
+            segment_length,
+            scores_in_segment: 0,
+        }
+    }
+
+    /// Roulette wheel selection over the weights
+    pub fn select<R: Rng>(&self, rng: &mut R) -> usize {
+        let total_weight: f64 = self.weights.iter().sum();
+        let mut remaining = rng.gen::<f64>() * total_weight;
+        for (k, weight) in self.weights.iter().enumerate() {
+            if remaining < *weight {
+                return k;
+            }
+            remaining -= weight;
+        }
+        self.weights.len() - 1
+    }
+
+    /// Adds the score of the k-th choice, 0 if it was rejected, and updates the weights at the end of the segment
+    pub fn add_score(&mut self, k: usize, score: f64) {
+        self.segment_scores[k] += score;
+        self.segment_uses[k] += 1;
+        self.total_uses[k] += 1;
+        self.scores_in_segment += 1;
+        if self.scores_in_segment >= self.segment_length {
+            self.end_segment();
+        }
+    }
+
+    /// Moves the weights towards the average scores of the segment, choices that were not used keep their weight
+    fn end_segment(&mut self) {
+        for k in 0..self.weights.len() {
+            if self.segment_uses[k] > 0 {
+                let average_score = self.segment_scores[k] / self.segment_uses[k] as f64;
+                self.weights[k] = ((1.0 - REACTION_FACTOR) * self.weights[k]
+                    + REACTION_FACTOR * average_score)
+                    .max(MINIMUM_WEIGHT);
+            }
+            self.segment_scores[k] = 0.0;
+            self.segment_uses[k] = 0;
+        }
+        self.scores_in_segment = 0;
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Probability of selecting every choice
+    pub fn selection_probabilities(&self) -> Vec<f64> {
+        let total_weight: f64 = self.weights.iter().sum();
+        self.weights.iter().map(|w| w / total_weight).collect()
+    }
+
+    /// Number of times every choice was scored
+    pub fn total_uses(&self) -> &[u64] {
+        &self.total_uses
+    }
+}
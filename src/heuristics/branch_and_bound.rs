@@ -0,0 +1,331 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering::Relaxed},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{OptimisationSense, PartialSolution, Solution};
+
+use super::{StopSignal, FLOAT_PRECISION};
+
+/// How often to report the status of the search, in nodes
+const REPORT_STATUS_EVERY_NODES: u64 = 1_000_000;
+
+/// Parameters of branch and bound
+#[derive(Clone, Copy, Debug)]
+pub struct BranchAndBoundParameters {
+    pub strategy: SearchStrategy,
+    pub num_threads: u32,
+    /// Maximum number of nodes to explore, 0 for no limit
+    pub node_limit: u64,
+    /// Maximum running time, zero for no limit
+    pub time_limit: Duration,
+}
+
+/// Open node of the search tree with the lower bound of its partial solution
+struct OpenNode<B> {
+    bound: f64,
+    partial: B,
+}
+
+impl<B> PartialEq for OpenNode<B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bound.total_cmp(&other.bound) == Ordering::Equal
+    }
+}
+
+impl<B> Eq for OpenNode<B> {}
+
+impl<B> PartialOrd for OpenNode<B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<B> Ord for OpenNode<B> {
+    /// Reversed, so the heap pops the node with the lowest bound first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.bound.total_cmp(&self.bound)
+    }
+}
+
+/// Open nodes of a single worker
+enum OpenNodes<B> {
+    /// Depth first, the worker takes the deepest node and thieves the shallowest one, which has the largest subtree
+    Stack(Vec<OpenNode<B>>),
+    /// Best first, both the worker and thieves take the node with the lowest bound
+    Heap(BinaryHeap<OpenNode<B>>),
+}
+
+impl<B> OpenNodes<B> {
+    fn new(strategy: SearchStrategy) -> Self {
+        match strategy {
+            SearchStrategy::DepthFirst => OpenNodes::Stack(Vec::new()),
+            SearchStrategy::BestFirst => OpenNodes::Heap(BinaryHeap::new()),
+        }
+    }
+
+    fn push(&mut self, node: OpenNode<B>) {
+        match self {
+            OpenNodes::Stack(stack) => stack.push(node),
+            OpenNodes::Heap(heap) => heap.push(node),
+        }
+    }
+
+    fn pop(&mut self) -> Option<OpenNode<B>> {
+        match self {
+            OpenNodes::Stack(stack) => stack.pop(),
+            OpenNodes::Heap(heap) => heap.pop(),
+        }
+    }
+
+    fn steal(&mut self) -> Option<OpenNode<B>> {
+        match self {
+            OpenNodes::Stack(stack) if !stack.is_empty() => Some(stack.remove(0)),
+            OpenNodes::Stack(_) => None,
+            OpenNodes::Heap(heap) => heap.pop(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            OpenNodes::Stack(stack) => stack.len(),
+            OpenNodes::Heap(heap) => heap.len(),
+        }
+    }
+
+    /// Lowest bound of the open nodes, infinity if there are none
+    fn lowest_bound(&self) -> f64 {
+        match self {
+            OpenNodes::Stack(stack) => stack
+                .iter()
+                .map(|node| node.bound)
+                .fold(f64::INFINITY, f64::min),
+            OpenNodes::Heap(heap) => heap.peek().map_or(f64::INFINITY, |node| node.bound),
+        }
+    }
+}
+
+/// State shared by all workers
+struct Search<B, T> {
+    queues: Vec<Mutex<OpenNodes<B>>>,
+    incumbent: Mutex<T>,
+    /// Cost of the incumbent as bits of a float, so it can be read without locking
+    incumbent_cost: AtomicU64,
+    /// Number of nodes that are open or being expanded, the search is done when it reaches zero
+    pending: AtomicUsize,
+    nodes: AtomicU64,
+}
+
+impl<B, T> Search<B, T>
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    fn incumbent_cost(&self) -> f64 {
+        f64::from_bits(self.incumbent_cost.load(Relaxed))
+    }
+
+    /// Takes a node from the own queue, or steals one from the other workers
+    fn take(&self, worker: usize) -> Option<OpenNode<B>> {
+        if let Some(node) = self.queues[worker].lock().unwrap().pop() {
+            return Some(node);
+        }
+        (1..self.queues.len())
+            .map(|offset| (worker + offset) % self.queues.len())
+            .find_map(|victim| self.queues[victim].lock().unwrap().steal())
+    }
+
+    /// Replaces the incumbent by the complete partial solution if it is better, i.e. lower as only minimisation is supported
+    fn update_incumbent(&self, partial: &B) {
+        let mut incumbent = self.incumbent.lock().unwrap();
+        if partial.score() < incumbent.get_cost() - FLOAT_PRECISION {
+            *incumbent = partial.to_solution();
+            self.incumbent_cost
+                .store(incumbent.get_cost().to_bits(), Relaxed);
+        }
+    }
+}
+
+/// Expands nodes until the search is done or a limit is reached
+fn worker<B, T>(
+    index: usize,
+    search: &Search<B, T>,
+    parameters: &BranchAndBoundParameters,
+    start: Instant,
+    process_name: &str,
+    stop_signal: &StopSignal,
+) where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    loop {
+        // Check for stop signal and limits
+        if stop_signal.load(Relaxed)
+            || (parameters.node_limit > 0 && search.nodes.load(Relaxed) >= parameters.node_limit)
+            || (!parameters.time_limit.is_zero() && start.elapsed() >= parameters.time_limit)
+        {
+            break;
+        }
+
+        let Some(node) = search.take(index) else {
+            if search.pending.load(Relaxed) == 0 {
+                break;
+            }
+            std::thread::yield_now();
+            continue;
+        };
+        let nodes = search.nodes.fetch_add(1, Relaxed) + 1;
+
+        // The incumbent may have improved since the node was created, bounds are lower bounds of a minimisation problem
+        if node.bound < search.incumbent_cost() - FLOAT_PRECISION {
+            if node.partial.is_complete() {
+                search.update_incumbent(&node.partial);
+            } else {
+                // Branch, children that cannot improve the incumbent are pruned right away
+                let mut children: Vec<OpenNode<B>> = node
+                    .partial
+                    .extensions()
+                    .iter()
+                    .filter_map(|extension| {
+                        let mut child = node.partial.clone();
+                        child.extend(extension);
+                        let bound = child.lower_bound();
+                        (bound < search.incumbent_cost() - FLOAT_PRECISION).then_some(OpenNode {
+                            bound,
+                            partial: child,
+                        })
+                    })
+                    .collect();
+
+                // Depth first explores the child with the lowest bound first
+                children.sort_unstable_by(|a, b| b.bound.total_cmp(&a.bound));
+                search.pending.fetch_add(children.len(), Relaxed);
+                let mut queue = search.queues[index].lock().unwrap();
+                for child in children {
+                    queue.push(child);
+                }
+            }
+        }
+        search.pending.fetch_sub(1, Relaxed);
+
+        // print status every so often
+        if nodes % REPORT_STATUS_EVERY_NODES == 0 {
+            println!(
+                " {} - Nodes {} - Incumbent: {:.4} Open nodes: {}",
+                process_name,
+                nodes,
+                search.incumbent_cost(),
+                search.pending.load(Relaxed),
+            );
+        }
+    }
+}
+
+/// Branch and bound over partial solutions, depth first or best first, with the lower bounds of the partial solutions.
+/// Starts from the given incumbent, e.g. the best solution of a heuristic, so only partial solutions that could beat it are explored.
+/// The workers have their own open nodes and steal from each other when out of work.
+/// Returns the best solution and the proven lower bound on the optimal cost, equal to its cost when the search finished.
+/// Pruning compares lower bounds against the incumbent, so only minimisation problems are supported.
+pub fn branch_and_bound<B, T>(
+    incumbent: T,
+    parameters: &BranchAndBoundParameters,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> (T, f64)
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    debug_assert_eq!(
+        incumbent.sense(),
+        OptimisationSense::Minimise,
+        "Branch and bound only minimises"
+    );
+    let mut incumbent = incumbent;
+    let root = B::empty(&incumbent);
+    let num_workers = parameters.num_threads.max(1) as usize;
+    let search = Search {
+        queues: (0..num_workers)
+            .map(|_| Mutex::new(OpenNodes::new(parameters.strategy)))
+            .collect(),
+        incumbent_cost: AtomicU64::new(incumbent.get_cost().to_bits()),
+        incumbent: Mutex::new(incumbent),
+        pending: AtomicUsize::new(1),
+        nodes: AtomicU64::new(0),
+    };
+    search.queues[0].lock().unwrap().push(OpenNode {
+        bound: root.lower_bound(),
+        partial: root,
+    });
+
+    // Print some info
+    println!(
+        "{} - Running {} branch and bound with {} threads, incumbent: {}",
+        process_name,
+        parameters.strategy,
+        num_workers,
+        search.incumbent_cost()
+    );
+
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for index in 0..num_workers {
+            let search = &search;
+            let stop_signal = &stop_signal;
+            scope.spawn(move || {
+                worker::<B, T>(index, search, parameters, start, process_name, stop_signal);
+            });
+        }
+    });
+
+    // Whatever is still open bounds the optimum from below
+    let open_nodes: usize = search
+        .queues
+        .iter()
+        .map(|queue| queue.lock().unwrap().len())
+        .sum();
+    let mut solution = search.incumbent.into_inner().unwrap();
+    let cost = solution.get_cost();
+    let lower_bound = search
+        .queues
+        .iter()
+        .map(|queue| queue.lock().unwrap().lowest_bound())
+        .fold(cost, f64::min);
+
+    // Print final cost and gap
+    println!(
+        "{} - Explored {} nodes in {:.3}s",
+        process_name,
+        search.nodes.load(Relaxed),
+        start.elapsed().as_secs_f64()
+    );
+    println!("{} - Final cost: {}", process_name, cost);
+    if open_nodes == 0 {
+        println!("{} - Proven optimal", process_name);
+    } else {
+        println!(
+            "{} - Stopped with {} open nodes, lower bound: {:.4}, gap: {:.4}%",
+            process_name,
+            open_nodes,
+            lower_bound,
+            100.0 * (cost - lower_bound) / cost.abs().max(FLOAT_PRECISION)
+        );
+    }
+    (solution, lower_bound)
+}
+
+/// Order in which branch and bound explores the open nodes
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum SearchStrategy {
+    /// Deepest node first, needs little memory and finds complete solutions quickly
+    DepthFirst,
+    /// Node with the lowest bound first, raises the proven lower bound fastest
+    BestFirst,
+}
@@ -0,0 +1,425 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::SmallRng, SeedableRng};
+
+use crate::solution::RealVectorSolution;
+
+use super::{
+    real_vector::{evaluate_vectors, random_values, standard_normal},
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_GENERATION: u32 = 100;
+
+/// Initial step size relative to the width of the bounds
+const INITIAL_STEP_SIZE_FRACTION: f64 = 0.3;
+
+/// The search restarts when the step size in every direction is below this
+const MINIMUM_STEP_SIZE: f64 = 1e-12;
+
+/// The search also restarts when the best costs of the recent generations are all within this range
+const MINIMUM_COST_RANGE: f64 = 1e-12;
+
+/// Number of restarts that double the population, later restarts keep its size
+const MAX_POPULATION_DOUBLINGS: u32 = 9;
+
+/// Maximum number of sweeps of the Jacobi eigenvalue algorithm
+const MAX_JACOBI_SWEEPS: usize = 100;
+
+/// Parameters of CMA-ES
+#[derive(Clone, Copy, Debug)]
+pub struct CmaEsParameters {
+    /// Number of offspring per generation, 0 for the default of 4 + 3 ln(n)
+    pub population_size: usize,
+    /// Whether to restart with a doubled population once converged, as in IPOP-CMA-ES
+    pub restarts: bool,
+    /// Number of threads evaluating the offspring
+    pub num_threads: u32,
+}
+
+/// Identity matrix of the given size
+fn identity(n: usize) -> Vec<Vec<f64>> {
+    (0..n)
+        .map(|i| (0..n).map(|j| f64::from(u8::from(i == j))).collect())
+        .collect()
+}
+
+/// Eigenvalues and eigenvectors, as the columns of the matrix, of a symmetric matrix using the cyclic Jacobi eigenvalue algorithm
+fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut vectors = identity(n);
+    for _ in 0..MAX_JACOBI_SWEEPS {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        let diagonal: f64 = (0..n).map(|i| a[i][i] * a[i][i]).sum();
+        if off_diagonal <= 1e-30 * diagonal {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                // Rotation zeroing a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                for (k, (apk, aqk)) in row_p.into_iter().zip(row_q).enumerate() {
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in vectors.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), vectors)
+}
+
+/// State of a single run of CMA-ES, following the tutorial by Hansen
+struct CmaEs {
+    lambda: usize,
+    weights: Vec<f64>,
+    mu_eff: f64,
+    c_sigma: f64,
+    d_sigma: f64,
+    c_c: f64,
+    c_1: f64,
+    c_mu: f64,
+    chi_n: f64,
+    mean: Vec<f64>,
+    sigma: f64,
+    covariance: Vec<Vec<f64>>,
+    /// Eigenvectors of the covariance matrix as columns
+    basis: Vec<Vec<f64>>,
+    /// Square roots of the eigenvalues of the covariance matrix
+    scales: Vec<f64>,
+    path_sigma: Vec<f64>,
+    path_c: Vec<f64>,
+    generation: u32,
+    /// Best costs of the recent generations, at most 10 + 30n / lambda as in the tutorial
+    recent_best_costs: VecDeque<f64>,
+    history_length: usize,
+}
+
+impl CmaEs {
+    fn new(mean: Vec<f64>, sigma: f64, lambda: usize) -> Self {
+        let n = mean.len();
+        let nf = n as f64;
+        let mu = lambda / 2;
+        let raw: Vec<f64> = (0..mu)
+            .map(|i| (mu as f64 + 0.5).ln() - ((i + 1) as f64).ln())
+            .collect();
+        let total: f64 = raw.iter().sum();
+        let weights: Vec<f64> = raw.iter().map(|w| w / total).collect();
+        let mu_eff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+        let c_sigma = (mu_eff + 2.0) / (nf + mu_eff + 5.0);
+        let c_1 = 2.0 / ((nf + 1.3).powi(2) + mu_eff);
+        CmaEs {
+            lambda,
+            weights,
+            mu_eff,
+            c_sigma,
+            d_sigma: 1.0 + 2.0 * (((mu_eff - 1.0) / (nf + 1.0)).sqrt() - 1.0).max(0.0) + c_sigma,
+            c_c: (4.0 + mu_eff / nf) / (nf + 4.0 + 2.0 * mu_eff / nf),
+            c_1,
+            c_mu: (1.0 - c_1)
+                .min(2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((nf + 2.0).powi(2) + mu_eff)),
+            chi_n: nf.sqrt() * (1.0 - 1.0 / (4.0 * nf) + 1.0 / (21.0 * nf * nf)),
+            mean,
+            sigma,
+            covariance: identity(n),
+            basis: identity(n),
+            scales: vec![1.0; n],
+            path_sigma: vec![0.0; n],
+            path_c: vec![0.0; n],
+            generation: 0,
+            recent_best_costs: VecDeque::new(),
+            history_length: 10 + (30 * n).div_ceil(lambda),
+        }
+    }
+
+    /// Samples the offspring, clamped to the bounds
+    fn sample(&self, bounds: &[(f64, f64)], small_rng: &mut SmallRng) -> Vec<Vec<f64>> {
+        let n = self.mean.len();
+        (0..self.lambda)
+            .map(|_| {
+                let z: Vec<f64> = (0..n)
+                    .map(|k| self.scales[k] * standard_normal(small_rng))
+                    .collect();
+                (0..n)
+                    .map(|i| {
+                        let y: f64 = (0..n).map(|k| self.basis[i][k] * z[k]).sum();
+                        (self.mean[i] + self.sigma * y).clamp(bounds[i].0, bounds[i].1)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Moves the distribution towards the best offspring, which are sorted by cost
+    fn update(&mut self, sorted: &[&[f64]], best_cost: f64) {
+        let n = self.mean.len();
+        let nf = n as f64;
+        self.generation += 1;
+
+        // Best costs of the recent generations
+        self.recent_best_costs.push_back(best_cost);
+        if self.recent_best_costs.len() > self.history_length {
+            self.recent_best_costs.pop_front();
+        }
+
+        // Steps of the selected offspring in units of sigma, and the new mean
+        let steps: Vec<Vec<f64>> = sorted[..self.weights.len()]
+            .iter()
+            .map(|x| (0..n).map(|i| (x[i] - self.mean[i]) / self.sigma).collect())
+            .collect();
+        let step: Vec<f64> = (0..n)
+            .map(|i| {
+                (0..steps.len())
+                    .map(|k| self.weights[k] * steps[k][i])
+                    .sum()
+            })
+            .collect();
+        for (mean, step) in self.mean.iter_mut().zip(&step) {
+            *mean += self.sigma * step;
+        }
+
+        // Evolution path of the step size, using C^(-1/2) = B D^-1 B^T
+        let rotated: Vec<f64> = (0..n)
+            .map(|k| (0..n).map(|i| self.basis[i][k] * step[i]).sum::<f64>() / self.scales[k])
+            .collect();
+        let whitened: Vec<f64> = (0..n)
+            .map(|i| (0..n).map(|k| self.basis[i][k] * rotated[k]).sum())
+            .collect();
+        let sigma_factor = (self.c_sigma * (2.0 - self.c_sigma) * self.mu_eff).sqrt();
+        for (path, value) in self.path_sigma.iter_mut().zip(&whitened) {
+            *path = (1.0 - self.c_sigma) * *path + sigma_factor * value;
+        }
+        let path_sigma_norm = self.path_sigma.iter().map(|p| p * p).sum::<f64>().sqrt();
+
+        // Evolution path of the covariance, stalled when the step size path is long
+        let h_sigma = path_sigma_norm
+            / (1.0 - (1.0 - self.c_sigma).powi(2 * self.generation as i32)).sqrt()
+            < (1.4 + 2.0 / (nf + 1.0)) * self.chi_n;
+        let h_sigma = f64::from(u8::from(h_sigma));
+        let c_factor = (self.c_c * (2.0 - self.c_c) * self.mu_eff).sqrt();
+        for (path, value) in self.path_c.iter_mut().zip(&step) {
+            *path = (1.0 - self.c_c) * *path + h_sigma * c_factor * value;
+        }
+
+        // Rank one and rank mu update of the covariance matrix
+        let correction = (1.0 - h_sigma) * self.c_c * (2.0 - self.c_c);
+        for i in 0..n {
+            for j in 0..n {
+                let rank_mu: f64 = (0..steps.len())
+                    .map(|k| self.weights[k] * steps[k][i] * steps[k][j])
+                    .sum();
+                self.covariance[i][j] = (1.0 - self.c_1 - self.c_mu) * self.covariance[i][j]
+                    + self.c_1
+                        * (self.path_c[i] * self.path_c[j] + correction * self.covariance[i][j])
+                    + self.c_mu * rank_mu;
+            }
+        }
+
+        // Step size adaptation
+        self.sigma *= ((self.c_sigma / self.d_sigma) * (path_sigma_norm / self.chi_n - 1.0)).exp();
+
+        // New sampling basis
+        let (eigenvalues, basis) = symmetric_eigen(&self.covariance);
+        self.scales = eigenvalues.iter().map(|e| e.max(1e-20).sqrt()).collect();
+        self.basis = basis;
+    }
+
+    /// Whether the distribution has collapsed or the best cost stagnates, so further generations cannot make progress
+    fn converged(&self) -> bool {
+        let largest = self.scales.iter().cloned().fold(0.0, f64::max);
+        let highest = self
+            .recent_best_costs
+            .iter()
+            .cloned()
+            .fold(f64::MIN, f64::max);
+        let lowest = self
+            .recent_best_costs
+            .iter()
+            .cloned()
+            .fold(f64::MAX, f64::min);
+        let stagnated = self.recent_best_costs.len() == self.history_length
+            && highest - lowest < MINIMUM_COST_RANGE;
+        self.sigma * largest < MINIMUM_STEP_SIZE || !self.sigma.is_finite() || stagnated
+    }
+}
+
+/// Covariance matrix adaptation evolution strategy on real vectors within box constraints.
+/// Samples offspring from a multivariate normal distribution and moves its mean, covariance and step size towards the best offspring.
+/// Offspring are clamped to the bounds and evaluated in parallel. Optionally restarts from a random point with a doubled population once converged.
+pub fn cma_es<T>(
+    solution: &mut T,
+    parameters: &CmaEsParameters,
+    num_generations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    T: RealVectorSolution + Sync,
+{
+    let mut small_rng = SmallRng::from_entropy();
+    let bounds = solution.bounds().to_vec();
+    let n = bounds.len();
+    let width = bounds
+        .iter()
+        .map(|(lower, upper)| upper - lower)
+        .sum::<f64>()
+        / n as f64;
+    let mut lambda = match parameters.population_size {
+        0 => 4 + (3.0 * (n as f64).ln()).floor() as usize,
+        size => size,
+    }
+    .max(4);
+
+    // Print some info
+    println!(
+        "{} - Running CMA-ES for {} generations with {} offspring per generation",
+        process_name, num_generations, lambda
+    );
+
+    let mut best_solution = solution.clone();
+    let mut num_restarts = 0;
+    let mut state = CmaEs::new(
+        solution.values().to_vec(),
+        INITIAL_STEP_SIZE_FRACTION * width,
+        lambda,
+    );
+    for generation in 0..num_generations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Sample, evaluate and sort the offspring
+        let offspring = state.sample(&bounds, &mut small_rng);
+        let mut offspring = evaluate_vectors(solution, offspring, parameters.num_threads);
+        let mut ranked: Vec<(f64, usize)> = offspring
+            .iter_mut()
+            .enumerate()
+            .map(|(index, x)| (x.get_cost(), index))
+            .collect();
+        ranked.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        if ranked[0].0 < best_solution.get_cost() {
+            best_solution = offspring[ranked[0].1].clone();
+        }
+        let sorted: Vec<&[f64]> = ranked
+            .iter()
+            .map(|&(_, index)| offspring[index].values())
+            .collect();
+        state.update(&sorted, ranked[0].0);
+
+        // print cost every so often
+        if generation % REPORT_STATUS_EVERY_GENERATION == 0 {
+            println!(
+                " {} - Generation {} - Best cost: {:.4} Generation best: {:.4} Sigma: {:.4e}",
+                process_name,
+                generation,
+                best_solution.get_cost(),
+                ranked[0].0,
+                state.sigma
+            );
+        }
+
+        // Restart with a doubled population once converged
+        if state.converged() {
+            if !parameters.restarts {
+                println!("{} - Converged at generation {}", process_name, generation);
+                break;
+            }
+            if num_restarts < MAX_POPULATION_DOUBLINGS {
+                lambda *= 2;
+            }
+            num_restarts += 1;
+            println!(
+                "{} - Converged at generation {}, restarting with {} offspring",
+                process_name, generation, lambda
+            );
+            state = CmaEs::new(
+                random_values(solution, &mut small_rng),
+                INITIAL_STEP_SIZE_FRACTION * width,
+                lambda,
+            );
+        }
+    }
+
+    // Set the best solution
+    *solution = best_solution;
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use crate::examples::continuous::{ContinuousInstanceReader, ContinuousSolution};
+
+    use super::*;
+
+    /// Dimension of the benchmark functions in the tests
+    const DIMENSION: usize = 5;
+
+    /// Largest difference from the optimal cost accepted at the end of a run
+    const TOLERANCE: f64 = 1e-6;
+
+    fn run(function: &str, restarts: bool) -> ContinuousSolution {
+        let mut solution = ContinuousInstanceReader {}
+            .parse_instance(&format!("{}\n{}", function, DIMENSION), None);
+        let parameters = CmaEsParameters {
+            // Without restarts a large population is needed to reliably find the global minimum of Rastrigin
+            population_size: if restarts { 0 } else { 1000 },
+            restarts,
+            num_threads: 2,
+        };
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        cma_es(&mut solution, &parameters, 1000, "test", stop_signal);
+        solution
+    }
+
+    #[test]
+    fn solves_sphere() {
+        for restarts in [false, true] {
+            let solution = run("sphere", restarts);
+            assert!(
+                solution.error() < TOLERANCE,
+                "restarts {}: {}",
+                restarts,
+                solution.error()
+            );
+        }
+    }
+
+    #[test]
+    fn solves_rastrigin() {
+        for restarts in [false, true] {
+            let solution = run("rastrigin", restarts);
+            assert!(
+                solution.error() < TOLERANCE,
+                "restarts {}: {}",
+                restarts,
+                solution.error()
+            );
+        }
+    }
+}
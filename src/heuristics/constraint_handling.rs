@@ -0,0 +1,221 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{LocalRandomMove, OptimisationSense, Solution};
+
+use super::{
+    simulated_annealing::{
+        determine_start_and_end_temp, get_cooling_schedule, metropolis_rule, CoolingSchedule,
+    },
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 10_000_000;
+
+/// Number of iterations between adaptations of the penalty weight
+const PENALTY_ADAPTATION_INTERVAL: u32 = 1_000;
+
+/// Fraction of feasible solutions the adaptive penalty steers towards, so the search oscillates around the boundary
+const TARGET_FEASIBLE_FRACTION: f64 = 0.5;
+
+/// Factor the adaptive penalty weight is multiplied or divided by
+const PENALTY_ADAPTATION_FACTOR: f64 = 1.1;
+
+/// How far the adaptive penalty weight can move away from the starting weight, in both directions
+const PENALTY_WEIGHT_RANGE: f64 = 1_000.0;
+
+/// Cost and constraint violation of a solution
+pub type Evaluation = (f64, f64);
+
+/// Compares solutions of a constrained problem according to the chosen strategy, adapting the penalty weight if needed
+#[derive(Clone, Copy, Debug)]
+pub struct ConstraintHandler {
+    strategy: ConstraintHandling,
+    sense: OptimisationSense,
+    initial_weight: f64,
+    weight: f64,
+    /// Feasible solutions seen and solutions seen since the last adaptation
+    num_feasible: u32,
+    num_recorded: u32,
+}
+
+impl ConstraintHandler {
+    /// The weight has to be positive, the adaptive penalty keeps it within a range around it
+    pub fn new(strategy: ConstraintHandling, sense: OptimisationSense, weight: f64) -> Self {
+        assert!(
+            weight.is_finite() && weight > 0.0,
+            "Penalty weight must be positive"
+        );
+        ConstraintHandler {
+            strategy,
+            sense,
+            initial_weight: weight,
+            weight,
+            num_feasible: 0,
+            num_recorded: 0,
+        }
+    }
+
+    /// Current weight of a unit of violation, in units of cost
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// How much better the new evaluation is than the old one, negative if it is worse.
+    /// Infinite when feasibility first compares a feasible with an infeasible solution.
+    pub fn improvement(&self, old: Evaluation, new: Evaluation) -> f64 {
+        let (old_cost, old_violation) = old;
+        let (new_cost, new_violation) = new;
+        match self.strategy {
+            ConstraintHandling::StaticPenalty | ConstraintHandling::AdaptivePenalty => {
+                self.sense.improvement(old_cost, new_cost)
+                    - self.weight * (new_violation - old_violation)
+            }
+            ConstraintHandling::FeasibilityFirst => {
+                match (is_feasible(old_violation), is_feasible(new_violation)) {
+                    (true, true) => self.sense.improvement(old_cost, new_cost),
+                    (false, true) => f64::INFINITY,
+                    (true, false) => f64::NEG_INFINITY,
+                    (false, false) => self.weight * (old_violation - new_violation),
+                }
+            }
+        }
+    }
+
+    /// Whether evaluation a is strictly better than evaluation b
+    pub fn is_better(&self, a: Evaluation, b: Evaluation) -> bool {
+        self.improvement(b, a) > 0.0
+    }
+
+    /// Records the violation of the current solution. The adaptive penalty raises its weight when too few
+    /// recent solutions were feasible and lowers it when too many were, the other strategies ignore this.
+    pub fn record(&mut self, violation: f64) {
+        if let ConstraintHandling::AdaptivePenalty = self.strategy {
+            self.num_feasible += u32::from(is_feasible(violation));
+            self.num_recorded += 1;
+            if self.num_recorded >= PENALTY_ADAPTATION_INTERVAL {
+                let feasible_fraction = f64::from(self.num_feasible) / f64::from(self.num_recorded);
+                if feasible_fraction < TARGET_FEASIBLE_FRACTION {
+                    self.weight *= PENALTY_ADAPTATION_FACTOR;
+                } else {
+                    self.weight /= PENALTY_ADAPTATION_FACTOR;
+                }
+                self.weight = self.weight.clamp(
+                    self.initial_weight / PENALTY_WEIGHT_RANGE,
+                    self.initial_weight * PENALTY_WEIGHT_RANGE,
+                );
+                self.num_feasible = 0;
+                self.num_recorded = 0;
+            }
+        }
+    }
+}
+
+/// Whether a solution with the given violation satisfies all constraints
+pub fn is_feasible(violation: f64) -> bool {
+    violation <= 0.0
+}
+
+/// Simulated annealing that may visit infeasible solutions, comparing them by the constraint handling strategy.
+/// Temperatures are determined like simulated annealing, from the cost alone. Only feasible solutions are kept as the best,
+/// if none was found the solution is left at the end of the run.
+pub fn constrained_simulated_annealing<M, T>(
+    solution: &mut T,
+    num_iterations: u32,
+    handler: &mut ConstraintHandler,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    // Determine starting and ending temperature
+    let (starting_temperature, ending_temperature) =
+        determine_start_and_end_temp::<M, T>(20_000, solution, false);
+    let cooling_schedule = get_cooling_schedule(
+        CoolingSchedule::Exponential,
+        starting_temperature,
+        ending_temperature,
+        num_iterations,
+    );
+
+    // Print some info
+    println!(
+        "{} - Running constrained simulated annealing with {} for {} iterations with starting temperature: {} and ending temperature: {}",
+        process_name, handler.strategy, num_iterations, starting_temperature, ending_temperature
+    );
+
+    let mut small_rng = SmallRng::from_entropy();
+    let mut temperature = starting_temperature;
+    let mut current: Evaluation = (solution.get_cost(), solution.constraint_violation());
+    let mut best: Option<(Evaluation, T)> =
+        is_feasible(current.1).then(|| (current, solution.clone()));
+    for it in 0..num_iterations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Do the move, maybe reject it if it is worse
+        M::do_random_move(solution);
+        let new: Evaluation = (solution.get_cost(), solution.constraint_violation());
+        let improvement = handler.improvement(current, new);
+        if improvement < 0.0 && small_rng.gen::<f64>() > metropolis_rule(improvement, temperature) {
+            M::undo_last_move(solution);
+        } else {
+            current = new;
+        }
+        handler.record(current.1);
+        temperature = cooling_schedule(temperature);
+
+        // Update best solution, only feasible ones count
+        if is_feasible(current.1)
+            && best
+                .as_ref()
+                .is_none_or(|(evaluation, _)| handler.is_better(current, *evaluation))
+        {
+            best = Some((current, solution.clone()));
+        }
+
+        // print cost every so often
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            let percentage = (it as f64 / num_iterations as f64) * 100.0;
+            println!(
+                " {} - {:.0}% - Best feasible cost: {:.4} Current cost: {:.4} Violation: {:.4} Weight: {:.4} Temp: {:.4}",
+                process_name,
+                percentage,
+                best.as_ref().map_or(f64::NAN, |((cost, _), _)| *cost),
+                current.0,
+                current.1,
+                handler.weight(),
+                temperature,
+            );
+        }
+    }
+
+    // Set the best solution
+    match best {
+        Some((_, best_solution)) => {
+            *solution = best_solution;
+            println!("{} - Final cost: {}", process_name, solution.get_cost());
+        }
+        None => println!(
+            "{} - No feasible solution found, final cost: {} violation: {}",
+            process_name, current.0, current.1
+        ),
+    }
+    M::report_statistics(solution, process_name);
+}
+
+/// How infeasible solutions are compared with other solutions
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum ConstraintHandling {
+    /// Cost plus a fixed weight times the violation
+    StaticPenalty,
+    /// Cost plus a weight times the violation, the weight adapts so the search oscillates around the feasible region
+    AdaptivePenalty,
+    /// Feasible solutions always beat infeasible ones, infeasible ones compare by violation
+    FeasibilityFirst,
+}
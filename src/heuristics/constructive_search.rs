@@ -0,0 +1,349 @@
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{PartialSolution, Solution};
+
+use super::StopSignal;
+
+/// How often to report the status of the algorithm, in construction steps
+const REPORT_STATUS_EVERY_STEP: u32 = 100;
+
+/// Up to k extensions with the best score as (score, extension), best first
+pub fn best_extensions<P, T>(partial: &P, k: usize) -> Vec<(f64, P::Extension)>
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    let mut scored: Vec<(f64, P::Extension)> = partial
+        .extensions()
+        .into_iter()
+        .map(|extension| (partial.score_extension(&extension), extension))
+        .collect();
+    if k < scored.len() {
+        scored.select_nth_unstable_by(k, |a, b| a.0.total_cmp(&b.0));
+        scored.truncate(k);
+    }
+    scored.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    scored
+}
+
+/// Completes the partial solution by always taking the extension with the best score
+pub fn complete_greedily<P, T>(partial: &mut P)
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    while !partial.is_complete() {
+        match best_extensions::<P, T>(partial, 1).pop() {
+            Some((_, extension)) => partial.extend(&extension),
+            None => break,
+        }
+    }
+}
+
+/// Beam search, keeps the partial solutions with the best scores after every step.
+/// Every partial solution in the beam is extended in every possible way, the extensions are scored in parallel.
+/// When stopped the best partial solution in the beam is completed greedily.
+pub fn beam_search<P, T>(
+    solution: &T,
+    beam_width: usize,
+    num_threads: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> T
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    let beam_width = beam_width.max(1);
+    let mut beam = vec![P::empty(solution)];
+    let mut best_complete: Option<P> = None;
+    let mut step: u32 = 0;
+
+    // A trivial instance may need no steps at all
+    if beam[0].is_complete() {
+        best_complete = beam.pop();
+    }
+    while !beam.is_empty() {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            let best = (0..beam.len())
+                .min_by(|&a, &b| beam[a].score().total_cmp(&beam[b].score()))
+                .unwrap();
+            let mut partial = beam.swap_remove(best);
+            complete_greedily::<P, T>(&mut partial);
+            keep_best::<P, T>(&mut best_complete, partial);
+            break;
+        }
+
+        // Best extensions of every partial solution, no partial solution contributes more than the beam width
+        let chunk_size = beam.len().div_ceil(num_threads.max(1) as usize);
+        let mut candidates: Vec<(f64, usize, P::Extension)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = beam
+                .chunks(chunk_size)
+                .enumerate()
+                .map(|(chunk_index, chunk)| {
+                    scope.spawn(move || {
+                        let mut candidates = Vec::new();
+                        for (k, partial) in chunk.iter().enumerate() {
+                            let index = chunk_index * chunk_size + k;
+                            for (score, extension) in best_extensions::<P, T>(partial, beam_width) {
+                                candidates.push((score, index, extension));
+                            }
+                        }
+                        candidates
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        if beam_width < candidates.len() {
+            candidates.select_nth_unstable_by(beam_width, |a, b| a.0.total_cmp(&b.0));
+            candidates.truncate(beam_width);
+        }
+
+        // Extend the chosen partial solutions, complete ones leave the beam
+        let mut next_beam = Vec::with_capacity(candidates.len());
+        for (_, index, extension) in candidates {
+            let mut partial = beam[index].clone();
+            partial.extend(&extension);
+            if partial.is_complete() {
+                keep_best::<P, T>(&mut best_complete, partial);
+            } else {
+                next_beam.push(partial);
+            }
+        }
+        beam = next_beam;
+
+        // print score every so often
+        if step % REPORT_STATUS_EVERY_STEP == 0 {
+            let best_score = beam
+                .iter()
+                .map(|partial| partial.score())
+                .fold(f64::INFINITY, f64::min);
+            println!(
+                " {} - Step {} - Best score: {:.4} Beam size: {}",
+                process_name,
+                step,
+                best_score,
+                beam.len(),
+            );
+        }
+        step += 1;
+    }
+
+    let mut solution = best_complete
+        .expect("Beam search found no complete solution")
+        .to_solution();
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    solution
+}
+
+/// Lowest score reachable within the given number of steps, looking at the best candidates in every step
+fn lookahead<P, T>(partial: &P, depth: u32, num_candidates: usize) -> f64
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    if depth == 0 || partial.is_complete() {
+        return partial.score();
+    }
+    best_extensions::<P, T>(partial, num_candidates)
+        .into_iter()
+        .map(|(score, extension)| {
+            if depth == 1 {
+                return score;
+            }
+            let mut child = partial.clone();
+            child.extend(&extension);
+            lookahead::<P, T>(&child, depth - 1, num_candidates)
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Greedy construction with lookahead, every step takes the extension leading to the lowest score within the lookahead depth.
+/// Only the best scoring candidates are looked at in every step, a depth of 1 is plain greedy.
+/// When stopped the rest of the solution is completed greedily.
+pub fn greedy_lookahead<P, T>(
+    solution: &T,
+    lookahead_depth: u32,
+    num_candidates: usize,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> T
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    let mut partial = P::empty(solution);
+    let mut step: u32 = 0;
+    while !partial.is_complete() {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            complete_greedily::<P, T>(&mut partial);
+            break;
+        }
+
+        let best = best_extensions::<P, T>(&partial, num_candidates.max(1))
+            .into_iter()
+            .map(|(_, extension)| {
+                let mut child = partial.clone();
+                child.extend(&extension);
+                let value =
+                    lookahead::<P, T>(&child, lookahead_depth.saturating_sub(1), num_candidates);
+                (value, extension)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        match best {
+            Some((_, extension)) => partial.extend(&extension),
+            None => break,
+        }
+
+        // print score every so often
+        if step % REPORT_STATUS_EVERY_STEP == 0 {
+            println!(
+                " {} - Step {} - Score: {:.4}",
+                process_name,
+                step,
+                partial.score()
+            );
+        }
+        step += 1;
+    }
+
+    let mut solution = partial.to_solution();
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    solution
+}
+
+/// Pilot method, every step completes the best candidates greedily and takes the extension whose pilot solution is the cheapest.
+/// The pilots are run in parallel by a fixed number of threads, candidates whose lower bound is no better than the best pilot solution are skipped.
+/// The best pilot solution ever seen is returned.
+/// When stopped the best pilot solution so far is returned.
+pub fn pilot_method<P, T>(
+    solution: &T,
+    num_candidates: usize,
+    num_threads: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> T
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    let mut partial = P::empty(solution);
+    let mut best_complete: Option<P> = None;
+    let mut step: u32 = 0;
+    while !partial.is_complete() {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Every pilot completes one of the candidates greedily, candidates bounded by the best pilot solution are skipped
+        let best_cost = best_complete
+            .as_ref()
+            .map_or(f64::INFINITY, |best| best.score());
+        let candidates = best_extensions::<P, T>(&partial, num_candidates.max(1));
+        let chunk_size = candidates
+            .len()
+            .div_ceil(num_threads.max(1) as usize)
+            .max(1);
+        let pilots: Vec<Option<(P::Extension, P)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let partial = &partial;
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(_, extension)| {
+                                let mut pilot = partial.clone();
+                                pilot.extend(extension);
+                                if pilot.lower_bound() >= best_cost {
+                                    return None;
+                                }
+                                complete_greedily::<P, T>(&mut pilot);
+                                Some((extension.clone(), pilot))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        let Some((extension, pilot)) = pilots
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| a.1.score().total_cmp(&b.1.score()))
+        else {
+            println!(
+                "{} - No candidate can improve the best pilot solution",
+                process_name
+            );
+            break;
+        };
+        partial.extend(&extension);
+        keep_best::<P, T>(&mut best_complete, pilot);
+
+        // print cost every so often
+        if step % REPORT_STATUS_EVERY_STEP == 0 {
+            println!(
+                " {} - Step {} - Best pilot cost: {:.4}",
+                process_name,
+                step,
+                best_complete
+                    .as_ref()
+                    .map_or(f64::INFINITY, |best| best.score()),
+            );
+        }
+        step += 1;
+    }
+
+    // The partial solution itself may have been completed without a pilot
+    if partial.is_complete() {
+        keep_best::<P, T>(&mut best_complete, partial);
+    }
+    let mut solution = match best_complete {
+        Some(best) => best.to_solution(),
+        None => {
+            let mut partial = P::empty(solution);
+            complete_greedily::<P, T>(&mut partial);
+            partial.to_solution()
+        }
+    };
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    solution
+}
+
+/// Keeps the complete partial solution if it has the best score so far
+fn keep_best<P, T>(best: &mut Option<P>, candidate: P)
+where
+    P: PartialSolution<T>,
+    T: Solution,
+{
+    if best
+        .as_ref()
+        .is_none_or(|best| candidate.score() < best.score())
+    {
+        *best = Some(candidate);
+    }
+}
+
+/// Constructive heuristic to build the solution with
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum ConstructiveMethod {
+    BeamSearch,
+    GreedyLookahead,
+    /// Completes every candidate greedily before deciding on a step
+    PilotMethod,
+}
@@ -0,0 +1,321 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::SmallRng, seq::index::sample, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::RealVectorSolution;
+
+use super::{
+    real_vector::{evaluate_vectors, random_values, standard_normal},
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_GENERATION: u32 = 100;
+
+/// Learning rate of the JADE parameter adaptation
+const JADE_LEARNING_RATE: f64 = 0.1;
+
+/// Fraction of the population JADE picks the p-best individual from
+const JADE_GREEDINESS: f64 = 0.05;
+
+/// Parameters of differential evolution
+#[derive(Clone, Copy, Debug)]
+pub struct DifferentialEvolutionParameters {
+    pub population_size: usize,
+    pub strategy: DeStrategy,
+    /// Scale of the difference vectors, the starting mean for JADE
+    pub scale_factor: f64,
+    /// Probability of taking a value from the mutant, the starting mean for JADE
+    pub crossover_rate: f64,
+    /// Number of threads evaluating the trial vectors
+    pub num_threads: u32,
+}
+
+/// Values outside the bounds are put halfway between the bound and the value of the target vector
+fn repair(value: f64, target: f64, (lower, upper): (f64, f64)) -> f64 {
+    if value < lower {
+        (lower + target) / 2.0
+    } else if value > upper {
+        (upper + target) / 2.0
+    } else {
+        value
+    }
+}
+
+/// Binomial crossover of the target and the mutant, at least one value comes from the mutant
+fn binomial_crossover(
+    target: &[f64],
+    mutant: &[f64],
+    bounds: &[(f64, f64)],
+    crossover_rate: f64,
+    small_rng: &mut SmallRng,
+) -> Vec<f64> {
+    let forced = small_rng.gen_range(0..target.len());
+    (0..target.len())
+        .map(|j| {
+            if j == forced || small_rng.gen::<f64>() < crossover_rate {
+                repair(mutant[j], target[j], bounds[j])
+            } else {
+                target[j]
+            }
+        })
+        .collect()
+}
+
+/// Mean of the successful scale factors weighted by themselves, which favours larger ones
+fn lehmer_mean(values: &[f64]) -> f64 {
+    values.iter().map(|v| v * v).sum::<f64>() / values.iter().sum::<f64>()
+}
+
+/// Differential evolution on real vectors within box constraints.
+/// Every individual is the target of a trial vector, made by binomial crossover with a mutant built from difference vectors.
+/// The trial replaces the target if it is at least as good. Trial vectors are evaluated in parallel.
+/// JADE uses current-to-pbest mutation with an archive of replaced individuals and adapts the scale factor and crossover rate per individual.
+pub fn differential_evolution<T>(
+    solution: &mut T,
+    parameters: &DifferentialEvolutionParameters,
+    num_generations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    T: RealVectorSolution + Sync,
+{
+    let mut small_rng = SmallRng::from_entropy();
+    let population_size = parameters.population_size.max(4);
+    let bounds = solution.bounds().to_vec();
+
+    // Print some info
+    println!(
+        "{} - Running {} differential evolution for {} generations with population size {}",
+        process_name, parameters.strategy, num_generations, population_size
+    );
+
+    // Initial population, the given solution and random points
+    let mut population = vec![solution.clone()];
+    let random_points: Vec<Vec<f64>> = (1..population_size)
+        .map(|_| random_values(solution, &mut small_rng))
+        .collect();
+    population.extend(evaluate_vectors(
+        solution,
+        random_points,
+        parameters.num_threads,
+    ));
+    let mut costs: Vec<f64> = population.iter_mut().map(|x| x.get_cost()).collect();
+
+    // JADE state
+    let mut mean_scale_factor = parameters.scale_factor;
+    let mut mean_crossover_rate = parameters.crossover_rate;
+    let mut archive: Vec<Vec<f64>> = Vec::new();
+
+    for generation in 0..num_generations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Individuals sorted by cost, for best/1 and the p-best of JADE
+        let mut ranking: Vec<usize> = (0..population_size).collect();
+        ranking.sort_unstable_by(|&a, &b| costs[a].total_cmp(&costs[b]));
+        let num_pbest = ((JADE_GREEDINESS * population_size as f64).ceil() as usize).max(1);
+
+        // Build a trial vector for every individual
+        let mut scale_factors = Vec::with_capacity(population_size);
+        let mut crossover_rates = Vec::with_capacity(population_size);
+        let trials: Vec<Vec<f64>> = (0..population_size)
+            .map(|i| {
+                let target = population[i].values();
+                let others: Vec<usize> = sample(&mut small_rng, population_size - 1, 3)
+                    .into_iter()
+                    .map(|r| if r >= i { r + 1 } else { r })
+                    .collect();
+                let (scale_factor, crossover_rate) = match parameters.strategy {
+                    DeStrategy::Jade => {
+                        // Cauchy distributed scale factor, regenerated when not positive
+                        let scale_factor = loop {
+                            let sample = mean_scale_factor
+                                + 0.1 * (PI * (small_rng.gen::<f64>() - 0.5)).tan();
+                            if sample > 0.0 {
+                                break sample.min(1.0);
+                            }
+                        };
+                        let crossover_rate = (mean_crossover_rate
+                            + 0.1 * standard_normal(&mut small_rng))
+                        .clamp(0.0, 1.0);
+                        (scale_factor, crossover_rate)
+                    }
+                    _ => (parameters.scale_factor, parameters.crossover_rate),
+                };
+                scale_factors.push(scale_factor);
+                crossover_rates.push(crossover_rate);
+
+                let mutant: Vec<f64> = match parameters.strategy {
+                    DeStrategy::RandOneBin => {
+                        let (a, b, c) = (
+                            population[others[0]].values(),
+                            population[others[1]].values(),
+                            population[others[2]].values(),
+                        );
+                        (0..a.len())
+                            .map(|j| a[j] + scale_factor * (b[j] - c[j]))
+                            .collect()
+                    }
+                    DeStrategy::BestOneBin => {
+                        let best = population[ranking[0]].values();
+                        let (b, c) = (
+                            population[others[0]].values(),
+                            population[others[1]].values(),
+                        );
+                        (0..best.len())
+                            .map(|j| best[j] + scale_factor * (b[j] - c[j]))
+                            .collect()
+                    }
+                    DeStrategy::Jade => {
+                        // current-to-pbest/1, the second difference vector may come from the archive
+                        let pbest = population[ranking[small_rng.gen_range(0..num_pbest)]].values();
+                        let b = population[others[0]].values();
+                        let k = small_rng.gen_range(0..population_size + archive.len());
+                        let c = if k < population_size {
+                            population[others[1]].values()
+                        } else {
+                            &archive[k - population_size]
+                        };
+                        (0..target.len())
+                            .map(|j| {
+                                target[j]
+                                    + scale_factor * (pbest[j] - target[j])
+                                    + scale_factor * (b[j] - c[j])
+                            })
+                            .collect()
+                    }
+                };
+                binomial_crossover(target, &mutant, &bounds, crossover_rate, &mut small_rng)
+            })
+            .collect();
+
+        // Selection, trials at least as good replace their target
+        let trials = evaluate_vectors(solution, trials, parameters.num_threads);
+        let mut successful_scale_factors = Vec::new();
+        let mut successful_crossover_rates = Vec::new();
+        for (i, mut trial) in trials.into_iter().enumerate() {
+            let trial_cost = trial.get_cost();
+            if trial_cost <= costs[i] {
+                if trial_cost < costs[i] {
+                    successful_scale_factors.push(scale_factors[i]);
+                    successful_crossover_rates.push(crossover_rates[i]);
+                    if let DeStrategy::Jade = parameters.strategy {
+                        archive.push(population[i].values().to_vec());
+                    }
+                }
+                population[i] = trial;
+                costs[i] = trial_cost;
+            }
+        }
+
+        // JADE adapts the means towards the successful parameters and keeps the archive at population size
+        if let DeStrategy::Jade = parameters.strategy {
+            while archive.len() > population_size {
+                let k = small_rng.gen_range(0..archive.len());
+                archive.swap_remove(k);
+            }
+            if !successful_scale_factors.is_empty() {
+                mean_scale_factor = (1.0 - JADE_LEARNING_RATE) * mean_scale_factor
+                    + JADE_LEARNING_RATE * lehmer_mean(&successful_scale_factors);
+                mean_crossover_rate = (1.0 - JADE_LEARNING_RATE) * mean_crossover_rate
+                    + JADE_LEARNING_RATE
+                        * (successful_crossover_rates.iter().sum::<f64>()
+                            / successful_crossover_rates.len() as f64);
+            }
+        }
+
+        // print cost every so often
+        if generation % REPORT_STATUS_EVERY_GENERATION == 0 {
+            let best = costs.iter().cloned().fold(f64::INFINITY, f64::min);
+            let average = costs.iter().sum::<f64>() / costs.len() as f64;
+            println!(
+                " {} - Generation {} - Best cost: {:.4} Average cost: {:.4} F: {:.3} CR: {:.3}",
+                process_name, generation, best, average, mean_scale_factor, mean_crossover_rate
+            );
+        }
+    }
+
+    // Set the best solution
+    let best = (0..population_size)
+        .min_by(|&a, &b| costs[a].total_cmp(&costs[b]))
+        .unwrap();
+    *solution = population.swap_remove(best);
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+}
+
+/// Mutation strategy of differential evolution, all with binomial crossover
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum DeStrategy {
+    /// Random base vector plus one scaled difference vector
+    RandOneBin,
+    /// Best individual plus one scaled difference vector, converges fast but prematurely with a small scale factor
+    BestOneBin,
+    /// Current-to-pbest mutation with an archive and self-adapting scale factor and crossover rate
+    Jade,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use strum::IntoEnumIterator;
+
+    use crate::examples::continuous::{ContinuousInstanceReader, ContinuousSolution};
+
+    use super::*;
+
+    /// Dimension of the benchmark functions in the tests
+    const DIMENSION: usize = 5;
+
+    /// Largest difference from the optimal cost accepted at the end of a run
+    const TOLERANCE: f64 = 1e-6;
+
+    fn run(function: &str, strategy: DeStrategy) -> ContinuousSolution {
+        let mut solution = ContinuousInstanceReader {}
+            .parse_instance(&format!("{}\n{}", function, DIMENSION), None);
+        let parameters = DifferentialEvolutionParameters {
+            population_size: 40,
+            strategy,
+            scale_factor: 0.5,
+            // Both functions are separable, which a low crossover rate exploits
+            crossover_rate: 0.1,
+            num_threads: 2,
+        };
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        differential_evolution(&mut solution, &parameters, 1000, "test", stop_signal);
+        solution
+    }
+
+    #[test]
+    fn every_strategy_solves_sphere() {
+        for strategy in DeStrategy::iter() {
+            let solution = run("sphere", strategy);
+            assert!(
+                solution.error() < TOLERANCE,
+                "{}: {}",
+                strategy,
+                solution.error()
+            );
+        }
+    }
+
+    #[test]
+    fn every_strategy_solves_rastrigin() {
+        for strategy in DeStrategy::iter() {
+            let solution = run("rastrigin", strategy);
+            assert!(
+                solution.error() < TOLERANCE,
+                "{}: {}",
+                strategy,
+                solution.error()
+            );
+        }
+    }
+}
@@ -0,0 +1,762 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::TourSolution;
+
+use super::{genetic_algorithm::sort_population, StopSignal, FLOAT_PRECISION};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_GENERATION: u32 = 10;
+
+/// Number of nearest neighbours kept for every city, used by 2-opt and when merging subtours
+const NUM_NEAR_NEIGHBOURS: usize = 10;
+
+/// The search stagnates when the best tour has not improved for this many generations, divided by the number of children per pair
+const STAGNATION_CHILDREN: u32 = 1_500;
+
+/// Marks a missing edge in the adjacency arrays
+const NONE: usize = usize::MAX;
+
+/// Parameters of the edge assembly crossover
+#[derive(Clone, Copy, Debug)]
+pub struct EaxParameters {
+    pub population_size: usize,
+    /// Number of children created from every pair of parents, the best one replaces the first parent
+    pub num_children: usize,
+    pub strategy: ESetStrategy,
+    /// Number of threads used to create the initial population and the offspring
+    pub num_threads: u32,
+}
+
+/// Nearest neighbours of every city, sorted from nearest to furthest
+pub fn near_neighbours<T: TourSolution + Sync>(solution: &T, num_threads: u32) -> Vec<Vec<usize>> {
+    let n = solution.tour().len();
+    let mut neighbours = vec![Vec::new(); n];
+    let chunk_size = n.div_ceil(num_threads.max(1) as usize).max(1);
+    std::thread::scope(|scope| {
+        for (chunk_index, chunk) in neighbours.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(move || {
+                for (offset, list) in chunk.iter_mut().enumerate() {
+                    let city = chunk_index * chunk_size + offset;
+                    let mut others: Vec<(f64, usize)> = (0..n)
+                        .filter(|&other| other != city)
+                        .map(|other| (solution.edge_length(city, other), other))
+                        .collect();
+                    if NUM_NEAR_NEIGHBOURS < others.len() {
+                        others.select_nth_unstable_by(NUM_NEAR_NEIGHBOURS, |a, b| {
+                            a.0.total_cmp(&b.0)
+                        });
+                        others.truncate(NUM_NEAR_NEIGHBOURS);
+                    }
+                    others.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    *list = others.into_iter().map(|(_, other)| other).collect();
+                }
+            });
+        }
+    });
+    neighbours
+}
+
+/// Creates the initial population of random tours improved by 2-opt, in parallel
+pub fn eax_initial_population<T: TourSolution + Sync>(
+    solution: &T,
+    neighbours: &[Vec<usize>],
+    parameters: &EaxParameters,
+) -> Vec<T> {
+    let population_size = parameters.population_size;
+    let num_threads = (parameters.num_threads.max(1) as usize).min(population_size.max(1));
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_threads)
+            .map(|thread| {
+                // Divide the individuals as evenly as possible
+                let count = population_size / num_threads
+                    + usize::from(thread < population_size % num_threads);
+                scope.spawn(move || {
+                    let mut small_rng = SmallRng::from_entropy();
+                    (0..count)
+                        .map(|_| {
+                            let mut tour = solution.tour().to_vec();
+                            tour.shuffle(&mut small_rng);
+                            two_opt(solution, &mut tour, neighbours);
+                            solution.with_tour(tour)
+                        })
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Next city in the tour, or the previous one when going backwards
+fn next_in_tour(tour: &[usize], position: &[usize], city: usize, forward: bool) -> usize {
+    let n = tour.len();
+    if forward {
+        tour[(position[city] + 1) % n]
+    } else {
+        tour[(position[city] + n - 1) % n]
+    }
+}
+
+/// Reverses the tour from city `first` up to and including city `last`, wrapping around the end.
+/// The shorter of the segment and the rest of the tour is reversed, which gives the same cycle.
+fn reverse_segment(tour: &mut [usize], position: &mut [usize], first: usize, last: usize) {
+    let n = tour.len();
+    let (mut i, mut j) = (position[first], position[last]);
+    let mut length = (j + n - i) % n + 1;
+    if 2 * length > n {
+        (i, j) = ((j + 1) % n, (i + n - 1) % n);
+        length = n - length;
+    }
+    for _ in 0..length / 2 {
+        tour.swap(i, j);
+        position[tour[i]] = i;
+        position[tour[j]] = j;
+        i = (i + 1) % n;
+        j = (j + n - 1) % n;
+    }
+}
+
+/// 2-opt local search that only tries to connect cities to their near neighbours.
+/// Cities are queued again when an edge next to them changes, until no improving move is left.
+fn two_opt<T: TourSolution>(solution: &T, tour: &mut [usize], neighbours: &[Vec<usize>]) {
+    let n = tour.len();
+    if n < 5 {
+        return;
+    }
+    let mut position = vec![0; n];
+    for (index, &city) in tour.iter().enumerate() {
+        position[city] = index;
+    }
+    let mut queue: VecDeque<usize> = tour.iter().copied().collect();
+    let mut queued = vec![true; n];
+
+    while let Some(a) = queue.pop_front() {
+        queued[a] = false;
+        'search: for forward in [true, false] {
+            // Replace edges (a, b) and (c, d) by (a, c) and (b, d)
+            let b = next_in_tour(tour, &position, a, forward);
+            let removed_ab = solution.edge_length(a, b);
+            for &c in &neighbours[a] {
+                let added_ac = solution.edge_length(a, c);
+                if added_ac >= removed_ab {
+                    break;
+                }
+                let d = next_in_tour(tour, &position, c, forward);
+                if c == b || d == a {
+                    continue;
+                }
+                let delta =
+                    added_ac + solution.edge_length(b, d) - removed_ab - solution.edge_length(c, d);
+                if delta < -FLOAT_PRECISION {
+                    let (first, last) = if forward { (b, c) } else { (a, d) };
+                    reverse_segment(tour, &mut position, first, last);
+                    for city in [a, b, c, d] {
+                        if !queued[city] {
+                            queued[city] = true;
+                            queue.push_back(city);
+                        }
+                    }
+                    break 'search;
+                }
+            }
+        }
+    }
+}
+
+/// Both neighbours of every city in the tour
+fn tour_links(tour: &[usize]) -> Vec<[usize; 2]> {
+    let n = tour.len();
+    let mut links = vec![[NONE; 2]; n];
+    for (index, &city) in tour.iter().enumerate() {
+        links[city] = [tour[(index + n - 1) % n], tour[(index + 1) % n]];
+    }
+    links
+}
+
+/// Follows the links from city 0 to get the tour, the links have to form a single cycle
+fn links_to_tour(links: &[[usize; 2]]) -> Vec<usize> {
+    let mut tour = Vec::with_capacity(links.len());
+    let (mut previous, mut current) = (links[0][1], 0);
+    for _ in 0..links.len() {
+        tour.push(current);
+        let next = if links[current][0] != previous {
+            links[current][0]
+        } else {
+            links[current][1]
+        };
+        previous = current;
+        current = next;
+    }
+    tour
+}
+
+/// Replaces a neighbour in the links of a city
+fn replace_link(links: &mut [usize; 2], from: usize, to: usize) {
+    if links[0] == from {
+        links[0] = to;
+    } else {
+        links[1] = to;
+    }
+}
+
+/// One of the remaining edges, picked at random
+fn random_edge(links: &[usize; 2], small_rng: &mut SmallRng) -> Option<usize> {
+    match (links[0] != NONE, links[1] != NONE) {
+        (true, true) => Some(links[small_rng.gen_range(0..2)]),
+        (true, false) => Some(links[0]),
+        (false, true) => Some(links[1]),
+        (false, false) => None,
+    }
+}
+
+/// Splits the edges that are in only one of the parents into AB-cycles, which alternate between edges of A and edges of B.
+/// Every cycle starts with an edge of A, so the edges at even indices belong to A and the others to B.
+fn ab_cycles(
+    links_a: &[[usize; 2]],
+    links_b: &[[usize; 2]],
+    small_rng: &mut SmallRng,
+) -> Vec<Vec<usize>> {
+    let n = links_a.len();
+
+    // Remove the edges the parents share
+    let mut only_a = links_a.to_vec();
+    let mut only_b = links_b.to_vec();
+    for city in 0..n {
+        for slot in 0..2 {
+            if links_b[city].contains(&links_a[city][slot]) {
+                only_a[city][slot] = NONE;
+            }
+            if links_a[city].contains(&links_b[city][slot]) {
+                only_b[city][slot] = NONE;
+            }
+        }
+    }
+
+    // Walk alternating edges from random cities, cutting off a cycle whenever the path closes on itself
+    let mut starts: Vec<usize> = (0..n).filter(|&city| only_a[city] != [NONE; 2]).collect();
+    starts.shuffle(small_rng);
+    let mut cycles = Vec::new();
+    let mut path: Vec<usize> = Vec::new();
+    let mut path_positions: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for start in starts {
+        if only_a[start] == [NONE; 2] {
+            continue;
+        }
+        path.push(start);
+        path_positions[start].push(0);
+        while let Some(&current) = path.last() {
+            // The k-th edge of the path belongs to A if k is even
+            let k = path.len() - 1;
+            let edges = if k % 2 == 0 { &mut only_a } else { &mut only_b };
+            let Some(next) = random_edge(&edges[current], small_rng) else {
+                break;
+            };
+            replace_link(&mut edges[current], next, NONE);
+            replace_link(&mut edges[next], current, NONE);
+
+            // The cycle closes if the edge leaving the earlier visit is of the other parent
+            if let Some(&j) = path_positions[next]
+                .iter()
+                .rev()
+                .find(|&&j| (k - j) % 2 == 1)
+            {
+                for (index, &city) in path.iter().enumerate().skip(j + 1) {
+                    path_positions[city].retain(|&position| position != index);
+                }
+                let mut cycle = path.split_off(j);
+                path.push(next);
+                if j % 2 == 1 {
+                    cycle.rotate_left(1);
+                }
+                cycles.push(cycle);
+            } else {
+                path_positions[next].push(path.len());
+                path.push(next);
+            }
+
+            if path.len() == 1 && only_a[path[0]] == [NONE; 2] {
+                break;
+            }
+        }
+        for city in path.drain(..) {
+            path_positions[city].clear();
+        }
+    }
+    cycles
+}
+
+/// AB-cycles that are combined into the E-set around the centre cycle
+fn select_e_set(
+    cycles: &[Vec<usize>],
+    centre: usize,
+    block: bool,
+    in_centre: &mut [bool],
+) -> Vec<usize> {
+    if !block {
+        return vec![centre];
+    }
+
+    // Block of the centre and the smaller cycles that share a city with it
+    for &city in &cycles[centre] {
+        in_centre[city] = true;
+    }
+    let e_set = (0..cycles.len())
+        .filter(|&k| {
+            k == centre
+                || (cycles[k].len() < cycles[centre].len()
+                    && cycles[k].iter().any(|&city| in_centre[city]))
+        })
+        .collect();
+    for &city in &cycles[centre] {
+        in_centre[city] = false;
+    }
+    e_set
+}
+
+/// Merges the subtours into a single tour, always merging the smallest subtour into a neighbouring one.
+/// The cheapest exchange of an edge of the subtour and an edge near it is used, returns the change in length.
+fn merge_subtours<T: TourSolution>(
+    solution: &T,
+    links: &mut [[usize; 2]],
+    neighbours: &[Vec<usize>],
+) -> f64 {
+    let n = links.len();
+
+    // Label the subtours
+    let mut subtour_of = vec![NONE; n];
+    let mut subtours: Vec<Vec<usize>> = Vec::new();
+    for start in 0..n {
+        if subtour_of[start] != NONE {
+            continue;
+        }
+        let label = subtours.len();
+        let mut cities = Vec::new();
+        let (mut previous, mut current) = (links[start][1], start);
+        loop {
+            subtour_of[current] = label;
+            cities.push(current);
+            let next = if links[current][0] != previous {
+                links[current][0]
+            } else {
+                links[current][1]
+            };
+            previous = current;
+            current = next;
+            if current == start {
+                break;
+            }
+        }
+        subtours.push(cities);
+    }
+
+    let mut delta = 0.0;
+    let mut remaining: Vec<usize> = (0..subtours.len()).collect();
+    while remaining.len() > 1 {
+        let (remaining_index, smallest) = remaining
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|&(_, label)| subtours[label].len())
+            .unwrap();
+
+        // Remove (c, c_next) and (x, y), add (c, x) and (c_next, y). Near neighbours first, every city if none of those work
+        let mut best: Option<(f64, [usize; 4])> = None;
+        for search_all in [false, true] {
+            let all_cities: Vec<usize> = if search_all {
+                (0..n).collect()
+            } else {
+                Vec::new()
+            };
+            for &c in &subtours[smallest] {
+                let candidates = if search_all {
+                    &all_cities
+                } else {
+                    &neighbours[c]
+                };
+                for c_next in links[c] {
+                    let removed_c = solution.edge_length(c, c_next);
+                    for &v in candidates {
+                        if subtour_of[v] == smallest {
+                            continue;
+                        }
+                        for v_next in links[v] {
+                            let removed = removed_c + solution.edge_length(v, v_next);
+                            for (x, y) in [(v, v_next), (v_next, v)] {
+                                let change = solution.edge_length(c, x)
+                                    + solution.edge_length(c_next, y)
+                                    - removed;
+                                if best.is_none_or(|(best_change, _)| change < best_change) {
+                                    best = Some((change, [c, c_next, x, y]));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if best.is_some() {
+                break;
+            }
+        }
+
+        let (change, [c, c_next, x, y]) = best.unwrap();
+        replace_link(&mut links[c], c_next, x);
+        replace_link(&mut links[c_next], c, y);
+        replace_link(&mut links[x], y, c);
+        replace_link(&mut links[y], x, c_next);
+        delta += change;
+
+        // Relabel the merged subtour
+        let target = subtour_of[x];
+        let merged = std::mem::take(&mut subtours[smallest]);
+        for &city in &merged {
+            subtour_of[city] = target;
+        }
+        subtours[target].extend(merged);
+        remaining.swap_remove(remaining_index);
+    }
+    delta
+}
+
+/// Creates children of the two parents, each from parent A with the edges of an E-set exchanged for those of parent B.
+/// Returns the best child if it is shorter than parent A.
+fn best_child<T: TourSolution>(
+    parent_a: &T,
+    parent_b: &T,
+    cost_a: f64,
+    neighbours: &[Vec<usize>],
+    num_children: usize,
+    block: bool,
+    small_rng: &mut SmallRng,
+) -> Option<T> {
+    let links_a = tour_links(parent_a.tour());
+    let links_b = tour_links(parent_b.tour());
+    let cycles = ab_cycles(&links_a, &links_b, small_rng);
+
+    // With a single cycle the only child is parent B
+    if cycles.len() < 2 {
+        return None;
+    }
+
+    let mut centres: Vec<usize> = (0..cycles.len()).collect();
+    centres.shuffle(small_rng);
+    let mut in_centre = vec![false; links_a.len()];
+    let mut best: Option<(f64, Vec<[usize; 2]>)> = None;
+    for &centre in centres.iter().take(num_children) {
+        let e_set = select_e_set(&cycles, centre, block, &mut in_centre);
+        if e_set.len() == cycles.len() {
+            continue;
+        }
+
+        // Remove the edges of A in the E-set, then add those of B
+        let mut links = links_a.clone();
+        let mut delta = 0.0;
+        for &k in &e_set {
+            let cycle = &cycles[k];
+            for i in (0..cycle.len()).step_by(2) {
+                let (x, y) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+                replace_link(&mut links[x], y, NONE);
+                replace_link(&mut links[y], x, NONE);
+                delta -= parent_a.edge_length(x, y);
+            }
+        }
+        for &k in &e_set {
+            let cycle = &cycles[k];
+            for i in (1..cycle.len()).step_by(2) {
+                let (x, y) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+                replace_link(&mut links[x], NONE, y);
+                replace_link(&mut links[y], NONE, x);
+                delta += parent_a.edge_length(x, y);
+            }
+        }
+        delta += merge_subtours(parent_a, &mut links, neighbours);
+
+        let cost = cost_a + delta;
+        if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+            best = Some((cost, links));
+        }
+    }
+
+    match best {
+        Some((cost, links)) if cost < cost_a - FLOAT_PRECISION => {
+            Some(parent_a.with_tour(links_to_tour(&links)))
+        }
+        _ => None,
+    }
+}
+
+/// Pairs every individual with the next one in the order and creates their best child, in parallel.
+/// All pairs use the population at the start of the generation.
+fn create_children<T: TourSolution + Sync>(
+    population: &[T],
+    costs: &[f64],
+    order: &[usize],
+    neighbours: &[Vec<usize>],
+    parameters: &EaxParameters,
+    block: bool,
+) -> Vec<Option<T>> {
+    let num_threads = (parameters.num_threads.max(1) as usize).min(order.len());
+    let chunk_size = order.len().div_ceil(num_threads);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..order.len())
+            .step_by(chunk_size)
+            .map(|first| {
+                scope.spawn(move || {
+                    let mut small_rng = SmallRng::from_entropy();
+                    (first..(first + chunk_size).min(order.len()))
+                        .map(|i| {
+                            let (a, b) = (order[i], order[(i + 1) % order.len()]);
+                            best_child(
+                                &population[a],
+                                &population[b],
+                                costs[a],
+                                neighbours,
+                                parameters.num_children,
+                                block,
+                                &mut small_rng,
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Genetic algorithm with the edge assembly crossover (EAX) of Nagata.
+/// Every generation the population is paired up in a random order and the best child of each pair replaces its first parent if it is shorter.
+/// Stops when stagnating, or switches from single to block E-sets first if that strategy is chosen.
+/// The population is left sorted from best to worst.
+pub fn edge_assembly_crossover<T: TourSolution + Sync>(
+    population: &mut Vec<T>,
+    neighbours: &[Vec<usize>],
+    parameters: &EaxParameters,
+    num_generations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) {
+    // Print some info
+    println!(
+        "{} - Running edge assembly crossover for {} generations with population size {}, {} children per pair and {} E-sets",
+        process_name,
+        num_generations,
+        population.len(),
+        parameters.num_children,
+        parameters.strategy
+    );
+
+    let mut costs = sort_population(population);
+    let stagnation_limit = (STAGNATION_CHILDREN / parameters.num_children.max(1) as u32).max(1);
+    let mut block = matches!(parameters.strategy, ESetStrategy::Block);
+    let mut best_cost = costs[0];
+    let mut generations_without_improvement = 0;
+    let mut small_rng = SmallRng::from_entropy();
+    for generation in 0..num_generations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Pair every individual with the next one in a random order
+        let mut order: Vec<usize> = (0..population.len()).collect();
+        order.shuffle(&mut small_rng);
+        let children = create_children(population, &costs, &order, neighbours, parameters, block);
+        for (i, child) in children.into_iter().enumerate() {
+            if let Some(child) = child {
+                population[order[i]] = child;
+            }
+        }
+        costs = sort_population(population);
+
+        // Switch to block E-sets or stop when stagnating
+        if costs[0] < best_cost - FLOAT_PRECISION {
+            best_cost = costs[0];
+            generations_without_improvement = 0;
+        } else {
+            generations_without_improvement += 1;
+        }
+        if generations_without_improvement >= stagnation_limit {
+            if !block && matches!(parameters.strategy, ESetStrategy::SingleThenBlock) {
+                println!("{} - Stagnating, switching to block E-sets", process_name);
+                block = true;
+                generations_without_improvement = 0;
+            } else {
+                println!("{} - Stagnating, stopping", process_name);
+                break;
+            }
+        }
+
+        // print cost every so often
+        if generation % REPORT_STATUS_EVERY_GENERATION == 0 {
+            let average = costs.iter().sum::<f64>() / costs.len() as f64;
+            println!(
+                " {} - Generation {} - Best cost: {:.4} Average cost: {:.4}",
+                process_name, generation, costs[0], average
+            );
+        }
+    }
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, costs[0]);
+}
+
+/// Which AB-cycles are combined into the E-set of a child
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum ESetStrategy {
+    /// A single random AB-cycle
+    Single,
+    /// A random AB-cycle and the smaller AB-cycles that share a city with it
+    Block,
+    /// Single AB-cycles until the search stagnates, then blocks
+    SingleThenBlock,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        examples::tsp::{TspInstanceReader, TspSolution},
+        solution::Solution,
+    };
+
+    use super::*;
+
+    /// Eight cities evenly spaced on a circle, visited in order by the shortest tour
+    fn circle_instance() -> TspSolution {
+        let points: String = (0..8)
+            .map(|i| {
+                let angle = std::f64::consts::TAU * i as f64 / 8.0;
+                format!("{:.6} {:.6}\n", 10.0 * angle.cos(), 10.0 * angle.sin())
+            })
+            .collect();
+        TspInstanceReader {}.parse_instance(&format!("8\n{}", points), None)
+    }
+
+    /// Parent A crosses itself within the first and within the last four cities, parent B is the shortest tour.
+    /// Their AB-cycles are (0, 2, 3, 1) and (4, 6, 7, 5), up to where they start.
+    fn parents() -> (TspSolution, TspSolution) {
+        let solution = circle_instance();
+        (
+            solution.with_tour(vec![0, 2, 1, 3, 4, 6, 5, 7]),
+            solution.with_tour((0..8).collect()),
+        )
+    }
+
+    /// Length of the tour given by the links, which have to form a single cycle through every city
+    fn tour_length(solution: &TspSolution, links: &[[usize; 2]]) -> f64 {
+        let mut tour = links_to_tour(links);
+        let n = tour.len();
+        let length = (0..n)
+            .map(|i| solution.edge_length(tour[i], tour[(i + 1) % n]))
+            .sum();
+        tour.sort_unstable();
+        assert_eq!(tour, (0..n).collect::<Vec<_>>());
+        length
+    }
+
+    #[test]
+    fn ab_cycles_alternate_between_the_parents() {
+        let (parent_a, parent_b) = parents();
+        let links_a = tour_links(parent_a.tour());
+        let links_b = tour_links(parent_b.tour());
+        for seed in 0..20 {
+            let mut cycles = ab_cycles(&links_a, &links_b, &mut SmallRng::seed_from_u64(seed));
+            for cycle in &cycles {
+                for i in 0..cycle.len() {
+                    let (x, y) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+                    let (own, other) = if i % 2 == 0 {
+                        (&links_a, &links_b)
+                    } else {
+                        (&links_b, &links_a)
+                    };
+                    assert!(own[x].contains(&y) && !other[x].contains(&y));
+                }
+            }
+
+            let mut cities: Vec<Vec<usize>> = cycles
+                .iter_mut()
+                .map(|cycle| {
+                    cycle.sort_unstable();
+                    cycle.clone()
+                })
+                .collect();
+            cities.sort_unstable();
+            assert_eq!(cities, vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]]);
+        }
+    }
+
+    #[test]
+    fn block_e_set_adds_smaller_cycles_sharing_a_city() {
+        let cycles = vec![
+            vec![0, 1, 2, 3, 4, 5],
+            vec![1, 6],
+            vec![7, 8, 9, 10],
+            vec![0, 7],
+        ];
+        let mut in_centre = vec![false; 11];
+        assert_eq!(select_e_set(&cycles, 0, false, &mut in_centre), vec![0]);
+        assert_eq!(
+            select_e_set(&cycles, 0, true, &mut in_centre),
+            vec![0, 1, 3]
+        );
+        assert_eq!(select_e_set(&cycles, 2, true, &mut in_centre), vec![2, 3]);
+        assert_eq!(select_e_set(&cycles, 1, true, &mut in_centre), vec![1]);
+        assert!(in_centre.iter().all(|&marked| !marked));
+    }
+
+    #[test]
+    fn merged_subtours_form_a_single_tour() {
+        let solution = circle_instance();
+        let neighbours = near_neighbours(&solution, 1);
+
+        // Two subtours, the cities 0 to 3 and the cities 4 to 7 in order
+        let mut links: Vec<[usize; 2]> = (0..8)
+            .map(|city| {
+                let first = city / 4 * 4;
+                [first + (city + 3) % 4, first + (city + 1) % 4]
+            })
+            .collect();
+        let length_before: f64 = (0..8)
+            .map(|city| solution.edge_length(city, links[city][1]))
+            .sum();
+
+        let delta = merge_subtours(&solution, &mut links, &neighbours);
+        assert!((length_before + delta - tour_length(&solution, &links)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn best_child_takes_the_edges_of_an_ab_cycle() {
+        let (mut parent_a, parent_b) = parents();
+        let cost_a = parent_a.get_cost();
+        let neighbours = near_neighbours(&parent_a, 1);
+
+        // Exchanging either AB-cycle removes one crossing, which gives the same length on the circle
+        let expected = parent_a.with_tour(vec![0, 1, 2, 3, 4, 6, 5, 7]).get_cost();
+        for seed in 0..20 {
+            let mut child = best_child(
+                &parent_a,
+                &parent_b,
+                cost_a,
+                &neighbours,
+                2,
+                false,
+                &mut SmallRng::seed_from_u64(seed),
+            )
+            .unwrap();
+            let cost = child.get_cost();
+            let links = tour_links(child.tour());
+            assert!((cost - tour_length(&child, &links)).abs() < 1e-9);
+            assert!((cost - expected).abs() < 1e-9);
+            assert!(cost < cost_a);
+        }
+    }
+}
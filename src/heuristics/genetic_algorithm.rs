@@ -0,0 +1,243 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{Crossover, LocalRandomMove, Solution};
+
+use super::StopSignal;
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_GENERATION: u32 = 10;
+
+/// Number of random moves applied to the starting solution to create every other individual of the initial population
+const INITIAL_POPULATION_RANDOM_MOVES: u32 = 1_000;
+
+/// Parameters of the genetic algorithm
+#[derive(Clone, Copy, Debug)]
+pub struct GeneticAlgorithmParameters {
+    pub population_size: usize,
+    /// Number of best individuals that survive to the next generation unchanged
+    pub num_elites: usize,
+    pub selection: Selection,
+    /// Number of individuals competing in a tournament, only used for tournament selection
+    pub tournament_size: usize,
+    /// Probability that a child is mutated by a random move
+    pub mutation_probability: f64,
+    /// Number of threads used to create and evaluate the offspring
+    pub num_threads: u32,
+}
+
+/// Creates the initial population, the starting solution and copies of it scrambled by random moves.
+pub fn initial_population<M, T>(solution: &T, population_size: usize) -> Vec<T>
+where
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    let mut population = vec![solution.clone()];
+    while population.len() < population_size {
+        let mut individual = solution.clone();
+        for _ in 0..INITIAL_POPULATION_RANDOM_MOVES {
+            M::do_random_move(&mut individual);
+        }
+        population.push(individual);
+    }
+    population
+}
+
+/// Picks the index of a parent from the population
+pub fn select_parent(
+    costs: &[f64],
+    selection: Selection,
+    tournament_size: usize,
+    small_rng: &mut SmallRng,
+) -> usize {
+    match selection {
+        Selection::Tournament => {
+            // Best of a number of random individuals
+            let mut best = small_rng.gen_range(0..costs.len());
+            for _ in 1..tournament_size {
+                let contender = small_rng.gen_range(0..costs.len());
+                if costs[contender] < costs[best] {
+                    best = contender;
+                }
+            }
+            best
+        }
+        Selection::RouletteWheel => {
+            // Fitness is how much better than the worst individual, with a small offset so everyone has a chance
+            let worst = costs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let best = costs.iter().cloned().fold(f64::INFINITY, f64::min);
+            let offset = (worst - best) / costs.len() as f64 + f64::EPSILON;
+            let total_fitness: f64 = costs.iter().map(|cost| worst - cost + offset).sum();
+            let mut remaining = small_rng.gen::<f64>() * total_fitness;
+            for (index, cost) in costs.iter().enumerate() {
+                let fitness = worst - cost + offset;
+                if remaining < fitness {
+                    return index;
+                }
+                remaining -= fitness;
+            }
+            costs.len() - 1
+        }
+    }
+}
+
+/// Creates the given number of children by selection, crossover and mutation.
+/// Work is split over the threads, so the children are evaluated in parallel as well.
+pub fn create_offspring<C, M, T>(
+    population: &[T],
+    costs: &[f64],
+    parameters: &GeneticAlgorithmParameters,
+    num_children: usize,
+) -> Vec<T>
+where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + Sync,
+{
+    let num_threads = (parameters.num_threads.max(1) as usize).min(num_children.max(1));
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_threads)
+            .map(|thread| {
+                // Divide the children as evenly as possible
+                let count =
+                    num_children / num_threads + usize::from(thread < num_children % num_threads);
+                scope.spawn(move || {
+                    let mut small_rng = SmallRng::from_entropy();
+                    (0..count)
+                        .map(|_| {
+                            let a = select_parent(
+                                costs,
+                                parameters.selection,
+                                parameters.tournament_size,
+                                &mut small_rng,
+                            );
+                            let b = select_parent(
+                                costs,
+                                parameters.selection,
+                                parameters.tournament_size,
+                                &mut small_rng,
+                            );
+                            let mut child = C::crossover(&population[a], &population[b]);
+                            if small_rng.gen::<f64>() < parameters.mutation_probability {
+                                M::do_random_move(&mut child);
+                            }
+                            child.get_cost();
+                            child
+                        })
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Sorts the population from best to worst, returning the sorted costs
+pub fn sort_population<T: Solution>(population: &mut Vec<T>) -> Vec<f64> {
+    let mut with_costs: Vec<(f64, T)> = population
+        .drain(..)
+        .map(|mut individual| (individual.get_cost(), individual))
+        .collect();
+    with_costs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let costs = with_costs.iter().map(|(cost, _)| *cost).collect();
+    population.extend(with_costs.into_iter().map(|(_, individual)| individual));
+    costs
+}
+
+/// Generational genetic algorithm with elitism, evolves the population for the given number of generations or until stopped.
+/// The population is left sorted from best to worst.
+pub fn genetic_algorithm<C, M, T>(
+    population: &mut Vec<T>,
+    parameters: &GeneticAlgorithmParameters,
+    num_generations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + Sync,
+{
+    // Print some info
+    println!(
+        "{} - Running genetic algorithm for {} generations with population size {} and {} selection",
+        process_name,
+        num_generations,
+        population.len(),
+        parameters.selection
+    );
+
+    let mut costs = sort_population(population);
+    for generation in 0..num_generations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        costs = next_generation::<C, M, T>(population, &costs, parameters);
+
+        // print cost every so often
+        if generation % REPORT_STATUS_EVERY_GENERATION == 0 {
+            let average = costs.iter().sum::<f64>() / costs.len() as f64;
+            println!(
+                " {} - Generation {} - Best cost: {:.4} Average cost: {:.4}",
+                process_name, generation, costs[0], average
+            );
+        }
+    }
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, costs[0]);
+    M::report_statistics(&mut population[0], process_name);
+}
+
+/// Runs the generations of the genetic algorithm without reporting, as an epoch of the island model whose islands report instead
+pub fn genetic_algorithm_epoch<C, M, T>(
+    population: &mut Vec<T>,
+    parameters: &GeneticAlgorithmParameters,
+    num_generations: u32,
+    stop_signal: StopSignal,
+) where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + Sync,
+{
+    let mut costs = sort_population(population);
+    for _ in 0..num_generations {
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+        costs = next_generation::<C, M, T>(population, &costs, parameters);
+    }
+}
+
+/// Elites survive, the rest of the population is replaced by children. Returns the costs of the population, sorted like it
+fn next_generation<C, M, T>(
+    population: &mut Vec<T>,
+    costs: &[f64],
+    parameters: &GeneticAlgorithmParameters,
+) -> Vec<f64>
+where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + Sync,
+{
+    let num_elites = parameters.num_elites.min(population.len());
+    let offspring =
+        create_offspring::<C, M, T>(population, costs, parameters, population.len() - num_elites);
+    population.truncate(num_elites);
+    population.extend(offspring);
+    sort_population(population)
+}
+
+/// How parents are selected
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum Selection {
+    /// Best out of a number of random individuals
+    Tournament,
+    /// Random, proportional to how much better than the worst individual
+    RouletteWheel,
+}
@@ -0,0 +1,189 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{
+    GreedyConstruction, LocalRandomMove, RelinkingMoves, Solution, SolutionDistance,
+};
+
+use super::{
+    local_search::{LocalSearch, LocalSearchRunner},
+    SharedBest, StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 100;
+
+/// Parameters of GRASP
+#[derive(Clone, Copy, Debug)]
+pub struct GraspParameters {
+    /// Greediness of the construction, 0 is pure greedy and 1 is fully random
+    pub alpha: f64,
+    pub local_search: LocalSearch,
+    pub annealing_iterations: u32,
+    /// Maximum number of solutions in the elite pool
+    pub elite_pool_size: usize,
+    pub relinking: RelinkingDirection,
+}
+
+/// Walks from the initiating solution towards the guiding solution, every step taking the cheapest move that brings it closer.
+/// Returns the best solution on the path, not counting the two ends.
+pub fn path_relinking<G, T>(initiating: &T, guide: &T) -> Option<T>
+where
+    G: RelinkingMoves<T>,
+    T: Solution + SolutionDistance,
+{
+    let mut current = initiating.clone();
+    let mut best: Option<T> = None;
+    loop {
+        let moves = G::moves_towards(&current, guide);
+        let Some(cheapest) = moves
+            .iter()
+            .map(|m| (G::evaluate_move(&current, m), m))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, m)| m)
+        else {
+            break;
+        };
+        G::apply_move(&mut current, cheapest);
+
+        // The guide itself is not new
+        if current.distance_to(guide) == 0.0 {
+            break;
+        }
+        if best
+            .as_mut()
+            .is_none_or(|best| current.get_cost() < best.get_cost())
+        {
+            best = Some(current.clone());
+        }
+    }
+    best
+}
+
+/// Pool of the best distinct solutions found
+struct ElitePool<T> {
+    solutions: Vec<T>,
+    capacity: usize,
+}
+
+impl<T: Solution + SolutionDistance> ElitePool<T> {
+    /// Adds the candidate if it is not in the pool yet and the pool is not full or it is better than the worst.
+    /// When full, it replaces the most similar solution among the ones that are worse.
+    fn add(&mut self, candidate: &mut T) {
+        let cost = candidate.get_cost();
+        let distances: Vec<f64> = self
+            .solutions
+            .iter()
+            .map(|elite| candidate.distance_to(elite))
+            .collect();
+        if distances.contains(&0.0) {
+            return;
+        }
+        if self.solutions.len() < self.capacity {
+            self.solutions.push(candidate.clone());
+            return;
+        }
+        let most_similar_worse = (0..self.solutions.len())
+            .filter(|&k| cost < self.solutions[k].get_cost())
+            .min_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+        if let Some(k) = most_similar_worse {
+            self.solutions[k] = candidate.clone();
+        }
+    }
+}
+
+/// Greedy randomised adaptive search procedure, repeatedly constructs a solution with the randomised greedy construction and improves it by local search.
+/// The best distinct solutions are kept in an elite pool, new solutions are relinked with a random elite solution and the best solution on the path is improved as well.
+/// Runs until stopped, improvements are shared with the other threads through the shared best solution.
+pub fn grasp<B, G, M, T>(
+    solution: &mut T,
+    parameters: &GraspParameters,
+    process_name: &str,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    B: GreedyConstruction<T>,
+    G: RelinkingMoves<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance,
+{
+    let mut small_rng = SmallRng::from_entropy();
+    let runner = LocalSearchRunner::new::<M, T>(
+        parameters.local_search,
+        parameters.annealing_iterations,
+        solution,
+    );
+
+    // Print some info
+    println!(
+        "{} - Running GRASP with alpha {}, {} local search and {} path relinking",
+        process_name, parameters.alpha, parameters.local_search, parameters.relinking
+    );
+
+    // Main loop, runs until stopped
+    let mut elite_pool = ElitePool {
+        solutions: Vec::new(),
+        capacity: parameters.elite_pool_size.max(1),
+    };
+    let mut best_solution = solution.clone();
+    let mut it: u32 = 0;
+    while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        // Construct and improve
+        let mut candidate = B::construct(solution, parameters.alpha);
+        runner.run::<G, M, T>(&mut candidate, &mut small_rng, &stop_signal);
+
+        // Relink with a random elite solution, continue with the best of the path if that is better
+        if !elite_pool.solutions.is_empty() {
+            let elite = &elite_pool.solutions[small_rng.gen_range(0..elite_pool.solutions.len())];
+            let relinked = match parameters.relinking {
+                RelinkingDirection::Forward => path_relinking::<G, T>(&candidate, elite),
+                RelinkingDirection::Backward => path_relinking::<G, T>(elite, &candidate),
+                RelinkingDirection::Disabled => None,
+            };
+            if let Some(mut relinked) = relinked {
+                runner.run::<G, M, T>(&mut relinked, &mut small_rng, &stop_signal);
+                if relinked.get_cost() < candidate.get_cost() {
+                    candidate = relinked;
+                }
+            }
+        }
+        elite_pool.add(&mut candidate);
+
+        // Update best solutions
+        shared_best.update(&mut candidate);
+        if candidate.get_cost() < best_solution.get_cost() {
+            best_solution = candidate;
+        }
+
+        // print cost every so often
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            println!(
+                " {} - Iteration {} - Best cost: {:.4} Elite pool size: {} Shared best cost: {:.4}",
+                process_name,
+                it,
+                best_solution.get_cost(),
+                elite_pool.solutions.len(),
+                shared_best.get_cost(),
+            );
+        }
+        it = it.wrapping_add(1);
+    }
+
+    // Set the best solution
+    *solution = best_solution;
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    M::report_statistics(solution, process_name);
+}
+
+/// Direction of path relinking between a new solution and an elite solution
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum RelinkingDirection {
+    /// From the new solution towards the elite solution
+    Forward,
+    /// From the elite solution towards the new solution
+    Backward,
+    /// No path relinking, plain GRASP
+    Disabled,
+}
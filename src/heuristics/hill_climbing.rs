@@ -0,0 +1,116 @@
+use std::ops::ControlFlow;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{MoveGenerator, Solution};
+
+use super::{StopSignal, FLOAT_PRECISION};
+
+/// Deterministic local search, keeps applying improving moves until none are left.
+/// Returns the number of moves applied, the solution is left in a local optimum unless stopped early.
+pub fn hill_climbing<G, T>(
+    solution: &mut T,
+    strategy: HillClimbingStrategy,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> u32
+where
+    G: MoveGenerator<T>,
+    T: Solution,
+{
+    // Print some info
+    println!(
+        "{} - Running hill climbing ({}) from cost: {:.4}",
+        process_name,
+        strategy,
+        solution.get_cost()
+    );
+
+    // Main loop, step until no improving move is found
+    let mut small_rng = SmallRng::from_entropy();
+    let mut steps = 0;
+    while hill_climbing_step::<G, T>(solution, strategy, &mut small_rng) {
+        steps += 1;
+
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+    }
+
+    // Print final cost
+    println!(
+        "{} - Local optimum after {} steps, final cost: {}",
+        process_name,
+        steps,
+        solution.get_cost()
+    );
+    steps
+}
+
+/// Applies a single improving move, if any. Neighbours are considered starting from a random position.
+/// Returns whether an improving move was applied.
+pub fn hill_climbing_step<G, T>(
+    solution: &mut T,
+    strategy: HillClimbingStrategy,
+    small_rng: &mut SmallRng,
+) -> bool
+where
+    G: MoveGenerator<T>,
+    T: Solution,
+{
+    let start = small_rng.gen();
+
+    match strategy {
+        HillClimbingStrategy::BestImprovement => {
+            // Find the move with the largest decrease in cost
+            let mut best_move = None;
+            let mut best_delta = -FLOAT_PRECISION;
+            let _ = G::for_each_move(solution, start, |m| {
+                let delta = G::evaluate_move(solution, &m);
+                if delta < best_delta {
+                    best_delta = delta;
+                    best_move = Some(m);
+                }
+                ControlFlow::Continue(())
+            });
+
+            match best_move {
+                Some(m) => {
+                    G::apply_move(solution, &m);
+                    true
+                }
+                None => false,
+            }
+        }
+        HillClimbingStrategy::FirstImprovement => {
+            // Apply the first move that decreases the cost
+            let first_move = G::for_each_move(solution, start, |m| {
+                if G::evaluate_move(solution, &m) < -FLOAT_PRECISION {
+                    ControlFlow::Break(m)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+
+            match first_move {
+                ControlFlow::Break(m) => {
+                    G::apply_move(solution, &m);
+                    true
+                }
+                ControlFlow::Continue(()) => false,
+            }
+        }
+    }
+}
+
+/// Which improving move to pick in every step
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum HillClimbingStrategy {
+    /// Evaluate the complete neighbourhood, apply the best move
+    BestImprovement,
+    /// Apply the first improving move found
+    FirstImprovement,
+}
@@ -0,0 +1,190 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::Solution;
+
+use super::{genetic_algorithm::sort_population, SharedBest, StopSignal};
+
+/// How often every island reports its status, in epochs
+const REPORT_STATUS_EVERY_EPOCH: u32 = 10;
+
+/// Parameters of the island model
+#[derive(Clone, Copy, Debug)]
+pub struct IslandParameters {
+    /// Number of best individuals sent to other islands after every epoch
+    pub num_migrants: usize,
+    pub topology: Topology,
+    pub policy: MigrationPolicy,
+}
+
+/// Individuals that arrived from other islands replace individuals of the population
+fn integrate_migrants<T: Solution>(
+    population: &mut [T],
+    migrants: Vec<T>,
+    policy: MigrationPolicy,
+    small_rng: &mut SmallRng,
+) {
+    for mut migrant in migrants {
+        match policy {
+            MigrationPolicy::BestReplacesWorst => {
+                let worst = (0..population.len())
+                    .max_by(|&a, &b| {
+                        population[a]
+                            .get_cost()
+                            .total_cmp(&population[b].get_cost())
+                    })
+                    .unwrap();
+                if migrant.get_cost() < population[worst].get_cost() {
+                    population[worst] = migrant;
+                }
+            }
+            MigrationPolicy::Random => {
+                let index = small_rng.gen_range(0..population.len());
+                population[index] = migrant;
+            }
+        }
+    }
+}
+
+/// Runs a single island until stopped, alternating epochs of the heuristic with migration
+#[allow(clippy::too_many_arguments)]
+fn run_island<T, F>(
+    index: usize,
+    process_name: &str,
+    population: &mut Vec<T>,
+    parameters: &IslandParameters,
+    epoch: &F,
+    senders: &[Sender<Vec<T>>],
+    receiver: Receiver<Vec<T>>,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    T: Solution,
+    F: Fn(&mut Vec<T>, &str, &StopSignal),
+{
+    let num_islands = senders.len();
+    let mut small_rng = SmallRng::from_entropy();
+    let mut epoch_count: u32 = 0;
+    while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        epoch(population, process_name, &stop_signal);
+        sort_population(population);
+        shared_best.update(&mut population[0]);
+
+        // Send the best individuals along the topology, islands that already stopped are skipped
+        if num_islands > 1 {
+            let migrants = population[..parameters.num_migrants.min(population.len())].to_vec();
+            let targets: Vec<usize> = match parameters.topology {
+                Topology::Ring => vec![(index + 1) % num_islands],
+                Topology::Complete => (0..num_islands).filter(|&i| i != index).collect(),
+                Topology::Random => {
+                    let target = (index + small_rng.gen_range(1..num_islands)) % num_islands;
+                    vec![target]
+                }
+            };
+            for target in targets {
+                let _ = senders[target].send(migrants.clone());
+            }
+        }
+
+        // Take in the migrants that arrived in the meantime
+        while let Ok(migrants) = receiver.try_recv() {
+            integrate_migrants(population, migrants, parameters.policy, &mut small_rng);
+        }
+
+        // print cost every so often
+        if epoch_count % REPORT_STATUS_EVERY_EPOCH == 0 {
+            println!(
+                " {} - Epoch {} - Best cost: {:.4} Shared best cost: {:.4}",
+                process_name,
+                epoch_count,
+                population[0].get_cost(),
+                shared_best.get_cost(),
+            );
+        }
+        epoch_count = epoch_count.wrapping_add(1);
+    }
+
+    // Print final cost
+    println!(
+        "{} - Final cost: {}",
+        process_name,
+        population[0].get_cost()
+    );
+}
+
+/// Island model, every island runs the heuristic on its own population in a thread of its own.
+/// After every epoch the best individuals migrate to other islands along the topology, through channels.
+/// Runs until stopped, improvements are collected in the shared best solution.
+pub fn island_model<T, F>(
+    islands: Vec<Vec<T>>,
+    parameters: &IslandParameters,
+    epoch: F,
+    instance_name: &str,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    T: Solution,
+    F: Fn(&mut Vec<T>, &str, &StopSignal) + Sync,
+{
+    // Print some info
+    println!(
+        "Running island model with {} islands, {} topology and {} migration",
+        islands.len(),
+        parameters.topology,
+        parameters.policy
+    );
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = islands.iter().map(|_| channel()).unzip();
+    std::thread::scope(|scope| {
+        for (index, (mut population, receiver)) in islands.into_iter().zip(receivers).enumerate() {
+            let senders = senders.clone();
+            let stop_signal = stop_signal.clone();
+            let epoch = &epoch;
+            let process_name = instance_name.to_owned() + &index.to_string();
+            scope.spawn(move || {
+                run_island(
+                    index,
+                    &process_name,
+                    &mut population,
+                    parameters,
+                    epoch,
+                    &senders,
+                    receiver,
+                    stop_signal,
+                    shared_best,
+                );
+            });
+        }
+    });
+}
+
+/// Heuristic every island runs between migrations, the heuristics that can be resumed on a population for an epoch
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum IslandHeuristic {
+    /// Short annealing runs on a single solution
+    SimulatedAnnealing,
+    GeneticAlgorithm,
+    MemeticAlgorithm,
+}
+
+/// Which islands receive the migrants of an island
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum Topology {
+    /// The next island
+    Ring,
+    /// Every other island
+    Complete,
+    /// A random other island, different after every epoch
+    Random,
+}
+
+/// Which individuals the migrants replace
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum MigrationPolicy {
+    /// The worst individual, if the migrant is better
+    BestReplacesWorst,
+    /// A random individual
+    Random,
+}
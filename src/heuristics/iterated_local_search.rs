@@ -0,0 +1,132 @@
+use rand::{rngs::SmallRng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{LocalRandomMove, MoveGenerator, Solution};
+
+use super::{
+    local_search::{LocalSearch, LocalSearchRunner},
+    SharedBest, StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 100;
+
+/// How many times the perturbation strength is applied when restarting
+const RESTART_PERTURBATION_MULTIPLIER: u32 = 10;
+
+/// Iterated local search, repeatedly perturbs a local optimum and runs a local search on the result.
+/// The acceptance rule decides from which local optimum the next perturbation starts.
+/// Improvements are shared with the other threads through the shared best solution.
+#[allow(clippy::too_many_arguments)]
+pub fn iterated_local_search<G, M, P, T>(
+    solution: &mut T,
+    local_search: LocalSearch,
+    acceptance: IlsAcceptance,
+    perturbation_strength: u32,
+    stagnation_limit: u32,
+    annealing_iterations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    P: LocalRandomMove<T>,
+    T: Solution,
+{
+    let mut small_rng = SmallRng::from_entropy();
+
+    let runner = LocalSearchRunner::new::<M, T>(local_search, annealing_iterations, solution);
+
+    // Print some info
+    println!(
+        "{} - Running iterated local search with {} local search and {} acceptance",
+        process_name, local_search, acceptance
+    );
+
+    // Start from a local optimum
+    runner.run::<G, M, T>(solution, &mut small_rng, &stop_signal);
+    shared_best.update(solution);
+    let mut current = solution.clone();
+    let mut best_solution = solution.clone();
+
+    // Main loop, runs until stopped
+    let mut iterations_without_improvement = 0;
+    let mut it: u32 = 0;
+    while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        // Perturb the current local optimum and improve it again
+        let mut candidate = current.clone();
+        for _ in 0..perturbation_strength {
+            P::do_random_move(&mut candidate);
+        }
+        runner.run::<G, M, T>(&mut candidate, &mut small_rng, &stop_signal);
+
+        // Update best solutions
+        shared_best.update(&mut candidate);
+        if candidate.get_cost() < best_solution.get_cost() {
+            best_solution = candidate.clone();
+            iterations_without_improvement = 0;
+        } else {
+            iterations_without_improvement += 1;
+        }
+
+        // Decide where to continue from
+        match acceptance {
+            IlsAcceptance::Better => {
+                if candidate.get_cost() < current.get_cost() {
+                    current = candidate;
+                }
+            }
+            IlsAcceptance::RandomWalk => {
+                current = candidate;
+            }
+            IlsAcceptance::RestartOnStagnation => {
+                if candidate.get_cost() < current.get_cost() {
+                    current = candidate;
+                }
+
+                // Restart from a heavily perturbed copy of the best solution over all threads
+                if iterations_without_improvement >= stagnation_limit {
+                    current = shared_best.get();
+                    for _ in 0..perturbation_strength * RESTART_PERTURBATION_MULTIPLIER {
+                        P::do_random_move(&mut current);
+                    }
+                    runner.run::<G, M, T>(&mut current, &mut small_rng, &stop_signal);
+                    iterations_without_improvement = 0;
+                }
+            }
+        }
+
+        // print cost every so often
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            println!(
+                " {} - Iteration {} - Best cost: {:.4} Current cost: {:.4} Shared best cost: {:.4}",
+                process_name,
+                it,
+                best_solution.get_cost(),
+                current.get_cost(),
+                shared_best.get_cost(),
+            );
+        }
+        it = it.wrapping_add(1);
+    }
+
+    // Set the best solution
+    *solution = best_solution;
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    M::report_statistics(solution, process_name);
+    P::report_statistics(solution, process_name);
+}
+
+/// Acceptance rule, decides which local optimum is perturbed next
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum IlsAcceptance {
+    /// Only continue from the new local optimum if it is better
+    Better,
+    /// Always continue from the new local optimum
+    RandomWalk,
+    /// Like better, but restart from the shared best solution when stuck for too long
+    RestartOnStagnation,
+}
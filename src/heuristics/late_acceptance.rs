@@ -0,0 +1,129 @@
+use std::ops::ControlFlow;
+
+use strum_macros::{Display, EnumIter};
+
+use crate::{
+    heuristics::check_early_return,
+    solution::{LocalRandomMove, Solution},
+};
+
+use super::StopSignal;
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 10_000_000;
+
+/// Late acceptance hill climbing, accepts a move if it is not worse than the current solution
+/// or than the solution of `history_length` iterations ago.
+/// Step counting hill climbing is the variant that instead keeps a single cost bound, updated every `history_length` iterations.
+pub fn late_acceptance_hill_climbing<M, T>(
+    solution: &mut T,
+    num_iterations: u32,
+    history_length: u32,
+    variant: LateAcceptanceVariant,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    // History of costs, for step counting only the first entry is used as the bound
+    let history_length = history_length.max(1);
+    let mut history = vec![solution.get_cost(); history_length as usize];
+    let mut step_counter = 0;
+
+    // Setup early return
+    let mut early_return_counter = 0;
+    let mut last_status_check_cost = solution.get_cost();
+
+    // Print some info
+    println!(
+        "{} - Running {} for {} iterations with history length {}",
+        process_name, variant, num_iterations, history_length
+    );
+
+    // Main loop
+    let sense = solution.sense();
+    let mut previous_cost = solution.get_cost();
+    let mut best_solution = solution.clone();
+    for it in 0..num_iterations {
+        // Do the move
+        M::do_random_move(solution);
+        let new_cost = solution.get_cost();
+
+        // Index of the history entry to compare against
+        let v = match variant {
+            LateAcceptanceVariant::LateAcceptance => (it % history_length) as usize,
+            LateAcceptanceVariant::StepCounting => 0,
+        };
+
+        // Accept if not worse than the bound or the previous solution in the sense of the problem, otherwise undo
+        if sense.improvement(history[v], new_cost) >= 0.0
+            || sense.improvement(previous_cost, new_cost) >= 0.0
+        {
+            previous_cost = new_cost;
+        } else {
+            M::undo_last_move(solution);
+        }
+
+        // Update history
+        match variant {
+            LateAcceptanceVariant::LateAcceptance => {
+                if sense.is_better(previous_cost, history[v]) {
+                    history[v] = previous_cost;
+                }
+            }
+            LateAcceptanceVariant::StepCounting => {
+                step_counter += 1;
+                if step_counter >= history_length {
+                    history[v] = previous_cost;
+                    step_counter = 0;
+                }
+            }
+        }
+
+        // print cost every so often, check for early return
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            let percentage = (it as f64 / num_iterations as f64) * 100.0;
+            println!(
+                " {} - {:.0}% - Best cost: {:.4} Current cost: {:.4} ",
+                process_name,
+                percentage,
+                best_solution.get_cost(),
+                solution.get_cost(),
+            );
+
+            if let ControlFlow::Break(_) = check_early_return(
+                &stop_signal,
+                process_name,
+                solution,
+                &mut last_status_check_cost,
+                &mut early_return_counter,
+                it,
+                num_iterations,
+            ) {
+                break;
+            }
+        }
+
+        // Update best solution
+        if sense.is_better(solution.get_cost(), best_solution.get_cost()) {
+            best_solution = solution.clone();
+        }
+    }
+
+    // Set the best solution
+    *solution = best_solution;
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    M::report_statistics(solution, process_name);
+}
+
+/// Variants of late acceptance
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum LateAcceptanceVariant {
+    /// Late acceptance hill climbing, compares against the cost `history_length` iterations ago
+    LateAcceptance,
+    /// Step counting hill climbing, compares against a bound that is updated every `history_length` iterations
+    StepCounting,
+}
@@ -0,0 +1,95 @@
+use rand::rngs::SmallRng;
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{LocalRandomMove, MoveGenerator, Solution};
+
+use super::{
+    hill_climbing::{hill_climbing_step, HillClimbingStrategy},
+    simulated_annealing::{anneal, determine_start_and_end_temp, CoolingSchedule},
+    StopSignal,
+};
+
+/// Number of random moves used to determine the temperatures of the short annealing runs
+const NUM_ITERATIONS_TEMPERATURE_DETERMINING: u32 = 20_000;
+
+/// Local search used to improve solutions inside other heuristics
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum LocalSearch {
+    /// Hill climbing, applying the best move in every step
+    BestImprovement,
+    /// Hill climbing, applying the first improving move in every step
+    FirstImprovement,
+    /// Short simulated annealing run, keeping the best solution found
+    ShortAnnealing,
+}
+
+/// Runs the chosen local search, the temperatures for annealing are determined once up front.
+#[derive(Clone, Copy, Debug)]
+pub struct LocalSearchRunner {
+    local_search: LocalSearch,
+    annealing_iterations: u32,
+    starting_temperature: f64,
+    ending_temperature: f64,
+}
+
+impl LocalSearchRunner {
+    /// Determines the annealing temperatures on a copy of the solution, as that applies random moves
+    pub fn new<M, T>(local_search: LocalSearch, annealing_iterations: u32, solution: &T) -> Self
+    where
+        M: LocalRandomMove<T>,
+        T: Solution,
+    {
+        let (starting_temperature, ending_temperature) = match local_search {
+            LocalSearch::ShortAnnealing => determine_start_and_end_temp::<M, T>(
+                NUM_ITERATIONS_TEMPERATURE_DETERMINING,
+                &mut solution.clone(),
+                true,
+            ),
+            _ => (0.0, 0.0),
+        };
+        LocalSearchRunner {
+            local_search,
+            annealing_iterations: annealing_iterations.max(2),
+            starting_temperature,
+            ending_temperature,
+        }
+    }
+
+    /// Hill climbing checks the stop signal after every step, as reaching a local optimum can take long on large instances.
+    /// A short annealing run is bounded by its number of iterations.
+    pub fn run<G, M, T>(&self, solution: &mut T, small_rng: &mut SmallRng, stop_signal: &StopSignal)
+    where
+        G: MoveGenerator<T>,
+        M: LocalRandomMove<T>,
+        T: Solution,
+    {
+        match self.local_search {
+            LocalSearch::BestImprovement => {
+                while !stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+                    && hill_climbing_step::<G, T>(
+                        solution,
+                        HillClimbingStrategy::BestImprovement,
+                        small_rng,
+                    )
+                {}
+            }
+            LocalSearch::FirstImprovement => {
+                while !stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+                    && hill_climbing_step::<G, T>(
+                        solution,
+                        HillClimbingStrategy::FirstImprovement,
+                        small_rng,
+                    )
+                {}
+            }
+            LocalSearch::ShortAnnealing => anneal::<M, T>(
+                solution,
+                self.annealing_iterations,
+                self.starting_temperature,
+                self.ending_temperature,
+                CoolingSchedule::Exponential,
+                small_rng,
+            ),
+        }
+    }
+}
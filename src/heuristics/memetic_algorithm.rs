@@ -0,0 +1,198 @@
+use rand::{rngs::SmallRng, SeedableRng};
+
+use crate::solution::{Crossover, LocalRandomMove, MoveGenerator, Solution, SolutionDistance};
+
+use super::{
+    genetic_algorithm::{create_offspring, sort_population, GeneticAlgorithmParameters},
+    local_search::LocalSearchRunner,
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_GENERATION: u32 = 10;
+
+/// Improves every individual with the local search, split over the threads.
+pub fn improve_population<G, M, T>(
+    population: &mut [T],
+    runner: &LocalSearchRunner,
+    num_threads: u32,
+    stop_signal: &StopSignal,
+) where
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    if population.is_empty() {
+        return;
+    }
+    let chunk_size = population.len().div_ceil(num_threads.max(1) as usize);
+    std::thread::scope(|scope| {
+        for chunk in population.chunks_mut(chunk_size) {
+            scope.spawn(move || {
+                let mut small_rng = SmallRng::from_entropy();
+                for individual in chunk {
+                    runner.run::<G, M, T>(individual, &mut small_rng, stop_signal);
+                    individual.get_cost();
+                }
+            });
+        }
+    });
+}
+
+/// Index and distance of the individual closest to the candidate
+fn nearest_individual<T: SolutionDistance>(population: &[T], candidate: &T) -> (usize, f64) {
+    population
+        .iter()
+        .enumerate()
+        .map(|(index, individual)| (index, candidate.distance_to(individual)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap()
+}
+
+/// Memetic algorithm, a genetic algorithm where every child is improved by a short local search.
+/// Children replace the individual closest to them if they are better, otherwise the worst individual,
+/// and duplicates are discarded, which keeps the population diverse. Individuals are only replaced by better children.
+/// The population is left sorted from best to worst.
+pub fn memetic_algorithm<C, G, M, T>(
+    population: &mut Vec<T>,
+    parameters: &GeneticAlgorithmParameters,
+    runner: &LocalSearchRunner,
+    num_generations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    C: Crossover<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance + Sync,
+{
+    // Print some info
+    println!(
+        "{} - Running memetic algorithm for {} generations with population size {} and {} selection",
+        process_name,
+        num_generations,
+        population.len(),
+        parameters.selection
+    );
+
+    // Start from local optima
+    improve_population::<G, M, T>(population, runner, parameters.num_threads, &stop_signal);
+    let mut costs = sort_population(population);
+
+    let num_children = num_children(population, parameters);
+    for generation in 0..num_generations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        let replaced = next_generation::<C, G, M, T>(
+            population,
+            &mut costs,
+            parameters,
+            runner,
+            num_children,
+            &stop_signal,
+        );
+
+        // print cost every so often
+        if generation % REPORT_STATUS_EVERY_GENERATION == 0 {
+            let average = costs.iter().sum::<f64>() / costs.len() as f64;
+            println!(
+                " {} - Generation {} - Best cost: {:.4} Average cost: {:.4} Replaced: {}",
+                process_name, generation, costs[0], average, replaced
+            );
+        }
+    }
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, costs[0]);
+    M::report_statistics(&mut population[0], process_name);
+}
+
+/// Runs the generations of the memetic algorithm without reporting, as an epoch of the island model whose islands report instead.
+/// The population should already consist of local optima, see improve_population, only the children are improved.
+pub fn memetic_algorithm_epoch<C, G, M, T>(
+    population: &mut Vec<T>,
+    parameters: &GeneticAlgorithmParameters,
+    runner: &LocalSearchRunner,
+    num_generations: u32,
+    stop_signal: StopSignal,
+) where
+    C: Crossover<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance + Sync,
+{
+    let mut costs = sort_population(population);
+    let num_children = num_children(population, parameters);
+    for _ in 0..num_generations {
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+        next_generation::<C, G, M, T>(
+            population,
+            &mut costs,
+            parameters,
+            runner,
+            num_children,
+            &stop_signal,
+        );
+    }
+}
+
+/// Every generation creates as many children as there are non-elite individuals
+fn num_children<T>(population: &[T], parameters: &GeneticAlgorithmParameters) -> usize {
+    population
+        .len()
+        .saturating_sub(parameters.num_elites)
+        .max(1)
+}
+
+/// Creates and improves the children, which replace individuals by distance based replacement.
+/// The costs are updated and sorted along with the population. Returns the number of individuals replaced.
+fn next_generation<C, G, M, T>(
+    population: &mut Vec<T>,
+    costs: &mut Vec<f64>,
+    parameters: &GeneticAlgorithmParameters,
+    runner: &LocalSearchRunner,
+    num_children: usize,
+    stop_signal: &StopSignal,
+) -> usize
+where
+    C: Crossover<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance + Sync,
+{
+    // Create and improve the children
+    let mut offspring = create_offspring::<C, M, T>(population, costs, parameters, num_children);
+    improve_population::<G, M, T>(&mut offspring, runner, parameters.num_threads, stop_signal);
+
+    // Distance based replacement
+    let mut replaced = 0;
+    for mut child in offspring {
+        let child_cost = child.get_cost();
+        let (nearest, distance) = nearest_individual(population, &child);
+        if distance == 0.0 {
+            continue;
+        }
+        // Better than the nearest individual replaces it, otherwise it may replace the worst
+        let worst = (0..costs.len())
+            .max_by(|&a, &b| costs[a].total_cmp(&costs[b]))
+            .unwrap();
+        let target = if child_cost < costs[nearest] {
+            nearest
+        } else if child_cost < costs[worst] {
+            worst
+        } else {
+            continue;
+        };
+        population[target] = child;
+        costs[target] = child_cost;
+        replaced += 1;
+    }
+    *costs = sort_population(population);
+    replaced
+}
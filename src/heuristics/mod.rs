@@ -1,24 +1,163 @@
 use std::{
+    fmt::Display,
     ops::ControlFlow,
-    sync::{atomic::AtomicBool, Arc},
+    sync::{atomic::AtomicBool, Arc, Mutex},
+    time::{Duration, Instant},
 };
 
-use dialoguer::{theme::ColorfulTheme, Input};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use rand::{rngs::SmallRng, SeedableRng};
+use strum::IntoEnumIterator;
 
 use crate::{
     get_thread_count,
-    solution::{LocalRandomMove, Solution},
+    solution::{
+        Crossover, GreedyConstruction, LocalRandomMove, MoveGenerator, MultiObjectiveSolution,
+        PartialSolution, RealVectorSolution, RelinkingMoves, Solution, SolutionDistance,
+        TourSolution,
+    },
 };
 
 use crate::heuristics::tempering::tempering;
+use adaptive_large_neighbourhood_search::{
+    adaptive_large_neighbourhood_search, DestroyOperators, RepairOperators,
+};
+use branch_and_bound::{branch_and_bound, BranchAndBoundParameters};
+use cma_es::{cma_es, CmaEsParameters};
+use constraint_handling::{
+    constrained_simulated_annealing, is_feasible, ConstraintHandler, ConstraintHandling,
+};
+use constructive_search::{
+    beam_search, complete_greedily, greedy_lookahead, pilot_method, ConstructiveMethod,
+};
+use differential_evolution::{differential_evolution, DifferentialEvolutionParameters};
+use edge_assembly_crossover::{
+    eax_initial_population, edge_assembly_crossover, near_neighbours, ESetStrategy, EaxParameters,
+};
+use genetic_algorithm::{
+    genetic_algorithm, genetic_algorithm_epoch, initial_population, GeneticAlgorithmParameters,
+    Selection,
+};
+use grasp::{grasp, GraspParameters};
+use hill_climbing::{hill_climbing, HillClimbingStrategy};
+use island_model::{island_model, IslandHeuristic, IslandParameters};
+use iterated_local_search::{iterated_local_search, IlsAcceptance};
+use late_acceptance::{late_acceptance_hill_climbing, LateAcceptanceVariant};
+use local_search::{LocalSearch, LocalSearchRunner};
+use memetic_algorithm::{improve_population, memetic_algorithm, memetic_algorithm_epoch};
+use monte_carlo_tree_search::{monte_carlo_tree_search, MctsParameters, MctsVariant, Rollout};
+use multi_objective_annealing::multi_objective_simulated_annealing;
+use nsga_ii::{nsga_ii, NsgaIiParameters};
+use pareto::ParetoArchive;
+use population_annealing::{population_annealing, PopulationAnnealingParameters};
+use portfolio::{portfolio, report_improvements, PortfolioHeuristic, PortfolioParameters};
 use simulated_annealing::simulated_annealing;
+use variable_neighbourhood_search::{variable_neighbourhood_search, Neighbourhoods, VnsVariant};
 
+pub mod adaptive_large_neighbourhood_search;
+pub mod adaptive_weights;
+pub mod branch_and_bound;
+pub mod cma_es;
+pub mod constraint_handling;
+pub mod constructive_search;
+pub mod differential_evolution;
+pub mod edge_assembly_crossover;
+pub mod genetic_algorithm;
+pub mod grasp;
+pub mod hill_climbing;
+pub mod island_model;
+pub mod iterated_local_search;
+pub mod late_acceptance;
+pub mod local_search;
+pub mod memetic_algorithm;
+pub mod monte_carlo_tree_search;
+pub mod multi_objective_annealing;
+pub mod nsga_ii;
+pub mod pareto;
+pub mod population_annealing;
+pub mod portfolio;
+pub mod real_vector;
 pub mod simulated_annealing;
 pub mod tempering;
+pub mod variable_neighbourhood_search;
 
 /// Stop signal for all threads
 pub type StopSignal = Arc<AtomicBool>;
 
+/// Best solution found over all threads, cheap to clone as it is shared
+pub struct SharedBest<T> {
+    best: Arc<Mutex<T>>,
+    history: Arc<Mutex<Vec<Improvement>>>,
+    start: Instant,
+}
+
+/// Improvement of the shared best solution
+#[derive(Clone, Debug)]
+pub struct Improvement {
+    /// Time since the shared best solution was created
+    pub time: Duration,
+    /// Name of the thread that found the improvement
+    pub source: String,
+    pub cost: f64,
+}
+
+impl<T: Solution> SharedBest<T> {
+    pub fn new(solution: T) -> Self {
+        SharedBest {
+            best: Arc::new(Mutex::new(solution)),
+            history: Arc::new(Mutex::new(Vec::new())),
+            start: Instant::now(),
+        }
+    }
+
+    /// Replaces the shared best solution by the candidate if it is better, ignoring floating point noise. Returns whether it was replaced.
+    /// Every replacement is recorded in the history, together with the name of the current thread.
+    pub fn update(&self, candidate: &mut T) -> bool {
+        let mut best = self.best.lock().unwrap();
+        let improvement = candidate
+            .sense()
+            .improvement(best.get_cost(), candidate.get_cost());
+        if improvement > FLOAT_PRECISION {
+            *best = candidate.clone();
+            self.history.lock().unwrap().push(Improvement {
+                time: self.start.elapsed(),
+                source: std::thread::current()
+                    .name()
+                    .unwrap_or("unnamed")
+                    .to_owned(),
+                cost: best.get_cost(),
+            });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns all improvements of the shared best solution so far, in order
+    pub fn history(&self) -> Vec<Improvement> {
+        self.history.lock().unwrap().clone()
+    }
+
+    /// Returns a copy of the shared best solution
+    pub fn get(&self) -> T {
+        self.best.lock().unwrap().clone()
+    }
+
+    pub fn get_cost(&self) -> f64 {
+        self.best.lock().unwrap().get_cost()
+    }
+}
+
+impl<T> Clone for SharedBest<T> {
+    fn clone(&self) -> Self {
+        SharedBest {
+            best: self.best.clone(),
+            history: self.history.clone(),
+            start: self.start,
+        }
+    }
+}
+
 /// How many status checks need to be the same before early returning
 const EARLY_RETURN_TIMES: u32 = 5;
 
@@ -28,6 +167,105 @@ const FLOAT_PRECISION: f64 = 10e-6;
 /// Default number of iterations to run iterative algorithms for
 const DEFAULT_NUMBER_OF_ITERATIONS: u32 = 500_000_000;
 
+/// Default number of perturbation moves applied per iterated local search iteration, also used for shaking
+const DEFAULT_PERTURBATION_STRENGTH: u32 = 1;
+
+/// Default number of non-improving iterations before iterated local search restarts
+const DEFAULT_STAGNATION_LIMIT: u32 = 1_000;
+
+/// Default number of iterations of the short annealing runs used as local search
+const DEFAULT_SHORT_ANNEALING_ITERATIONS: u32 = 100_000;
+
+/// Default number of iterations for adaptive large neighbourhood search, iterations are a lot more expensive than single moves
+const DEFAULT_NUMBER_OF_ALNS_ITERATIONS: u32 = 100_000;
+
+/// Default maximum number of elements removed by a destroy operator
+const DEFAULT_MAX_DESTROY_DEGREE: usize = 30;
+
+/// Default population size for population based heuristics
+const DEFAULT_POPULATION_SIZE: usize = 100;
+
+/// Default number of children created from every pair of parents by the edge assembly crossover
+const DEFAULT_NUMBER_OF_EAX_CHILDREN: usize = 30;
+
+/// Default number of annealing iterations islands run between migrations
+const DEFAULT_MIGRATION_INTERVAL_ITERATIONS: u32 = 1_000_000;
+
+/// Default number of generations islands run between migrations
+const DEFAULT_MIGRATION_INTERVAL_GENERATIONS: u32 = 50;
+
+/// Default number of individuals an island sends to others after every epoch
+const DEFAULT_NUMBER_OF_MIGRANTS: usize = 1;
+
+/// Default number of iterations of annealing and late acceptance in the portfolio before sharing the solution
+const DEFAULT_PORTFOLIO_ROUND_ITERATIONS: u32 = 10_000_000;
+
+/// Default number of replicas for population annealing
+const DEFAULT_NUMBER_OF_REPLICAS: usize = 1_000;
+
+/// Default number of temperature steps for population annealing
+const DEFAULT_NUMBER_OF_TEMPERATURE_STEPS: u32 = 1_000;
+
+/// Default number of metropolis steps every replica makes per temperature step
+const DEFAULT_SWEEPS_PER_STEP: u32 = 10_000;
+
+/// Default greediness of the GRASP construction, 0 is pure greedy and 1 is fully random
+const DEFAULT_RCL_ALPHA: f64 = 0.2;
+
+/// Default maximum number of solutions in the GRASP elite pool
+const DEFAULT_ELITE_POOL_SIZE: usize = 10;
+
+/// Default number of partial solutions kept by beam search
+const DEFAULT_BEAM_WIDTH: usize = 100;
+
+/// Default number of steps greedy with lookahead looks ahead
+const DEFAULT_LOOKAHEAD_DEPTH: u32 = 2;
+
+/// Default number of best scoring extensions looked at further by lookahead and the pilot method
+const DEFAULT_NUMBER_OF_CANDIDATES: usize = 5;
+
+/// Default weight of the exploration term of UCT, relative to the normalised mean cost
+const DEFAULT_EXPLORATION_CONSTANT: f64 = 0.5;
+
+/// Default number of UCT iterations before committing to the best extension of the root
+const DEFAULT_MCTS_ITERATIONS_PER_STEP: u32 = 1_000;
+
+/// Default nesting level of NRPA
+const DEFAULT_NRPA_LEVEL: u32 = 3;
+
+/// Default number of searches of the level below for every NRPA search
+const DEFAULT_NRPA_ITERATIONS: u32 = 100;
+
+/// Default maximum number of nodes branch and bound explores, 0 for no limit
+const DEFAULT_NODE_LIMIT: u64 = 0;
+
+/// Default maximum running time of branch and bound in seconds, 0 for no limit
+const DEFAULT_TIME_LIMIT_SECONDS: u64 = 0;
+
+/// Default scale factor of differential evolution, the starting mean for JADE
+const DEFAULT_SCALE_FACTOR: f64 = 0.5;
+
+/// Default crossover rate of differential evolution, the starting mean for JADE
+const DEFAULT_CROSSOVER_RATE: f64 = 0.9;
+
+/// Default maximum number of solutions in a Pareto archive, 0 for no limit
+const DEFAULT_ARCHIVE_SIZE: usize = 100;
+
+/// Default number of iterations of multi-objective simulated annealing, every iteration evaluates all objectives
+const DEFAULT_MOSA_ITERATIONS: u32 = 10_000_000;
+
+/// Default weight of a unit of constraint violation, in units of cost
+const DEFAULT_PENALTY_WEIGHT: f64 = 100.0;
+
+/// Folder for solutions that violate the constraints, so they are not mistaken for valid ones
+const INFEASIBLE_OUTPUT_PATH: &str = "output/infeasible";
+
+/// Default number of generations for population based heuristics
+const DEFAULT_NUMBER_OF_GENERATIONS: u32 = 10_000;
+
+/// Default history length for late acceptance
+const DEFAULT_HISTORY_LENGTH: u32 = 5_000;
+
 /// Function to handle early return in heuristics
 fn check_early_return<T>(
     stop_signal: &StopSignal,
@@ -79,42 +317,114 @@ pub fn setup_simulated_annealing<M: LocalRandomMove<T>, T: Solution + 'static>(
     let number_of_threads = ask_user_num_threads(num_cpus, None);
 
     // Ask the user for the number of iterations
-    let number_of_iterations = ask_user_num_iterations();
+    let number_of_iterations = ask_user_num_iterations(None);
 
-    // necessary for borrowing in closure
-    let stop_signal_clone = stop_signal.clone();
+    run_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |solution, process_name, stop_signal| {
+            simulated_annealing::<M, T>(
+                solution,
+                number_of_iterations,
+                20_000,
+                crate::heuristics::simulated_annealing::CoolingSchedule::Exponential,
+                false,
+                process_name,
+                stop_signal,
+            );
+        },
+    );
+}
 
-    ctrlc::set_handler(move || {
-        println!("Received stop signal, stopping all threads");
-        stop_signal_clone.store(true, std::sync::atomic::Ordering::SeqCst);
-    })
-    .expect("Error setting Ctrl-C handler");
+pub fn setup_constrained_simulated_annealing<M: LocalRandomMove<T>, T: Solution + 'static>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) {
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
 
-    // Spawn threads
-    let handles: Vec<_> = (0..number_of_threads)
-        .map(|i| {
-            let solution = solution.clone();
-            let name: String = instance_name.to_owned() + &i.to_string();
-            let stop_signal = stop_signal.clone();
-            std::thread::spawn(move || {
-                infinite_loop::<M, T>(solution, number_of_iterations, &name, stop_signal);
-            })
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the number of iterations and how to handle the constraints
+    let number_of_iterations = ask_user_num_iterations(None);
+    let strategy: ConstraintHandling = ask_user_choice("Select a constraint handling strategy");
+    let penalty_weight: f64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Penalty weight of a unit of violation, the starting weight if adaptive (enter to use default)")
+        .default(DEFAULT_PENALTY_WEIGHT)
+        .validate_with(|weight: &f64| -> Result<(), &str> {
+            // The adaptive penalty scales the weight, which gets stuck at 0 and cannot be bounded when negative
+            if weight.is_finite() && *weight > 0.0 {
+                Ok(())
+            } else {
+                Err("The penalty weight must be positive")
+            }
         })
-        .collect();
+        .interact_text()
+        .unwrap();
 
-    // Print controls
-    println!("Press Ctrl+C to stop the program, all threads will stop and save their best solution to output/");
+    run_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |solution, process_name, stop_signal| {
+            let mut handler = ConstraintHandler::new(strategy, solution.sense(), penalty_weight);
+            constrained_simulated_annealing::<M, T>(
+                solution,
+                number_of_iterations,
+                &mut handler,
+                process_name,
+                stop_signal,
+            );
+        },
+    );
+}
 
-    // Wait for all threads to finish
-    for handle in handles {
-        handle.join().unwrap();
-    }
-    // Should only get here when Ctrl+C is pressed
+pub fn setup_late_acceptance<M: LocalRandomMove<T>, T: Solution + 'static>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) {
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
 
-    println!("All threads stopped, exiting.");
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the number of iterations
+    let number_of_iterations = ask_user_num_iterations(None);
+
+    // Ask the user for the variant and its only parameter, the history length
+    let variant: LateAcceptanceVariant = ask_user_choice("Select a variant");
+    let history_length: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("History length (enter to use default)")
+        .default(DEFAULT_HISTORY_LENGTH)
+        .interact_text()
+        .unwrap();
+
+    run_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |solution, process_name, stop_signal| {
+            late_acceptance_hill_climbing::<M, T>(
+                solution,
+                number_of_iterations,
+                history_length,
+                variant,
+                process_name,
+                stop_signal,
+            );
+        },
+    );
 }
 
-pub fn setup_tempering<M: LocalRandomMove<T>, T: Solution + 'static>(
+pub fn setup_hill_climbing<G: MoveGenerator<T>, T: Solution + 'static>(
     instance_name: &str,
     solution: T,
     stop_signal: StopSignal,
@@ -123,70 +433,1225 @@ pub fn setup_tempering<M: LocalRandomMove<T>, T: Solution + 'static>(
     let num_cpus = get_thread_count();
 
     // Ask for the number of threads to utilize
-    let number_of_threads = ask_user_num_threads(num_cpus, Some("Number of threads (enter to use default). Tempering requires at least 2 threads but only makes sense with more."));
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
 
-    // necessary for borrowing in closure
-    let stop_signal_clone = stop_signal.clone();
+    // Ask the user for the strategy
+    let strategy: HillClimbingStrategy = ask_user_choice("Select a strategy");
 
-    ctrlc::set_handler(move || {
-        println!("Received stop signal, stopping all threads");
-        stop_signal_clone.store(true, std::sync::atomic::Ordering::SeqCst);
-    })
-    .expect("Error setting Ctrl-C handler");
+    // Hill climbing ends in a local optimum, so every thread only runs once
+    spawn_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |mut solution, process_name, stop_signal| {
+            hill_climbing::<G, T>(&mut solution, strategy, process_name, stop_signal);
+            solution.write_solution("output");
+        },
+    );
+}
 
-    // Print controls
-    println!("Press Ctrl+C to stop the program, all threads will stop and the best solution will be saved to output/");
+pub fn setup_iterated_local_search<G, M, P, T>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) where
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    P: LocalRandomMove<T>,
+    T: Solution + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
 
-    // Do tempering
-    let mut solution = solution.clone();
-    tempering::<M, T>(
-        &mut solution,
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the components and their parameters
+    let (local_search, annealing_iterations) = ask_user_local_search();
+    let acceptance: IlsAcceptance = ask_user_choice("Select an acceptance rule");
+    let stagnation_limit = match acceptance {
+        IlsAcceptance::RestartOnStagnation => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(
+                "Number of iterations without improvement before restarting (enter to use default)",
+            )
+            .default(DEFAULT_STAGNATION_LIMIT)
+            .interact_text()
+            .unwrap(),
+        _ => u32::MAX,
+    };
+    let perturbation_strength: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of perturbation moves per iteration (enter to use default)")
+        .default(DEFAULT_PERTURBATION_STRENGTH)
+        .interact_text()
+        .unwrap();
+
+    // Threads only improve the shared best solution, which is saved at the end
+    let shared_best = SharedBest::new(solution.clone());
+    let thread_shared_best = shared_best.clone();
+    spawn_threads(
+        instance_name,
+        solution,
         number_of_threads,
-        50_000,
-        crate::heuristics::simulated_annealing::CoolingSchedule::Linear,
+        stop_signal,
+        move |mut solution, process_name, stop_signal| {
+            iterated_local_search::<G, M, P, T>(
+                &mut solution,
+                local_search,
+                acceptance,
+                perturbation_strength,
+                stagnation_limit,
+                annealing_iterations,
+                process_name,
+                stop_signal,
+                &thread_shared_best,
+            );
+        },
+    );
+    shared_best.get().write_solution("output");
+}
+
+pub fn setup_variable_neighbourhood_search<N: Neighbourhoods<T>, T: Solution + 'static>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) {
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the variant and its parameters
+    let variant: VnsVariant = ask_user_choice("Select a variant");
+    let strategy: HillClimbingStrategy = ask_user_choice("Select a local search strategy");
+    let shake_strength = match variant {
+        VnsVariant::Descent => 0,
+        _ => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Number of random moves per shake (enter to use default)")
+            .default(DEFAULT_PERTURBATION_STRENGTH)
+            .interact_text()
+            .unwrap(),
+    };
+
+    // Runs until stopped, or until a local optimum is found for the descent variant
+    spawn_threads(
         instance_name,
-        stop_signal.clone(),
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |mut solution, process_name, stop_signal| {
+            variable_neighbourhood_search::<N, T>(
+                &mut solution,
+                variant,
+                strategy,
+                shake_strength,
+                process_name,
+                stop_signal,
+            );
+            solution.write_solution("output");
+        },
+    );
+}
+
+pub fn setup_adaptive_large_neighbourhood_search<D, R, T>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) where
+    D: DestroyOperators<T>,
+    R: RepairOperators<T>,
+    T: Solution + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the number of iterations and how much to destroy
+    let number_of_iterations = ask_user_num_iterations(Some(DEFAULT_NUMBER_OF_ALNS_ITERATIONS));
+    let max_destroy_degree: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Maximum number of elements to destroy (enter to use default)")
+        .default(DEFAULT_MAX_DESTROY_DEGREE)
+        .interact_text()
+        .unwrap();
+
+    run_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |solution, process_name, stop_signal| {
+            adaptive_large_neighbourhood_search::<D, R, T>(
+                solution,
+                number_of_iterations,
+                max_destroy_degree,
+                process_name,
+                stop_signal,
+            );
+        },
+    );
+}
+
+pub fn setup_genetic_algorithm<C, M, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let parameters = ask_user_genetic_algorithm_parameters(number_of_threads);
+    let number_of_generations = ask_user_num_generations();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution will be saved to output/");
+
+    // Evolve the population, the best individual ends up in front
+    let mut population = initial_population::<M, T>(&solution, parameters.population_size);
+    genetic_algorithm::<C, M, T>(
+        &mut population,
+        &parameters,
+        number_of_generations,
+        instance_name,
+        stop_signal,
     );
+    population[0].write_solution("output");
 
     println!("All threads stopped, exiting.");
 }
 
-/// TODO: improve this, generalize to just take a closure?
-fn infinite_loop<M: LocalRandomMove<T>, T: Solution>(
-    mut solution: T,
-    number_of_iterations: u32,
-    process_name: &str,
+pub fn setup_memetic_algorithm<C, G, M, T>(
+    instance_name: &str,
+    solution: T,
     stop_signal: StopSignal,
-) {
-    // Main loop, run algo until cancelled
-    loop {
-        simulated_annealing::<M, T>(
-            &mut solution,
-            number_of_iterations,
-            20_000,
-            crate::heuristics::simulated_annealing::CoolingSchedule::Exponential,
-            false,
-            process_name,
-            stop_signal.clone(),
-        );
-        solution.write_solution("output");
+) where
+    C: Crossover<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
 
-        // Check stop signal
-        if stop_signal.load(std::sync::atomic::Ordering::SeqCst) {
-            break;
-        }
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let parameters = ask_user_genetic_algorithm_parameters(number_of_threads);
+    let (local_search, annealing_iterations) = ask_user_local_search();
+    let number_of_generations = ask_user_num_generations();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution will be saved to output/");
+
+    // Evolve the population, the best individual ends up in front
+    let runner = LocalSearchRunner::new::<M, T>(local_search, annealing_iterations, &solution);
+    let mut population = initial_population::<M, T>(&solution, parameters.population_size);
+    memetic_algorithm::<C, G, M, T>(
+        &mut population,
+        &parameters,
+        &runner,
+        number_of_generations,
+        instance_name,
+        stop_signal,
+    );
+    population[0].write_solution("output");
+
+    println!("All threads stopped, exiting.");
+}
+
+pub fn setup_edge_assembly_crossover<T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    T: TourSolution + Sync + 'static,
+{
+    // The AB-cycles and the 2-opt of the initial population ignore the direction of the edges
+    if !solution.is_symmetric() {
+        println!("Edge assembly crossover only supports symmetric distances, choose another heuristic for this asymmetric instance");
+        return;
     }
+
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let parameters = ask_user_eax_parameters(number_of_threads);
+    let number_of_generations = ask_user_num_generations();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution will be saved to output/");
+
+    // Evolve a population of 2-opt optimised tours, the best individual ends up in front
+    let neighbours = near_neighbours(&solution, number_of_threads);
+    let mut population = eax_initial_population(&solution, &neighbours, &parameters);
+    edge_assembly_crossover(
+        &mut population,
+        &neighbours,
+        &parameters,
+        number_of_generations,
+        instance_name,
+        stop_signal,
+    );
+    population[0].write_solution("output");
+
+    println!("All threads stopped, exiting.");
 }
 
-fn ask_user_num_iterations() -> u32 {
-    let number_of_iterations: u32 = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Number of iterations (enter to use default)")
-        .default((DEFAULT_NUMBER_OF_ITERATIONS as u32).into())
-        .interact_text()
-        .unwrap();
+pub fn setup_island_model<C, G, M, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    C: Crossover<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize, every thread is an island
+    let number_of_threads = ask_user_num_threads(
+        num_cpus,
+        Some("Number of threads (enter to use default). Every thread runs an island."),
+    );
+
+    // Ask the user for the heuristic of the islands and its parameters, every island uses a single thread
+    let heuristic: IslandHeuristic = ask_user_choice(
+        "Select the heuristic of the islands, only these can run in epochs between migrations",
+    );
+    let (population_size, interval_prompt, default_interval) = match heuristic {
+        IslandHeuristic::SimulatedAnnealing => (
+            1,
+            "Number of iterations between migrations (enter to use default)",
+            DEFAULT_MIGRATION_INTERVAL_ITERATIONS,
+        ),
+        _ => (
+            0,
+            "Number of generations between migrations (enter to use default)",
+            DEFAULT_MIGRATION_INTERVAL_GENERATIONS,
+        ),
+    };
+    let migration_interval: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(interval_prompt)
+        .default(default_interval)
+        .interact_text()
+        .unwrap();
+    let genetic_algorithm_parameters = match heuristic {
+        IslandHeuristic::SimulatedAnnealing => None,
+        _ => Some(ask_user_genetic_algorithm_parameters(1)),
+    };
+    let local_search = match heuristic {
+        IslandHeuristic::SimulatedAnnealing => {
+            Some((LocalSearch::ShortAnnealing, migration_interval))
+        }
+        IslandHeuristic::GeneticAlgorithm => None,
+        IslandHeuristic::MemeticAlgorithm => Some(ask_user_local_search()),
+    };
+    let parameters = IslandParameters {
+        num_migrants: Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Number of migrants per epoch (enter to use default)")
+            .default(DEFAULT_NUMBER_OF_MIGRANTS)
+            .interact_text()
+            .unwrap(),
+        topology: ask_user_choice("Select a migration topology"),
+        policy: ask_user_choice("Select a migration policy"),
+    };
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, all islands will stop and the best solution will be saved to output/");
+
+    // Create the islands
+    let population_size = genetic_algorithm_parameters
+        .map_or(population_size, |parameters| parameters.population_size);
+    let mut islands: Vec<Vec<T>> = (0..number_of_threads)
+        .map(|_| initial_population::<M, T>(&solution, population_size))
+        .collect();
+
+    // Only the islands of simulated annealing and the memetic algorithm run a local search
+    let runner = local_search.map(|(local_search, annealing_iterations)| {
+        LocalSearchRunner::new::<M, T>(local_search, annealing_iterations, &solution)
+    });
+
+    // Memetic islands start from local optima, their epochs only improve the children
+    if let (IslandHeuristic::MemeticAlgorithm, Some(runner)) = (heuristic, &runner) {
+        for island in islands.iter_mut() {
+            improve_population::<G, M, T>(island, runner, number_of_threads, &stop_signal);
+        }
+    }
+
+    // Every epoch runs the heuristic for one migration interval, the islands report instead of the heuristic
+    let epoch = |population: &mut Vec<T>, _process_name: &str, stop_signal: &StopSignal| match (
+        heuristic,
+        &genetic_algorithm_parameters,
+        &runner,
+    ) {
+        (IslandHeuristic::GeneticAlgorithm, Some(parameters), _) => {
+            genetic_algorithm_epoch::<C, M, T>(
+                population,
+                parameters,
+                migration_interval,
+                stop_signal.clone(),
+            );
+        }
+        (IslandHeuristic::MemeticAlgorithm, Some(parameters), Some(runner)) => {
+            memetic_algorithm_epoch::<C, G, M, T>(
+                population,
+                parameters,
+                runner,
+                migration_interval,
+                stop_signal.clone(),
+            );
+        }
+        (IslandHeuristic::SimulatedAnnealing, _, Some(runner)) => {
+            let mut small_rng = SmallRng::from_entropy();
+            for individual in population.iter_mut() {
+                runner.run::<G, M, T>(individual, &mut small_rng, stop_signal);
+            }
+        }
+        _ => unreachable!("Every island heuristic gets its parameters above"),
+    };
+
+    // Islands only improve the shared best solution, which is saved at the end
+    let shared_best = SharedBest::new(solution);
+    island_model(
+        islands,
+        &parameters,
+        epoch,
+        instance_name,
+        stop_signal,
+        &shared_best,
+    );
+    shared_best.get().write_solution("output");
+
+    println!("All threads stopped, exiting.");
+}
+
+pub fn setup_portfolio<B, D, G, M, P, R, T>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) where
+    B: PartialSolution<T>,
+    D: DestroyOperators<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    P: LocalRandomMove<T>,
+    R: RepairOperators<T>,
+    T: Solution + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask how many threads every heuristic gets, split evenly by default
+    let heuristics: Vec<PortfolioHeuristic> = PortfolioHeuristic::iter().collect();
+    let allocation: Vec<(PortfolioHeuristic, u32)> = heuristics
+        .iter()
+        .enumerate()
+        .map(|(index, &heuristic)| {
+            let default = num_cpus / heuristics.len() as u32
+                + u32::from((index as u32) < num_cpus % heuristics.len() as u32);
+            let num_threads: u32 = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Number of threads running {} (enter to use default)",
+                    heuristic
+                ))
+                .default(default)
+                .interact_text()
+                .unwrap();
+            (heuristic, num_threads)
+        })
+        .collect();
+
+    // Ask the user for the round length, the other members use their defaults
+    let round_iterations: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(
+            "Number of annealing and late acceptance iterations per round (enter to use default)",
+        )
+        .default(DEFAULT_PORTFOLIO_ROUND_ITERATIONS)
+        .interact_text()
+        .unwrap();
+    let parameters = PortfolioParameters {
+        round_iterations,
+        alns_round_iterations: DEFAULT_NUMBER_OF_ALNS_ITERATIONS,
+        history_length: DEFAULT_HISTORY_LENGTH,
+        max_destroy_degree: DEFAULT_MAX_DESTROY_DEGREE,
+        perturbation_strength: DEFAULT_PERTURBATION_STRENGTH,
+        stagnation_limit: DEFAULT_STAGNATION_LIMIT,
+        mcts: MctsParameters {
+            variant: MctsVariant::Uct,
+            rollout: Rollout::Greedy,
+            exploration: DEFAULT_EXPLORATION_CONSTANT,
+            iterations_per_step: DEFAULT_MCTS_ITERATIONS_PER_STEP,
+            nrpa_level: DEFAULT_NRPA_LEVEL,
+            nrpa_iterations: DEFAULT_NRPA_ITERATIONS,
+        },
+    };
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, all threads will stop and the best solution will be saved to output/");
+
+    // Threads only improve the shared best solution, which is saved at the end
+    let mut starting_solution = solution.clone();
+    let shared_best = SharedBest::new(solution.clone());
+    portfolio::<B, D, G, M, P, R, T>(
+        &solution,
+        &allocation,
+        &parameters,
+        instance_name,
+        stop_signal,
+        &shared_best,
+    );
+    report_improvements(&shared_best.history(), starting_solution.get_cost());
+    shared_best.get().write_solution("output");
+
+    println!("All threads stopped, exiting.");
+}
+
+pub fn setup_population_annealing<M, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    M: LocalRandomMove<T>,
+    T: Solution + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let num_replicas: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of replicas (enter to use default)")
+        .default(DEFAULT_NUMBER_OF_REPLICAS)
+        .interact_text()
+        .unwrap();
+    let num_temperature_steps: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of temperature steps (enter to use default)")
+        .default(DEFAULT_NUMBER_OF_TEMPERATURE_STEPS)
+        .interact_text()
+        .unwrap();
+    let sweeps_per_step: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of iterations per replica per temperature step (enter to use default)")
+        .default(DEFAULT_SWEEPS_PER_STEP)
+        .interact_text()
+        .unwrap();
+    let parameters = PopulationAnnealingParameters {
+        num_replicas,
+        num_temperature_steps,
+        sweeps_per_step,
+        cooling_schedule: ask_user_choice("Select a cooling schedule"),
+        num_threads: number_of_threads,
+    };
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution will be saved to output/");
+
+    // Cool the population, the best replica ever seen is kept
+    let mut solution = solution;
+    population_annealing::<M, T>(&mut solution, &parameters, instance_name, stop_signal);
+    solution.write_solution("output");
+
+    println!("All threads stopped, exiting.");
+}
+
+pub fn setup_grasp<B, G, M, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    B: GreedyConstruction<T>,
+    G: RelinkingMoves<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + SolutionDistance + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let alpha: f64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Alpha of the restricted candidate list, 0 is greedy and 1 is random (enter to use default)")
+        .default(DEFAULT_RCL_ALPHA)
+        .interact_text()
+        .unwrap();
+    let (local_search, annealing_iterations) = ask_user_local_search();
+    let relinking = ask_user_choice("Select a path relinking direction");
+    let elite_pool_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Elite pool size (enter to use default)")
+        .default(DEFAULT_ELITE_POOL_SIZE)
+        .interact_text()
+        .unwrap();
+    let parameters = GraspParameters {
+        alpha: alpha.clamp(0.0, 1.0),
+        local_search,
+        annealing_iterations,
+        elite_pool_size,
+        relinking,
+    };
+
+    // Threads only improve the shared best solution, which is saved at the end
+    let shared_best = SharedBest::new(solution.clone());
+    let thread_shared_best = shared_best.clone();
+    spawn_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |mut solution, process_name, stop_signal| {
+            grasp::<B, G, M, T>(
+                &mut solution,
+                &parameters,
+                process_name,
+                stop_signal,
+                &thread_shared_best,
+            );
+        },
+    );
+    shared_best.get().write_solution("output");
+}
+
+pub fn setup_constructive_search<P, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    P: PartialSolution<T>,
+    T: Solution + 'static,
+{
+    // Ask the user for the heuristic and its parameters
+    let method = ask_user_choice("Select a constructive heuristic");
+    let (beam_width, number_of_threads) = match method {
+        ConstructiveMethod::BeamSearch => {
+            let number_of_threads = ask_user_num_threads(get_thread_count(), None);
+            let beam_width: usize = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Beam width (enter to use default)")
+                .default(DEFAULT_BEAM_WIDTH)
+                .interact_text()
+                .unwrap();
+            (beam_width, number_of_threads)
+        }
+        _ => (DEFAULT_BEAM_WIDTH, 1),
+    };
+    let lookahead_depth: u32 = match method {
+        ConstructiveMethod::GreedyLookahead => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Lookahead depth, 1 is plain greedy (enter to use default)")
+            .default(DEFAULT_LOOKAHEAD_DEPTH)
+            .interact_text()
+            .unwrap(),
+        _ => DEFAULT_LOOKAHEAD_DEPTH,
+    };
+    let num_candidates: usize = match method {
+        ConstructiveMethod::GreedyLookahead | ConstructiveMethod::PilotMethod => {
+            Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Number of candidate extensions per step (enter to use default)")
+                .default(DEFAULT_NUMBER_OF_CANDIDATES)
+                .interact_text()
+                .unwrap()
+        }
+        _ => DEFAULT_NUMBER_OF_CANDIDATES,
+    };
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the solution will be completed greedily and saved to output/");
+
+    // Construct the solution from scratch
+    let solution = match method {
+        ConstructiveMethod::BeamSearch => beam_search::<P, T>(
+            &solution,
+            beam_width,
+            number_of_threads,
+            instance_name,
+            stop_signal,
+        ),
+        ConstructiveMethod::GreedyLookahead => greedy_lookahead::<P, T>(
+            &solution,
+            lookahead_depth,
+            num_candidates,
+            instance_name,
+            stop_signal,
+        ),
+        ConstructiveMethod::PilotMethod => pilot_method::<P, T>(
+            &solution,
+            num_candidates,
+            number_of_threads,
+            instance_name,
+            stop_signal,
+        ),
+    };
+    solution.write_solution("output");
+}
+
+pub fn setup_monte_carlo_tree_search<B, T>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) where
+    B: PartialSolution<T>,
+    T: Solution + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the variant and its parameters
+    let variant = ask_user_choice("Select a Monte Carlo tree search variant");
+    let mut parameters = MctsParameters {
+        variant,
+        rollout: Rollout::Greedy,
+        exploration: DEFAULT_EXPLORATION_CONSTANT,
+        iterations_per_step: DEFAULT_MCTS_ITERATIONS_PER_STEP,
+        nrpa_level: DEFAULT_NRPA_LEVEL,
+        nrpa_iterations: DEFAULT_NRPA_ITERATIONS,
+    };
+    match variant {
+        MctsVariant::Uct => {
+            parameters.rollout = ask_user_choice("Select a rollout policy");
+            parameters.exploration = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Exploration constant (enter to use default)")
+                .default(DEFAULT_EXPLORATION_CONSTANT)
+                .interact_text()
+                .unwrap();
+            parameters.iterations_per_step = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Number of iterations per construction step (enter to use default)")
+                .default(DEFAULT_MCTS_ITERATIONS_PER_STEP)
+                .interact_text()
+                .unwrap();
+        }
+        MctsVariant::Nrpa => {
+            parameters.nrpa_level = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Nesting level (enter to use default)")
+                .default(DEFAULT_NRPA_LEVEL)
+                .interact_text()
+                .unwrap();
+            parameters.nrpa_iterations = Input::with_theme(&ColorfulTheme::default())
+                .with_prompt("Number of iterations per level (enter to use default)")
+                .default(DEFAULT_NRPA_ITERATIONS)
+                .interact_text()
+                .unwrap();
+        }
+    }
+
+    // Threads only improve the shared best solution, which is saved at the end
+    let shared_best = SharedBest::new(solution.clone());
+    let thread_shared_best = shared_best.clone();
+    spawn_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |mut solution, process_name, stop_signal| {
+            monte_carlo_tree_search::<B, T>(
+                &mut solution,
+                &parameters,
+                process_name,
+                stop_signal,
+                &thread_shared_best,
+            );
+        },
+    );
+    shared_best.get().write_solution("output");
+}
+
+pub fn setup_branch_and_bound<B, G, M, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    B: PartialSolution<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    T: Solution + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let strategy = ask_user_choice("Select a search strategy");
+    let node_limit: u64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Maximum number of nodes, 0 for no limit (enter to use default)")
+        .default(DEFAULT_NODE_LIMIT)
+        .interact_text()
+        .unwrap();
+    let time_limit: u64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Maximum running time in seconds, 0 for no limit (enter to use default)")
+        .default(DEFAULT_TIME_LIMIT_SECONDS)
+        .interact_text()
+        .unwrap();
+    let parameters = BranchAndBoundParameters {
+        strategy,
+        num_threads: number_of_threads,
+        node_limit,
+        time_limit: Duration::from_secs(time_limit),
+    };
+
+    // The initial incumbent is the greedy solution improved by local search, if better than the starting solution
+    println!("Local search improving the greedy solution to use as the initial incumbent");
+    let (local_search, annealing_iterations) = ask_user_local_search();
+    let mut greedy = B::empty(&solution);
+    complete_greedily::<B, T>(&mut greedy);
+    let mut incumbent = greedy.to_solution();
+    let runner = LocalSearchRunner::new::<M, T>(local_search, annealing_iterations, &incumbent);
+    runner.run::<G, M, T>(&mut incumbent, &mut SmallRng::from_entropy(), &stop_signal);
+    let mut solution = solution;
+    if solution.get_cost() < incumbent.get_cost() {
+        incumbent = solution;
+    }
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution and the proven gap will be reported and saved to output/");
+
+    let (solution, _) =
+        branch_and_bound::<B, T>(incumbent, &parameters, instance_name, stop_signal);
+    solution.write_solution("output");
+}
+
+pub fn setup_differential_evolution<T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    T: RealVectorSolution + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let strategy = ask_user_choice("Select a mutation strategy");
+    let population_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Population size (enter to use default)")
+        .default(DEFAULT_POPULATION_SIZE)
+        .interact_text()
+        .unwrap();
+    let scale_factor: f64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Scale factor F, the starting mean for JADE (enter to use default)")
+        .default(DEFAULT_SCALE_FACTOR)
+        .interact_text()
+        .unwrap();
+    let crossover_rate: f64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Crossover rate CR, the starting mean for JADE (enter to use default)")
+        .default(DEFAULT_CROSSOVER_RATE)
+        .interact_text()
+        .unwrap();
+    let parameters = DifferentialEvolutionParameters {
+        population_size,
+        strategy,
+        scale_factor,
+        crossover_rate: crossover_rate.clamp(0.0, 1.0),
+        num_threads: number_of_threads,
+    };
+    let number_of_generations = ask_user_num_generations();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution will be saved to output/");
+
+    let mut solution = solution;
+    differential_evolution::<T>(
+        &mut solution,
+        &parameters,
+        number_of_generations,
+        instance_name,
+        stop_signal,
+    );
+    solution.write_solution("output");
+}
+
+pub fn setup_cma_es<T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    T: RealVectorSolution + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let population_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of offspring per generation, 0 for 4 + 3 ln(n) (enter to use default)")
+        .default(0)
+        .interact_text()
+        .unwrap();
+    let restarts = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Restart with a doubled population once converged?")
+        .default(true)
+        .interact()
+        .unwrap();
+    let parameters = CmaEsParameters {
+        population_size,
+        restarts,
+        num_threads: number_of_threads,
+    };
+    let number_of_generations = ask_user_num_generations();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the best solution will be saved to output/");
+
+    let mut solution = solution;
+    cma_es::<T>(
+        &mut solution,
+        &parameters,
+        number_of_generations,
+        instance_name,
+        stop_signal,
+    );
+    solution.write_solution("output");
+}
+
+pub fn setup_nsga_ii<C, M, T>(instance_name: &str, solution: T, stop_signal: StopSignal)
+where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: MultiObjectiveSolution + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the parameters
+    let population_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Population size (enter to use default)")
+        .default(DEFAULT_POPULATION_SIZE)
+        .interact_text()
+        .unwrap();
+    let mutation_probability: f64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Mutation probability (enter to use default)")
+        .default(0.1)
+        .interact_text()
+        .unwrap();
+    let parameters = NsgaIiParameters {
+        population_size: population_size.max(2),
+        mutation_probability,
+        num_threads: number_of_threads,
+    };
+    let number_of_generations = ask_user_num_generations();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the front will be saved to output/");
+
+    let population = initial_population::<M, T>(&solution, parameters.population_size);
+    let front = nsga_ii::<C, M, T>(
+        population,
+        &parameters,
+        number_of_generations,
+        instance_name,
+        stop_signal,
+    );
+    front.write_front("output", instance_name);
+}
+
+pub fn setup_multi_objective_simulated_annealing<M, T>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) where
+    M: LocalRandomMove<T>,
+    T: MultiObjectiveSolution + Sync + 'static,
+{
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, None);
+
+    // Ask the user for the number of iterations and the size of the archive
+    let number_of_iterations = ask_user_num_iterations(Some(DEFAULT_MOSA_ITERATIONS));
+    let archive_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Maximum size of the Pareto archive, 0 for no limit (enter to use default)")
+        .default(DEFAULT_ARCHIVE_SIZE)
+        .interact_text()
+        .unwrap();
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, the front will be saved to output/");
+
+    // Every thread runs its own chain, their archives are merged into the front
+    let mut front = ParetoArchive::new(archive_size);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..number_of_threads)
+            .map(|i| {
+                let solution = solution.clone();
+                let name: String = instance_name.to_owned() + &i.to_string();
+                let stop_signal = stop_signal.clone();
+                scope.spawn(move || {
+                    multi_objective_simulated_annealing::<M, T>(
+                        solution,
+                        number_of_iterations,
+                        archive_size,
+                        &name,
+                        stop_signal,
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            front.merge(&handle.join().unwrap());
+        }
+    });
+    front.write_front("output", instance_name);
+
+    println!("All threads stopped, exiting.");
+}
+
+pub fn setup_tempering<M: LocalRandomMove<T>, T: Solution + 'static>(
+    instance_name: &str,
+    solution: T,
+    stop_signal: StopSignal,
+) {
+    // Get number of threads of the system
+    let num_cpus = get_thread_count();
+
+    // Ask for the number of threads to utilize
+    let number_of_threads = ask_user_num_threads(num_cpus, Some("Number of threads (enter to use default). Tempering requires at least 2 threads but only makes sense with more."));
+
+    set_stop_handler(&stop_signal);
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, all threads will stop and the best solution will be saved to output/");
+
+    // Do tempering
+    let mut solution = solution.clone();
+    tempering::<M, T>(
+        &mut solution,
+        number_of_threads,
+        50_000,
+        crate::heuristics::simulated_annealing::CoolingSchedule::Linear,
+        instance_name,
+        stop_signal.clone(),
+    );
+
+    println!("All threads stopped, exiting.");
+}
+
+/// Sets up the Ctrl+C handler, which raises the stop signal for all threads
+fn set_stop_handler(stop_signal: &StopSignal) {
+    // necessary for borrowing in closure
+    let stop_signal_clone = stop_signal.clone();
+
+    ctrlc::set_handler(move || {
+        println!("Received stop signal, stopping all threads");
+        stop_signal_clone.store(true, std::sync::atomic::Ordering::SeqCst);
+    })
+    .expect("Error setting Ctrl-C handler");
+}
+
+/// Runs the heuristic on its own copy of the solution in every thread until the stop signal is raised.
+fn run_threads<T, F>(
+    instance_name: &str,
+    solution: T,
+    number_of_threads: u32,
+    stop_signal: StopSignal,
+    heuristic: F,
+) where
+    T: Solution + 'static,
+    F: Fn(&mut T, &str, StopSignal) + Send + Clone + 'static,
+{
+    spawn_threads(
+        instance_name,
+        solution,
+        number_of_threads,
+        stop_signal,
+        move |solution, process_name, stop_signal| {
+            infinite_loop(solution, process_name, stop_signal, heuristic.clone());
+        },
+    );
+}
+
+/// Spawns the threads, each getting its own copy of the solution, and waits for all of them to finish.
+fn spawn_threads<T, F>(
+    instance_name: &str,
+    solution: T,
+    number_of_threads: u32,
+    stop_signal: StopSignal,
+    thread_body: F,
+) where
+    T: Solution + 'static,
+    F: Fn(T, &str, StopSignal) + Send + Clone + 'static,
+{
+    set_stop_handler(&stop_signal);
+
+    // Spawn threads
+    let handles: Vec<_> = (0..number_of_threads)
+        .map(|i| {
+            let solution = solution.clone();
+            let name: String = instance_name.to_owned() + &i.to_string();
+            let stop_signal = stop_signal.clone();
+            let thread_body = thread_body.clone();
+            std::thread::spawn(move || {
+                thread_body(solution, &name, stop_signal);
+            })
+        })
+        .collect();
+
+    // Print controls
+    println!("Press Ctrl+C to stop the program, all threads will stop and save their best solution to output/");
+
+    // Wait for all threads to finish
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    println!("All threads stopped, exiting.");
+}
+
+/// Keeps running the heuristic on the solution, saving it after every run, until cancelled.
+/// A solution that violates the constraints is saved to a separate folder.
+fn infinite_loop<T, F>(mut solution: T, process_name: &str, stop_signal: StopSignal, heuristic: F)
+where
+    T: Solution,
+    F: Fn(&mut T, &str, StopSignal),
+{
+    // Main loop, run algo until cancelled
+    loop {
+        heuristic(&mut solution, process_name, stop_signal.clone());
+        let violation = solution.constraint_violation();
+        if is_feasible(violation) {
+            solution.write_solution("output");
+        } else {
+            println!(
+                "{} - No feasible solution found, the solution violates the constraints by {:.4} and is saved to {}",
+                process_name, violation, INFEASIBLE_OUTPUT_PATH
+            );
+            std::fs::create_dir_all(INFEASIBLE_OUTPUT_PATH)
+                .expect("Could not create folder for infeasible solutions!!!");
+            solution.write_solution(INFEASIBLE_OUTPUT_PATH);
+        }
+
+        // Check stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::SeqCst) {
+            break;
+        }
+    }
+}
+
+fn ask_user_num_iterations(default: Option<u32>) -> u32 {
+    let number_of_iterations: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of iterations (enter to use default)")
+        .default(default.unwrap_or(DEFAULT_NUMBER_OF_ITERATIONS))
+        .interact_text()
+        .unwrap();
     number_of_iterations
 }
 
+fn ask_user_genetic_algorithm_parameters(num_threads: u32) -> GeneticAlgorithmParameters {
+    let population_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Population size (enter to use default)")
+        .default(DEFAULT_POPULATION_SIZE)
+        .interact_text()
+        .unwrap();
+    let num_elites: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of elites (enter to use default)")
+        .default((population_size / 10).max(1))
+        .interact_text()
+        .unwrap();
+    let selection: Selection = ask_user_choice("Select a selection method");
+    let tournament_size = match selection {
+        Selection::Tournament => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Tournament size (enter to use default)")
+            .default(2)
+            .interact_text()
+            .unwrap(),
+        Selection::RouletteWheel => 0,
+    };
+    let mutation_probability: f64 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Mutation probability (enter to use default)")
+        .default(0.1)
+        .interact_text()
+        .unwrap();
+    GeneticAlgorithmParameters {
+        population_size: population_size.max(2),
+        num_elites,
+        selection,
+        tournament_size,
+        mutation_probability,
+        num_threads,
+    }
+}
+
+fn ask_user_eax_parameters(num_threads: u32) -> EaxParameters {
+    let population_size: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Population size (enter to use default)")
+        .default(DEFAULT_POPULATION_SIZE)
+        .interact_text()
+        .unwrap();
+    let num_children: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of children per pair of parents (enter to use default)")
+        .default(DEFAULT_NUMBER_OF_EAX_CHILDREN)
+        .interact_text()
+        .unwrap();
+    let strategy: ESetStrategy = ask_user_choice("Select an E-set strategy");
+    EaxParameters {
+        population_size: population_size.max(2),
+        num_children: num_children.max(1),
+        strategy,
+        num_threads,
+    }
+}
+
+/// Asks for the local search and, if it is annealing, the number of iterations per run
+fn ask_user_local_search() -> (LocalSearch, u32) {
+    let local_search: LocalSearch = ask_user_choice("Select a local search");
+    let annealing_iterations = match local_search {
+        LocalSearch::ShortAnnealing => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt("Number of iterations per annealing run (enter to use default)")
+            .default(DEFAULT_SHORT_ANNEALING_ITERATIONS)
+            .interact_text()
+            .unwrap(),
+        _ => 0,
+    };
+    (local_search, annealing_iterations)
+}
+
+fn ask_user_num_generations() -> u32 {
+    let number_of_generations: u32 = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("Number of generations (enter to use default)")
+        .default(DEFAULT_NUMBER_OF_GENERATIONS)
+        .interact_text()
+        .unwrap();
+    number_of_generations
+}
+
+/// Asks the user to pick one of the variants of an enum
+fn ask_user_choice<E: IntoEnumIterator + Display + Copy>(prompt: &str) -> E {
+    let options = E::iter().collect::<Vec<_>>();
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(&options)
+        .default(0)
+        .interact()
+        .unwrap();
+    options[choice]
+}
+
 fn ask_user_num_threads(default_num_cpus: u32, prompt: Option<&str>) -> u32 {
     let prompt = prompt.unwrap_or("Number of threads (enter to use default)");
     let number_of_threads: u32 = Input::with_theme(&ColorfulTheme::default())
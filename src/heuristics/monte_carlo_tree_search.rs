@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{PartialSolution, Solution};
+
+use super::{constructive_search::complete_greedily, SharedBest, StopSignal};
+
+/// How often to report the status of the algorithm, in constructions
+const REPORT_STATUS_EVERY_CONSTRUCTION: u32 = 10;
+
+/// Learning rate of the policy adaptation of NRPA
+const NRPA_LEARNING_RATE: f64 = 1.0;
+
+/// Parameters of Monte Carlo tree search
+#[derive(Clone, Copy, Debug)]
+pub struct MctsParameters {
+    pub variant: MctsVariant,
+    pub rollout: Rollout,
+    /// Weight of the exploration term of UCT, the exploitation term is normalised to [0, 1]
+    pub exploration: f64,
+    /// Number of UCT iterations before committing to the best extension of the root
+    pub iterations_per_step: u32,
+    /// Nesting level of NRPA, level 0 is a single playout
+    pub nrpa_level: u32,
+    /// Number of searches of the level below for every NRPA search
+    pub nrpa_iterations: u32,
+}
+
+/// Node of the search tree, the partial solution of a node is found by applying the extensions from the root
+struct Node<E> {
+    parent: Option<usize>,
+    /// Extension leading from the parent to this node, none for the root
+    extension: Option<E>,
+    children: Vec<usize>,
+    /// Extensions without a child yet, filled when the node is first expanded
+    untried: Option<Vec<E>>,
+    visits: u32,
+    total_cost: f64,
+    best_cost: f64,
+}
+
+impl<E> Node<E> {
+    fn new(parent: Option<usize>, extension: Option<E>) -> Self {
+        Node {
+            parent,
+            extension,
+            children: Vec::new(),
+            untried: None,
+            visits: 0,
+            total_cost: 0.0,
+            best_cost: f64::INFINITY,
+        }
+    }
+}
+
+/// Completes the partial solution by taking random extensions
+fn random_rollout<B, T>(partial: &mut B, small_rng: &mut SmallRng)
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    while !partial.is_complete() {
+        match partial.extensions().choose(small_rng) {
+            Some(extension) => partial.extend(extension),
+            None => break,
+        }
+    }
+}
+
+/// Child of the node with the highest upper confidence bound.
+/// Mean costs are normalised by the lowest and highest cost seen, so the exploration weight does not depend on the scale of the costs.
+fn select_child<E>(
+    tree: &[Node<E>],
+    node: usize,
+    exploration: f64,
+    cost_range: (f64, f64),
+) -> usize {
+    let (lowest, highest) = cost_range;
+    let spread = (highest - lowest).max(f64::EPSILON);
+    let log_visits = (tree[node].visits.max(1) as f64).ln();
+    let value = |child: usize| {
+        let child = &tree[child];
+        let visits = child.visits.max(1) as f64;
+        let mean = child.total_cost / visits;
+        (highest - mean) / spread + exploration * (log_visits / visits).sqrt()
+    };
+    *tree[node]
+        .children
+        .iter()
+        .max_by(|&&a, &&b| value(a).total_cmp(&value(b)))
+        .unwrap()
+}
+
+/// Builds a single solution with UCT, committing to the best extension of the root after every batch of iterations.
+/// The subtree of the chosen extension is kept for the next step. Returns the best complete solution found by the rollouts.
+fn uct_construction<B, T>(
+    solution: &T,
+    parameters: &MctsParameters,
+    small_rng: &mut SmallRng,
+    stop_signal: &StopSignal,
+) -> Option<T>
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    let mut tree = vec![Node::new(None, None)];
+    let mut root = 0;
+    let mut root_partial = B::empty(solution);
+    let mut cost_range = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut best: Option<T> = None;
+    while !root_partial.is_complete() {
+        for _ in 0..parameters.iterations_per_step.max(1) {
+            if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+                return best;
+            }
+
+            // Selection, descend while every extension of the node has a child
+            let mut node = root;
+            let mut partial = root_partial.clone();
+            while !partial.is_complete() {
+                let untried = tree[node]
+                    .untried
+                    .get_or_insert_with(|| partial.extensions());
+                if !untried.is_empty() {
+                    // Expansion of a random untried extension
+                    let extension = untried.swap_remove(small_rng.gen_range(0..untried.len()));
+                    partial.extend(&extension);
+                    tree.push(Node::new(Some(node), Some(extension)));
+                    let child = tree.len() - 1;
+                    tree[node].children.push(child);
+                    node = child;
+                    break;
+                }
+                if tree[node].children.is_empty() {
+                    break;
+                }
+                node = select_child(&tree, node, parameters.exploration, cost_range);
+                partial.extend(tree[node].extension.as_ref().unwrap());
+            }
+
+            // Rollout
+            match parameters.rollout {
+                Rollout::Random => random_rollout::<B, T>(&mut partial, small_rng),
+                Rollout::Greedy => complete_greedily::<B, T>(&mut partial),
+            }
+            if !partial.is_complete() {
+                continue;
+            }
+            let cost = partial.score();
+            cost_range = (cost_range.0.min(cost), cost_range.1.max(cost));
+            if best.as_mut().is_none_or(|best| cost < best.get_cost()) {
+                best = Some(partial.to_solution());
+            }
+
+            // Backpropagation up to the root
+            let mut current = Some(node);
+            while let Some(index) = current {
+                let node = &mut tree[index];
+                node.visits += 1;
+                node.total_cost += cost;
+                node.best_cost = node.best_cost.min(cost);
+                current = if index == root { None } else { node.parent };
+            }
+        }
+
+        // Commit to the extension leading to the best rollout
+        let Some(&chosen) = tree[root]
+            .children
+            .iter()
+            .min_by(|&&a, &&b| tree[a].best_cost.total_cmp(&tree[b].best_cost))
+        else {
+            break;
+        };
+        root_partial.extend(tree[chosen].extension.as_ref().unwrap());
+        root = chosen;
+    }
+    best
+}
+
+/// Policy of NRPA, the weight of every extension code
+type Policy = HashMap<u64, f64>;
+
+/// Completes the partial solution by picking extensions with probabilities following the softmax of their weights.
+/// Returns the extensions taken.
+fn playout<B, T>(partial: &mut B, policy: &Policy, small_rng: &mut SmallRng) -> Vec<B::Extension>
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    let mut sequence = Vec::new();
+    while !partial.is_complete() {
+        let extensions = partial.extensions();
+        if extensions.is_empty() {
+            break;
+        }
+        let weights: Vec<f64> = extensions
+            .iter()
+            .map(|extension| {
+                let code = partial.extension_code(extension);
+                policy.get(&code).copied().unwrap_or(0.0).exp()
+            })
+            .collect();
+        let mut pointer = small_rng.gen::<f64>() * weights.iter().sum::<f64>();
+        let mut index = extensions.len() - 1;
+        for (k, weight) in weights.iter().enumerate() {
+            if pointer < *weight {
+                index = k;
+                break;
+            }
+            pointer -= weight;
+        }
+        partial.extend(&extensions[index]);
+        sequence.push(extensions[index].clone());
+    }
+    sequence
+}
+
+/// Moves the policy towards the sequence, increasing the weights of its extensions and decreasing those of the alternatives
+fn adapt<B, T>(empty: &B, policy: &mut Policy, sequence: &[B::Extension])
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    let old_policy = policy.clone();
+    let mut partial = empty.clone();
+    for chosen in sequence {
+        let extensions = partial.extensions();
+        let codes: Vec<u64> = extensions
+            .iter()
+            .map(|extension| partial.extension_code(extension))
+            .collect();
+        let weights: Vec<f64> = codes
+            .iter()
+            .map(|code| old_policy.get(code).copied().unwrap_or(0.0).exp())
+            .collect();
+        let total_weight: f64 = weights.iter().sum();
+        for (code, weight) in codes.iter().zip(&weights) {
+            *policy.entry(*code).or_default() -= NRPA_LEARNING_RATE * weight / total_weight;
+        }
+        *policy.entry(partial.extension_code(chosen)).or_default() += NRPA_LEARNING_RATE;
+        partial.extend(chosen);
+    }
+}
+
+/// Nested rollout policy adaptation, every level runs searches of the level below and adapts the policy to the best sequence found.
+/// Returns the cost and extensions of the best sequence, none if stopped before any playout finished.
+fn nrpa<B, T>(
+    empty: &B,
+    level: u32,
+    policy: &mut Policy,
+    num_iterations: u32,
+    small_rng: &mut SmallRng,
+    stop_signal: &StopSignal,
+) -> Option<(f64, Vec<B::Extension>)>
+where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    if level == 0 {
+        let mut partial = empty.clone();
+        let sequence = playout::<B, T>(&mut partial, policy, small_rng);
+        return partial.is_complete().then(|| (partial.score(), sequence));
+    }
+
+    let mut best: Option<(f64, Vec<B::Extension>)> = None;
+    for _ in 0..num_iterations {
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            break;
+        }
+        let Some((cost, sequence)) = nrpa::<B, T>(
+            empty,
+            level - 1,
+            &mut policy.clone(),
+            num_iterations,
+            small_rng,
+            stop_signal,
+        ) else {
+            continue;
+        };
+        if best.as_ref().is_none_or(|best| cost <= best.0) {
+            best = Some((cost, sequence));
+        }
+        adapt::<B, T>(empty, policy, &best.as_ref().unwrap().1);
+    }
+    best
+}
+
+/// Monte Carlo tree search, repeatedly builds a solution from scratch until stopped.
+/// UCT grows a search tree with random or greedy rollouts, NRPA instead learns a policy for its playouts.
+/// Improvements are shared with the other threads through the shared best solution.
+pub fn monte_carlo_tree_search<B, T>(
+    solution: &mut T,
+    parameters: &MctsParameters,
+    process_name: &str,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    B: PartialSolution<T>,
+    T: Solution,
+{
+    let mut small_rng = SmallRng::from_entropy();
+
+    // Print some info
+    match parameters.variant {
+        MctsVariant::Uct => println!(
+            "{} - Running UCT with {} rollouts and {} iterations per step",
+            process_name, parameters.rollout, parameters.iterations_per_step
+        ),
+        MctsVariant::Nrpa => println!(
+            "{} - Running NRPA of level {} with {} iterations per level",
+            process_name, parameters.nrpa_level, parameters.nrpa_iterations
+        ),
+    }
+
+    // Main loop, runs until stopped
+    let empty = B::empty(solution);
+    let mut best_solution: Option<T> = None;
+    let mut construction: u32 = 0;
+    while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        let candidate = match parameters.variant {
+            MctsVariant::Uct => {
+                uct_construction::<B, T>(solution, parameters, &mut small_rng, &stop_signal)
+            }
+            MctsVariant::Nrpa => nrpa::<B, T>(
+                &empty,
+                parameters.nrpa_level,
+                &mut Policy::new(),
+                parameters.nrpa_iterations,
+                &mut small_rng,
+                &stop_signal,
+            )
+            .map(|(_, sequence)| {
+                let mut partial = empty.clone();
+                for extension in &sequence {
+                    partial.extend(extension);
+                }
+                partial.to_solution()
+            }),
+        };
+
+        // Update best solutions
+        if let Some(mut candidate) = candidate {
+            shared_best.update(&mut candidate);
+            if best_solution
+                .as_mut()
+                .is_none_or(|best| candidate.get_cost() < best.get_cost())
+            {
+                best_solution = Some(candidate);
+            }
+        }
+
+        // print cost every so often
+        if construction % REPORT_STATUS_EVERY_CONSTRUCTION == 0 {
+            println!(
+                " {} - Construction {} - Best cost: {:.4} Shared best cost: {:.4}",
+                process_name,
+                construction,
+                best_solution
+                    .as_mut()
+                    .map_or(f64::INFINITY, |best| best.get_cost()),
+                shared_best.get_cost(),
+            );
+        }
+        construction = construction.wrapping_add(1);
+    }
+
+    // Set the best solution, if any was built
+    if let Some(best_solution) = best_solution {
+        *solution = best_solution;
+    }
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+}
+
+/// Variant of Monte Carlo tree search
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum MctsVariant {
+    /// Upper confidence bounds applied to trees
+    Uct,
+    /// Nested rollout policy adaptation
+    Nrpa,
+}
+
+/// How UCT completes a partial solution to estimate its cost
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum Rollout {
+    /// Random extensions
+    Random,
+    /// Extensions with the best score
+    Greedy,
+}
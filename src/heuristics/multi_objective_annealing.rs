@@ -0,0 +1,134 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::solution::{LocalRandomMove, MultiObjectiveSolution};
+
+use super::{pareto::ParetoArchive, StopSignal, FLOAT_PRECISION};
+
+const STARTING_ACCEPTANCE_PROBABILITY: f64 = 0.5;
+const ENDING_ACCEPTANCE_PROBABILITY: f64 = 10e-6;
+
+/// Number of random moves sampled to determine the temperatures
+const TEMPERATURE_SAMPLES: u32 = 10_000;
+
+/// How often the chain draws new weights for the objectives, so it moves along the front
+const CHANGE_WEIGHTS_EVERY_ITERATION: u32 = 100_000;
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 1_000_000;
+
+/// Random weights of the objectives that sum to 1, uniform over the simplex
+fn random_weights(num_objectives: usize, small_rng: &mut SmallRng) -> Vec<f64> {
+    let samples: Vec<f64> = (0..num_objectives)
+        .map(|_| -(1.0 - small_rng.gen::<f64>()).ln())
+        .collect();
+    let total: f64 = samples.iter().sum();
+    samples.iter().map(|sample| sample / total).collect()
+}
+
+/// Weighted change in cost, every objective scaled by its cost in the starting solution
+fn weighted_delta(old: &[f64], new: &[f64], weights: &[f64], scales: &[f64]) -> f64 {
+    (0..old.len())
+        .map(|k| weights[k] * (new[k] - old[k]) / scales[k])
+        .sum()
+}
+
+/// Multi-objective simulated annealing (MOSA) in the style of Ulungu et al.
+/// The chain accepts moves on a weighted sum of the scaled objectives, the weights are redrawn regularly.
+/// Every accepted solution is offered to a Pareto archive, which is returned.
+/// Temperatures are determined from random moves of the starting solution, like simulated annealing, and cool exponentially.
+pub fn multi_objective_simulated_annealing<M, T>(
+    mut solution: T,
+    num_iterations: u32,
+    archive_capacity: usize,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> ParetoArchive<T>
+where
+    M: LocalRandomMove<T>,
+    T: MultiObjectiveSolution,
+{
+    let mut small_rng = SmallRng::from_entropy();
+    let mut costs = solution.get_costs();
+    let num_objectives = costs.len();
+    let scales: Vec<f64> = costs
+        .iter()
+        .map(|cost| cost.abs().max(FLOAT_PRECISION))
+        .collect();
+
+    // Average worsening of a random move with equal weights determines the temperatures
+    let equal_weights = vec![1.0 / num_objectives as f64; num_objectives];
+    let mut total_worsening = 0.0;
+    let mut num_worsening = 0;
+    for _ in 0..TEMPERATURE_SAMPLES {
+        M::do_random_move(&mut solution);
+        let delta = weighted_delta(&costs, &solution.get_costs(), &equal_weights, &scales);
+        if delta > 0.0 {
+            total_worsening += delta;
+            num_worsening += 1;
+        }
+        M::undo_last_move(&mut solution);
+    }
+    let average_worsening = total_worsening / f64::from(num_worsening.max(1));
+    let starting_temperature = -average_worsening / STARTING_ACCEPTANCE_PROBABILITY.ln();
+    let ending_temperature = -average_worsening / ENDING_ACCEPTANCE_PROBABILITY.ln();
+    let cooling_rate =
+        (ending_temperature / starting_temperature).powf(1.0 / f64::from(num_iterations.max(1)));
+
+    // Print some info
+    println!(
+        "{} - Running multi-objective simulated annealing for {} iterations with starting temperature: {} and ending temperature: {}",
+        process_name, num_iterations, starting_temperature, ending_temperature
+    );
+
+    let mut archive = ParetoArchive::new(archive_capacity);
+    archive.insert(&costs, &solution);
+    let mut weights = random_weights(num_objectives, &mut small_rng);
+    let mut temperature = starting_temperature;
+    for it in 0..num_iterations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        if it % CHANGE_WEIGHTS_EVERY_ITERATION == 0 {
+            weights = random_weights(num_objectives, &mut small_rng);
+        }
+
+        // Accept or undo the move
+        M::do_random_move(&mut solution);
+        let new_costs = solution.get_costs();
+        let delta = weighted_delta(&costs, &new_costs, &weights, &scales);
+        if delta <= 0.0 || small_rng.gen::<f64>() < (-delta / temperature).exp() {
+            archive.insert(&new_costs, &solution);
+            costs = new_costs;
+        } else {
+            M::undo_last_move(&mut solution);
+        }
+        temperature *= cooling_rate;
+
+        // print status every so often
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            let percentage = (it as f64 / num_iterations as f64) * 100.0;
+            println!(
+                " {} - {:.0}% - Archive size: {} Ideal point: {:.4?} Current costs: {:.4?} Temp: {:.6}",
+                process_name,
+                percentage,
+                archive.len(),
+                archive.ideal_point(),
+                costs,
+                temperature
+            );
+        }
+    }
+
+    // Print final front
+    println!(
+        "{} - Final front: {} solutions, ideal point: {:.4?}",
+        process_name,
+        archive.len(),
+        archive.ideal_point()
+    );
+    M::report_statistics(&mut solution, process_name);
+    archive
+}
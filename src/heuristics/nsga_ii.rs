@@ -0,0 +1,222 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::solution::{Crossover, LocalRandomMove, MultiObjectiveSolution};
+
+use super::{
+    pareto::{crowding_distances, non_dominated_sort, ParetoArchive},
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_GENERATION: u32 = 10;
+
+/// Parameters of NSGA-II
+#[derive(Clone, Copy, Debug)]
+pub struct NsgaIiParameters {
+    pub population_size: usize,
+    /// Probability that a child is mutated by a random move
+    pub mutation_probability: f64,
+    /// Number of threads used to create and evaluate the offspring
+    pub num_threads: u32,
+}
+
+/// Rank, the index of the front, and crowding distance of every individual
+fn rank_and_crowding(costs: &[Vec<f64>]) -> (Vec<usize>, Vec<f64>) {
+    let mut ranks = vec![0; costs.len()];
+    let mut crowding = vec![0.0; costs.len()];
+    for (rank, front) in non_dominated_sort(costs).iter().enumerate() {
+        for (&i, distance) in front.iter().zip(crowding_distances(costs, front)) {
+            ranks[i] = rank;
+            crowding[i] = distance;
+        }
+    }
+    (ranks, crowding)
+}
+
+/// Binary tournament on the crowded comparison: lower rank wins, ties go to the less crowded individual
+fn crowded_tournament(ranks: &[usize], crowding: &[f64], small_rng: &mut SmallRng) -> usize {
+    let a = small_rng.gen_range(0..ranks.len());
+    let b = small_rng.gen_range(0..ranks.len());
+    if ranks[a] < ranks[b] || (ranks[a] == ranks[b] && crowding[a] > crowding[b]) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Creates the given number of children by crowded tournaments, crossover and mutation, together with their costs.
+/// Work is split over the threads, so the children are evaluated in parallel as well.
+fn create_offspring<C, M, T>(
+    population: &[(Vec<f64>, T)],
+    ranks: &[usize],
+    crowding: &[f64],
+    parameters: &NsgaIiParameters,
+    num_children: usize,
+) -> Vec<(Vec<f64>, T)>
+where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: MultiObjectiveSolution + Sync,
+{
+    let num_threads = (parameters.num_threads.max(1) as usize).min(num_children.max(1));
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_threads)
+            .map(|thread| {
+                // Divide the children as evenly as possible
+                let count =
+                    num_children / num_threads + usize::from(thread < num_children % num_threads);
+                scope.spawn(move || {
+                    let mut small_rng = SmallRng::from_entropy();
+                    (0..count)
+                        .map(|_| {
+                            let a = crowded_tournament(ranks, crowding, &mut small_rng);
+                            let b = crowded_tournament(ranks, crowding, &mut small_rng);
+                            let mut child = C::crossover(&population[a].1, &population[b].1);
+                            if small_rng.gen::<f64>() < parameters.mutation_probability {
+                                M::do_random_move(&mut child);
+                            }
+                            (child.get_costs(), child)
+                        })
+                        .collect::<Vec<(Vec<f64>, T)>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// NSGA-II, the elitist non-dominated sorting genetic algorithm.
+/// Parents and children compete together, the next population takes whole fronts in order
+/// and fills up with the least crowded members of the front that does not fit.
+/// Returns the first front of the final population.
+pub fn nsga_ii<C, M, T>(
+    population: Vec<T>,
+    parameters: &NsgaIiParameters,
+    num_generations: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) -> ParetoArchive<T>
+where
+    C: Crossover<T>,
+    M: LocalRandomMove<T>,
+    T: MultiObjectiveSolution + Sync,
+{
+    // Print some info
+    println!(
+        "{} - Running NSGA-II for {} generations with population size {}",
+        process_name,
+        num_generations,
+        population.len()
+    );
+
+    let population_size = population.len();
+    let mut population: Vec<(Vec<f64>, T)> = population
+        .into_iter()
+        .map(|mut individual| (individual.get_costs(), individual))
+        .collect();
+    let costs: Vec<Vec<f64>> = population.iter().map(|(c, _)| c.clone()).collect();
+    let (mut ranks, mut crowding) = rank_and_crowding(&costs);
+
+    for generation in 0..num_generations {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Parents and children together
+        let offspring = create_offspring::<C, M, T>(
+            &population,
+            &ranks,
+            &crowding,
+            parameters,
+            population_size,
+        );
+        population.extend(offspring);
+
+        // Individuals with the same costs as an earlier one come after all fronts, which keeps the population diverse
+        let costs: Vec<Vec<f64>> = population.iter().map(|(c, _)| c.clone()).collect();
+        let mut unique: Vec<usize> = Vec::new();
+        let mut duplicates: Vec<usize> = Vec::new();
+        for i in 0..costs.len() {
+            if unique.iter().any(|&j| costs[j] == costs[i]) {
+                duplicates.push(i);
+            } else {
+                unique.push(i);
+            }
+        }
+        let unique_costs: Vec<Vec<f64>> = unique.iter().map(|&i| costs[i].clone()).collect();
+        let mut fronts: Vec<Vec<usize>> = non_dominated_sort(&unique_costs)
+            .into_iter()
+            .map(|front| front.into_iter().map(|j| unique[j]).collect())
+            .collect();
+        fronts.push(duplicates);
+
+        // Fill the next population front by front, the last front that fits partially by crowding distance
+        let mut survivors: Vec<usize> = Vec::with_capacity(population_size);
+        let mut next_ranks = Vec::with_capacity(population_size);
+        let mut next_crowding = Vec::with_capacity(population_size);
+        for (rank, front) in fronts.into_iter().enumerate() {
+            let distances = crowding_distances(&costs, &front);
+            let mut members: Vec<(usize, f64)> = front.into_iter().zip(distances).collect();
+            if survivors.len() + members.len() > population_size {
+                members.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
+                members.truncate(population_size - survivors.len());
+            }
+            for (i, distance) in members {
+                survivors.push(i);
+                next_ranks.push(rank);
+                next_crowding.push(distance);
+            }
+            if survivors.len() == population_size {
+                break;
+            }
+        }
+        let mut slots: Vec<Option<(Vec<f64>, T)>> = population.drain(..).map(Some).collect();
+        population = survivors
+            .into_iter()
+            .map(|i| slots[i].take().unwrap())
+            .collect();
+        ranks = next_ranks;
+        crowding = next_crowding;
+
+        // print front every so often
+        if generation % REPORT_STATUS_EVERY_GENERATION == 0 {
+            let first_front = ranks.iter().filter(|&&rank| rank == 0).count();
+            let num_objectives = population[0].0.len();
+            let ideal_point: Vec<f64> = (0..num_objectives)
+                .map(|objective| {
+                    population
+                        .iter()
+                        .map(|(costs, _)| costs[objective])
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .collect();
+            println!(
+                " {} - Generation {} - First front: {} Ideal point: {:.4?}",
+                process_name, generation, first_front, ideal_point
+            );
+        }
+    }
+
+    // The first front is the result
+    let mut archive = ParetoArchive::new(0);
+    for ((costs, individual), rank) in population.iter().zip(&ranks) {
+        if *rank == 0 {
+            archive.insert(costs, individual);
+        }
+    }
+
+    // Print final front
+    println!(
+        "{} - Final front: {} solutions, ideal point: {:.4?}",
+        process_name,
+        archive.len(),
+        archive.ideal_point()
+    );
+    M::report_statistics(&mut population[0].1, process_name);
+    archive
+}
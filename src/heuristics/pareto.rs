@@ -0,0 +1,169 @@
+use std::io::Write;
+
+use crate::solution::MultiObjectiveSolution;
+
+/// Whether costs a dominate costs b: no worse in any objective and better in at least one
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x <= y) && a.iter().zip(b).any(|(x, y)| x < y)
+}
+
+/// Splits the indices into fronts by fast non-dominated sorting.
+/// The first front is not dominated by anything, every next front only by the fronts before it.
+pub fn non_dominated_sort(costs: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let n = costs.len();
+    let mut dominated_by_count = vec![0; n];
+    let mut dominating: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for i in 0..n {
+        for j in i + 1..n {
+            if dominates(&costs[i], &costs[j]) {
+                dominating[i].push(j);
+                dominated_by_count[j] += 1;
+            } else if dominates(&costs[j], &costs[i]) {
+                dominating[j].push(i);
+                dominated_by_count[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut front: Vec<usize> = (0..n).filter(|&i| dominated_by_count[i] == 0).collect();
+    while !front.is_empty() {
+        let mut next = Vec::new();
+        for &i in &front {
+            for &j in &dominating[i] {
+                dominated_by_count[j] -= 1;
+                if dominated_by_count[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        fronts.push(front);
+        front = next;
+    }
+    fronts
+}
+
+/// Crowding distance of every member of the front, in the same order.
+/// Sum over the objectives of the normalised gap between the neighbours, infinite for the extremes.
+pub fn crowding_distances(costs: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+    let mut distances = vec![0.0; front.len()];
+    if front.len() <= 2 {
+        return vec![f64::INFINITY; front.len()];
+    }
+    let num_objectives = costs[front[0]].len();
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    let columns: Vec<Vec<f64>> = (0..num_objectives)
+        .map(|objective| front.iter().map(|&i| costs[i][objective]).collect())
+        .collect();
+    for values in columns {
+        order.sort_unstable_by(|&a, &b| values[a].total_cmp(&values[b]));
+        let (lowest, highest) = (values[order[0]], values[order[front.len() - 1]]);
+        distances[order[0]] = f64::INFINITY;
+        distances[order[front.len() - 1]] = f64::INFINITY;
+        if highest - lowest <= 0.0 {
+            continue;
+        }
+        for k in 1..front.len() - 1 {
+            distances[order[k]] +=
+                (values[order[k + 1]] - values[order[k - 1]]) / (highest - lowest);
+        }
+    }
+    distances
+}
+
+/// Archive of mutually non-dominated solutions, the best known approximation of the Pareto front.
+/// When full, the member in the most crowded part of the front is dropped, so the archive stays spread out.
+#[derive(Clone)]
+pub struct ParetoArchive<T> {
+    members: Vec<(Vec<f64>, T)>,
+    /// Maximum number of members, 0 for no limit
+    capacity: usize,
+}
+
+impl<T: MultiObjectiveSolution> ParetoArchive<T> {
+    pub fn new(capacity: usize) -> Self {
+        ParetoArchive {
+            members: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Adds the solution with the given costs unless a member is at least as good in every objective.
+    /// Removes the members it dominates. Returns whether it was added, the solution is only cloned then.
+    pub fn insert(&mut self, costs: &[f64], solution: &T) -> bool {
+        if self
+            .members
+            .iter()
+            .any(|(member, _)| member.iter().zip(costs).all(|(m, c)| m <= c))
+        {
+            return false;
+        }
+        self.members.retain(|(member, _)| !dominates(costs, member));
+        self.members.push((costs.to_vec(), solution.clone()));
+
+        // Drop the most crowded member when over capacity, the extremes are never dropped
+        if self.capacity > 0 && self.members.len() > self.capacity {
+            let costs: Vec<Vec<f64>> = self.members.iter().map(|(c, _)| c.clone()).collect();
+            let front: Vec<usize> = (0..costs.len()).collect();
+            let distances = crowding_distances(&costs, &front);
+            let most_crowded = (0..distances.len())
+                .min_by(|&a, &b| distances[a].total_cmp(&distances[b]))
+                .unwrap();
+            self.members.swap_remove(most_crowded);
+        }
+        true
+    }
+
+    /// Inserts all members of the other archive
+    pub fn merge(&mut self, other: &ParetoArchive<T>) {
+        for (costs, solution) in &other.members {
+            self.insert(costs, solution);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Best cost of every objective over the members, usually not reached by a single member
+    pub fn ideal_point(&self) -> Vec<f64> {
+        let num_objectives = self.members.first().map_or(0, |(costs, _)| costs.len());
+        (0..num_objectives)
+            .map(|objective| {
+                self.members
+                    .iter()
+                    .map(|(costs, _)| costs[objective])
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect()
+    }
+
+    /// Writes the costs of the front to a file, one member per line sorted by the first objective,
+    /// and the solutions themselves to a folder next to it, every member in a subfolder named after its line number.
+    /// Members can share a cost, which usually names the file of a solution, so they would overwrite each other otherwise.
+    pub fn write_front(&self, file_location: &str, instance_name: &str) {
+        let folder = format!("{}/{}-front", file_location, instance_name);
+        std::fs::create_dir_all(&folder).expect("Could not create folder for the front!!!");
+
+        let mut sorted: Vec<&(Vec<f64>, T)> = self.members.iter().collect();
+        sorted.sort_by(|a, b| a.0[0].total_cmp(&b.0[0]));
+        let file_path = format!("{}.out", folder);
+        let mut file =
+            std::fs::File::create(file_path.clone()).expect("Could not save front to file!!!");
+        for (index, (costs, solution)) in sorted.into_iter().enumerate() {
+            let line: Vec<String> = costs.iter().map(|cost| cost.to_string()).collect();
+            writeln!(file, "{}", line.join(" ")).expect("Could not write to file");
+
+            let member_folder = format!("{}/{}", folder, index);
+            std::fs::create_dir_all(&member_folder)
+                .expect("Could not create folder for the front!!!");
+            solution.write_solution(&member_folder);
+        }
+
+        println!(
+            "Front of {} solutions written to {}",
+            self.members.len(),
+            file_path
+        );
+    }
+}
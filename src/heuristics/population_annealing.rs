@@ -0,0 +1,216 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::solution::{LocalRandomMove, Solution};
+
+use super::{
+    simulated_annealing::{
+        determine_start_and_end_temp, get_cooling_schedule, sa_core, CoolingSchedule,
+    },
+    StopSignal,
+};
+
+/// How often to report the status of the algorithm, in temperature steps
+const REPORT_STATUS_EVERY_STEP: u32 = 10;
+
+/// Number of random moves used to determine the starting and ending temperature
+const NUM_ITERATIONS_TEMPERATURE_DETERMINING: u32 = 20_000;
+
+/// Parameters of population annealing
+#[derive(Clone, Copy, Debug)]
+pub struct PopulationAnnealingParameters {
+    /// Number of replicas in the population, kept the same after every resampling
+    pub num_replicas: usize,
+    /// Number of times the temperature is lowered
+    pub num_temperature_steps: u32,
+    /// Number of metropolis steps every replica makes at every temperature
+    pub sweeps_per_step: u32,
+    pub cooling_schedule: CoolingSchedule,
+    /// Number of threads the replicas are divided over
+    pub num_threads: u32,
+}
+
+/// Resamples the population when lowering the temperature, every replica is copied in proportion to its Boltzmann weight.
+/// Systematic resampling is used, so a replica expected to get w copies gets either floor(w) or ceil(w) copies.
+fn resample<T: Solution>(
+    population: &[T],
+    costs: &[f64],
+    old_temperature: f64,
+    new_temperature: f64,
+    small_rng: &mut SmallRng,
+) -> Vec<T> {
+    // Relative to the best cost to avoid overflow
+    let best = costs.iter().cloned().fold(f64::INFINITY, f64::min);
+    let beta_difference = 1.0 / new_temperature - 1.0 / old_temperature;
+    let weights: Vec<f64> = costs
+        .iter()
+        .map(|cost| (-(cost - best) * beta_difference).exp())
+        .collect();
+    let total_weight: f64 = weights.iter().sum();
+
+    let size = population.len();
+    let step = total_weight / size as f64;
+    let mut pointer = small_rng.gen::<f64>() * step;
+    let mut cumulative = 0.0;
+    let mut resampled = Vec::with_capacity(size);
+    for (replica, weight) in population.iter().zip(&weights) {
+        cumulative += weight;
+        while pointer < cumulative && resampled.len() < size {
+            resampled.push(replica.clone());
+            pointer += step;
+        }
+    }
+
+    // Rounding can leave the last places empty
+    while resampled.len() < size {
+        resampled.push(population[size - 1].clone());
+    }
+    resampled
+}
+
+/// Metropolis sweeps of every replica at the given temperature, divided over the threads.
+/// Returns the best replica found during the sweeps.
+fn sweep_population<M, T>(
+    population: &mut [T],
+    temperature: f64,
+    sweeps_per_step: u32,
+    num_threads: u32,
+) -> T
+where
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    let chunk_size = population.len().div_ceil(num_threads.max(1) as usize);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = population
+            .chunks_mut(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut small_rng = SmallRng::from_entropy();
+                    let mut best = chunk[0].clone();
+                    for replica in chunk.iter_mut() {
+                        let mut previous_cost = replica.get_cost();
+                        for _ in 0..sweeps_per_step {
+                            sa_core::<M, T>(
+                                replica,
+                                &mut previous_cost,
+                                &mut small_rng,
+                                temperature,
+                            );
+                            if previous_cost < best.get_cost() {
+                                best = replica.clone();
+                            }
+                        }
+                    }
+                    best
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                let mut best = handle.join().unwrap();
+                (best.get_cost(), best)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap()
+            .1
+    })
+}
+
+/// Population annealing, a population of replicas is cooled together.
+/// Every time the temperature is lowered the population is resampled by the Boltzmann weights, so replicas in good states multiply.
+/// At every temperature every replica makes metropolis steps using the core of simulated annealing, in parallel.
+pub fn population_annealing<M, T>(
+    solution: &mut T,
+    parameters: &PopulationAnnealingParameters,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    // Determine starting and ending temperature on a copy, as it applies random moves
+    let (starting_temperature, ending_temperature) = determine_start_and_end_temp::<M, T>(
+        NUM_ITERATIONS_TEMPERATURE_DETERMINING,
+        &mut solution.clone(),
+        false,
+    );
+    let num_temperature_steps = parameters.num_temperature_steps.max(2);
+    let cooling_schedule = get_cooling_schedule(
+        parameters.cooling_schedule,
+        starting_temperature,
+        ending_temperature,
+        num_temperature_steps,
+    );
+
+    // Print some info
+    println!(
+        "{} - Running population annealing with {} replicas for {} temperature steps, starting temperature: {}, ending temperature: {}",
+        process_name, parameters.num_replicas, num_temperature_steps, starting_temperature, ending_temperature
+    );
+
+    // Main loop
+    let mut small_rng = SmallRng::from_entropy();
+    let mut population = vec![solution.clone(); parameters.num_replicas.max(1)];
+    let mut best_solution = solution.clone();
+    let mut temperature = starting_temperature;
+    for step in 0..num_temperature_steps {
+        // Check for stop signal
+        if stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+            println!("{} - Stopping early", process_name);
+            break;
+        }
+
+        // Lower the temperature and resample, the first step runs at the starting temperature
+        if step > 0 {
+            let new_temperature = cooling_schedule(temperature);
+            let costs: Vec<f64> = population
+                .iter_mut()
+                .map(|replica| replica.get_cost())
+                .collect();
+            population = resample(
+                &population,
+                &costs,
+                temperature,
+                new_temperature,
+                &mut small_rng,
+            );
+            temperature = new_temperature;
+        }
+
+        // Equilibrate at the current temperature
+        let mut best_replica = sweep_population::<M, T>(
+            &mut population,
+            temperature,
+            parameters.sweeps_per_step,
+            parameters.num_threads,
+        );
+        if best_replica.get_cost() < best_solution.get_cost() {
+            best_solution = best_replica;
+        }
+
+        // print cost every so often
+        if step % REPORT_STATUS_EVERY_STEP == 0 {
+            let average = population
+                .iter_mut()
+                .map(|replica| replica.get_cost())
+                .sum::<f64>()
+                / population.len() as f64;
+            println!(
+                " {} - Step {} - Best cost: {:.4} Average cost: {:.4} Temp: {:.4}",
+                process_name,
+                step,
+                best_solution.get_cost(),
+                average,
+                temperature,
+            );
+        }
+    }
+
+    // Set the best solution
+    *solution = best_solution;
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+    M::report_statistics(solution, process_name);
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use rand::{rngs::SmallRng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{LocalRandomMove, MoveGenerator, PartialSolution, Solution};
+
+use super::{
+    adaptive_large_neighbourhood_search::{
+        adaptive_large_neighbourhood_search, DestroyOperators, RepairOperators,
+    },
+    iterated_local_search::{iterated_local_search, IlsAcceptance},
+    late_acceptance::{late_acceptance_hill_climbing, LateAcceptanceVariant},
+    local_search::{LocalSearch, LocalSearchRunner},
+    monte_carlo_tree_search::{monte_carlo_tree_search, MctsParameters},
+    Improvement, SharedBest, StopSignal,
+};
+
+/// Parameters of the members of the portfolio
+#[derive(Clone, Copy, Debug)]
+pub struct PortfolioParameters {
+    /// Number of iterations of annealing and late acceptance between looking at the shared best solution
+    pub round_iterations: u32,
+    /// Number of iterations of adaptive large neighbourhood search between looking at the shared best solution
+    pub alns_round_iterations: u32,
+    pub history_length: u32,
+    pub max_destroy_degree: usize,
+    pub perturbation_strength: u32,
+    pub stagnation_limit: u32,
+    pub mcts: MctsParameters,
+}
+
+/// Continues from the shared best solution if it is better than the own solution
+fn adopt_shared_best<T: Solution>(solution: &mut T, shared_best: &SharedBest<T>) {
+    if solution
+        .sense()
+        .is_better(shared_best.get_cost(), solution.get_cost())
+    {
+        *solution = shared_best.get();
+    }
+}
+
+/// Runs one member of the portfolio until stopped.
+/// Members working in rounds share their solution after every round and continue from the shared best solution if that is better.
+#[allow(clippy::too_many_arguments)]
+fn run_member<B, D, G, M, P, R, T>(
+    heuristic: PortfolioHeuristic,
+    solution: &mut T,
+    parameters: &PortfolioParameters,
+    annealing: &LocalSearchRunner,
+    process_name: &str,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    B: PartialSolution<T>,
+    D: DestroyOperators<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    P: LocalRandomMove<T>,
+    R: RepairOperators<T>,
+    T: Solution,
+{
+    let mut small_rng = SmallRng::from_entropy();
+    if let PortfolioHeuristic::IteratedLocalSearch = heuristic {
+        // Shares improvements itself and restarts from the shared best solution
+        iterated_local_search::<G, M, P, T>(
+            solution,
+            LocalSearch::FirstImprovement,
+            IlsAcceptance::RestartOnStagnation,
+            parameters.perturbation_strength,
+            parameters.stagnation_limit,
+            0,
+            process_name,
+            stop_signal,
+            shared_best,
+        );
+        return;
+    }
+    if let PortfolioHeuristic::MonteCarloTreeSearch = heuristic {
+        // Builds solutions from scratch, so it only shares its improvements
+        monte_carlo_tree_search::<B, T>(
+            solution,
+            &parameters.mcts,
+            process_name,
+            stop_signal,
+            shared_best,
+        );
+        return;
+    }
+
+    while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        match heuristic {
+            PortfolioHeuristic::SimulatedAnnealing => {
+                annealing.run::<G, M, T>(solution, &mut small_rng, &stop_signal);
+            }
+            PortfolioHeuristic::LateAcceptance => late_acceptance_hill_climbing::<M, T>(
+                solution,
+                parameters.round_iterations,
+                parameters.history_length,
+                LateAcceptanceVariant::LateAcceptance,
+                process_name,
+                stop_signal.clone(),
+            ),
+            PortfolioHeuristic::AdaptiveLargeNeighbourhoodSearch => {
+                adaptive_large_neighbourhood_search::<D, R, T>(
+                    solution,
+                    parameters.alns_round_iterations,
+                    parameters.max_destroy_degree,
+                    process_name,
+                    stop_signal.clone(),
+                )
+            }
+            PortfolioHeuristic::IteratedLocalSearch | PortfolioHeuristic::MonteCarloTreeSearch => {
+                unreachable!()
+            }
+        }
+        shared_best.update(solution);
+        adopt_shared_best(solution, shared_best);
+    }
+}
+
+/// Portfolio of heuristics, runs the given number of threads for every heuristic at the same time.
+/// The threads share the best solution, threads are named after their heuristic so every improvement is attributed to it.
+#[allow(clippy::too_many_arguments)]
+pub fn portfolio<B, D, G, M, P, R, T>(
+    solution: &T,
+    allocation: &[(PortfolioHeuristic, u32)],
+    parameters: &PortfolioParameters,
+    instance_name: &str,
+    stop_signal: StopSignal,
+    shared_best: &SharedBest<T>,
+) where
+    B: PartialSolution<T>,
+    D: DestroyOperators<T>,
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    P: LocalRandomMove<T>,
+    R: RepairOperators<T>,
+    T: Solution,
+{
+    // Print some info
+    let members: Vec<String> = allocation
+        .iter()
+        .filter(|(_, num_threads)| *num_threads > 0)
+        .map(|(heuristic, num_threads)| format!("{} {}", num_threads, heuristic))
+        .collect();
+    println!("Running portfolio of {}", members.join(", "));
+
+    // Temperatures of the annealing rounds are determined once for all threads
+    let annealing = LocalSearchRunner::new::<M, T>(
+        LocalSearch::ShortAnnealing,
+        parameters.round_iterations,
+        solution,
+    );
+
+    std::thread::scope(|scope| {
+        let mut thread_index = 0;
+        for &(heuristic, num_threads) in allocation {
+            for _ in 0..num_threads {
+                let mut solution = solution.clone();
+                let process_name = instance_name.to_owned() + &thread_index.to_string();
+                let stop_signal = stop_signal.clone();
+                let annealing = &annealing;
+                std::thread::Builder::new()
+                    .name(heuristic.to_string())
+                    .spawn_scoped(scope, move || {
+                        run_member::<B, D, G, M, P, R, T>(
+                            heuristic,
+                            &mut solution,
+                            parameters,
+                            annealing,
+                            &process_name,
+                            stop_signal,
+                            shared_best,
+                        );
+                    })
+                    .expect("Could not spawn thread");
+                thread_index += 1;
+            }
+        }
+    });
+}
+
+/// Prints every improvement of the shared best solution and how much every heuristic contributed
+pub fn report_improvements(history: &[Improvement], starting_cost: f64) {
+    println!("Improvements of the best solution over time:");
+    let mut contributions: BTreeMap<&str, (u32, f64)> = BTreeMap::new();
+    let mut previous_cost = starting_cost;
+    for improvement in history {
+        println!(
+            " {:>10.3}s - {} - Cost: {:.4}",
+            improvement.time.as_secs_f64(),
+            improvement.source,
+            improvement.cost
+        );
+        let contribution = contributions
+            .entry(improvement.source.as_str())
+            .or_default();
+        contribution.0 += 1;
+        contribution.1 += previous_cost - improvement.cost;
+        previous_cost = improvement.cost;
+    }
+
+    println!("Contribution per heuristic:");
+    for (source, (count, total)) in contributions {
+        println!(
+            " {} - {} improvements, total cost reduction {:.4}",
+            source, count, total
+        );
+    }
+}
+
+/// Heuristics that can be part of the portfolio
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum PortfolioHeuristic {
+    SimulatedAnnealing,
+    LateAcceptance,
+    IteratedLocalSearch,
+    AdaptiveLargeNeighbourhoodSearch,
+    MonteCarloTreeSearch,
+}
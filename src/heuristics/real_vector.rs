@@ -0,0 +1,51 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::SmallRng, Rng};
+
+use crate::solution::RealVectorSolution;
+
+/// Sample of the standard normal distribution, using the Box-Muller transform
+pub fn standard_normal(small_rng: &mut SmallRng) -> f64 {
+    let u: f64 = 1.0 - small_rng.gen::<f64>();
+    let v: f64 = small_rng.gen::<f64>();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+/// Uniformly random point within the bounds of the solution
+pub fn random_values<T: RealVectorSolution>(solution: &T, small_rng: &mut SmallRng) -> Vec<f64> {
+    solution
+        .bounds()
+        .iter()
+        .map(|&(lower, upper)| small_rng.gen_range(lower..=upper))
+        .collect()
+}
+
+/// Creates a solution for every vector, evaluating them in parallel
+pub fn evaluate_vectors<T>(solution: &T, vectors: Vec<Vec<f64>>, num_threads: u32) -> Vec<T>
+where
+    T: RealVectorSolution + Sync,
+{
+    let chunk_size = vectors.len().div_ceil(num_threads.max(1) as usize).max(1);
+    let mut chunks: Vec<Vec<Vec<f64>>> = Vec::new();
+    let mut vectors = vectors.into_iter().peekable();
+    while vectors.peek().is_some() {
+        chunks.push(vectors.by_ref().take(chunk_size).collect());
+    }
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .into_iter()
+                        .map(|values| solution.with_values(values))
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
@@ -1,4 +1,3 @@
-use core::num;
 use std::ops::ControlFlow;
 
 use crate::{
@@ -6,6 +5,7 @@ use crate::{
     solution::{LocalRandomMove, Solution},
 };
 use rand::{rngs::SmallRng, Rng, SeedableRng};
+use strum_macros::{Display, EnumIter};
 
 use super::StopSignal;
 
@@ -92,7 +92,10 @@ pub fn simulated_annealing<M, T>(
         }
 
         // Update best solution
-        if solution.get_cost() < best_solution.get_cost() {
+        if solution
+            .sense()
+            .is_better(solution.get_cost(), best_solution.get_cost())
+        {
             best_solution = solution.clone();
         }
     }
@@ -102,6 +105,47 @@ pub fn simulated_annealing<M, T>(
 
     // Print final cost
     println!("{} - Final cost: {}", process_name, solution.get_cost());
+    M::report_statistics(solution, process_name);
+}
+
+/// Runs simulated annealing between the given temperatures without reporting or early return, keeping the best solution found.
+/// Meant as a short local search inside other heuristics, the temperatures should be determined once beforehand.
+pub fn anneal<M, T>(
+    solution: &mut T,
+    num_iterations: u32,
+    starting_temperature: f64,
+    ending_temperature: f64,
+    cooling_schedule: CoolingSchedule,
+    small_rng: &mut SmallRng,
+) where
+    M: LocalRandomMove<T>,
+    T: Solution,
+{
+    let cooling_schedule = get_cooling_schedule(
+        cooling_schedule,
+        starting_temperature,
+        ending_temperature,
+        num_iterations,
+    );
+
+    let mut temperature = starting_temperature;
+    let mut previous_cost = solution.get_cost();
+    let mut best_solution = solution.clone();
+    for _ in 0..num_iterations {
+        sa_core::<M, T>(solution, &mut previous_cost, small_rng, temperature);
+        temperature = cooling_schedule(temperature);
+
+        // Update best solution
+        if solution
+            .sense()
+            .is_better(solution.get_cost(), best_solution.get_cost())
+        {
+            best_solution = solution.clone();
+        }
+    }
+
+    // Set the best solution
+    *solution = best_solution;
 }
 
 /// Core of the simulated annealing algorithm.
@@ -117,9 +161,9 @@ pub fn sa_core<M, T>(
     // Do the move
     M::do_random_move(solution);
 
-    // Check new cost again the one of the previous iteration
+    // Check new cost again the one of the previous iteration, positive if it improved in the sense of the problem
     let mut new_cost = solution.get_cost();
-    let cost_diff = *previous_cost - new_cost;
+    let cost_diff = solution.sense().improvement(*previous_cost, new_cost);
 
     // Check if we accept the move, always accept if it is better
     if cost_diff < 0.0 {
@@ -168,6 +212,9 @@ where
             smallest_cost_diff = cost_diff.abs();
         }
     }
+
+    // The random moves were not part of the search, so they should not count towards the statistics of the move
+    M::reset_statistics(solution);
     let avg_cost_diff = total_cost_diff / num_iterations_temperature_determining as f64;
     let starting_accepting_probability: f64 = if greedy_start {
         // Base starting acceptance on whether the initial solution is greedy or random
@@ -185,7 +232,7 @@ where
 
 /// Metropolis rule for simulated annealing.
 #[inline(always)]
-fn metropolis_rule(cost_difference: f64, temperature: f64) -> f64 {
+pub fn metropolis_rule(cost_difference: f64, temperature: f64) -> f64 {
     (cost_difference / temperature).exp()
 }
 
@@ -211,6 +258,7 @@ pub fn get_cooling_schedule(
 
 /// Cooling schedule for simulated annealing
 #[allow(dead_code)]
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
 pub enum CoolingSchedule {
     /// Arithmetic, new temp = old temp - c for constant c > 0
     Linear,
@@ -1,11 +1,5 @@
-use core::num;
-
-use rand::rngs::SmallRng;
-
 use crate::{
-    heuristics::simulated_annealing::{
-        determine_start_and_end_temp, get_cooling_schedule, sa_core,
-    },
+    heuristics::simulated_annealing::{determine_start_and_end_temp, get_cooling_schedule},
     solution::{LocalRandomMove, Solution},
 };
 
@@ -20,7 +14,7 @@ pub fn tempering<M, T>(
     num_iterations_temperature_determining: u32,
     cooling_setup: CoolingSchedule,
     process_name: &str,
-    stop_signal: StopSignal,
+    #[allow(unused_variables)] stop_signal: StopSignal,
 ) where
     M: LocalRandomMove<T>,
     T: Solution,
@@ -51,8 +45,10 @@ pub fn tempering<M, T>(
         temperatures.push(cooling_schedule(temperatures[(i - 1) as usize]));
     }
 
-    // Setup early return
+    // Setup early return, only read by the main loop below which is still a todo
+    #[allow(unused_variables, unused_mut)]
     let mut early_return_counter = 0;
+    #[allow(unused_variables, unused_mut)]
     let mut last_status_check_cost = solution.get_cost();
 
     // Print some info
@@ -0,0 +1,176 @@
+use rand::{rngs::SmallRng, SeedableRng};
+use strum_macros::{Display, EnumIter};
+
+use crate::solution::{LocalRandomMove, MoveGenerator, Solution};
+
+use super::{
+    hill_climbing::{hill_climbing_step, HillClimbingStrategy},
+    StopSignal, FLOAT_PRECISION,
+};
+
+/// How often to report the status of the algorithm
+const REPORT_STATUS_EVERY_ITERATION: u32 = 100;
+
+/// Ordered list of neighbourhood structures, implemented for tuples of moves.
+/// Every neighbourhood needs random moves for shaking and generated moves for the local search.
+pub trait Neighbourhoods<T: Solution> {
+    /// Number of neighbourhoods in the list
+    const COUNT: usize;
+
+    /// Applies a random move from the k-th neighbourhood.
+    fn shake(k: usize, solution: &mut T);
+
+    /// Applies an improving move from the k-th neighbourhood, if any. Returns whether a move was applied.
+    fn improve(
+        k: usize,
+        solution: &mut T,
+        strategy: HillClimbingStrategy,
+        small_rng: &mut SmallRng,
+    ) -> bool;
+}
+
+macro_rules! impl_neighbourhoods {
+    ($count:expr; $($index:tt => $name:ident),+) => {
+        impl<T: Solution, $($name: LocalRandomMove<T> + MoveGenerator<T>),+> Neighbourhoods<T> for ($($name,)+) {
+            const COUNT: usize = $count;
+
+            fn shake(k: usize, solution: &mut T) {
+                match k {
+                    $($index => $name::do_random_move(solution),)+
+                    _ => panic!("Neighbourhood {} does not exist", k),
+                }
+            }
+
+            fn improve(
+                k: usize,
+                solution: &mut T,
+                strategy: HillClimbingStrategy,
+                small_rng: &mut SmallRng,
+            ) -> bool {
+                match k {
+                    $($index => hill_climbing_step::<$name, T>(solution, strategy, small_rng),)+
+                    _ => panic!("Neighbourhood {} does not exist", k),
+                }
+            }
+        }
+    };
+}
+
+impl_neighbourhoods!(1; 0 => A);
+impl_neighbourhoods!(2; 0 => A, 1 => B);
+impl_neighbourhoods!(3; 0 => A, 1 => B, 2 => C);
+impl_neighbourhoods!(4; 0 => A, 1 => B, 2 => C, 3 => D);
+impl_neighbourhoods!(5; 0 => A, 1 => B, 2 => C, 3 => D, 4 => E);
+
+/// Variable neighbourhood descent, improves in the first neighbourhood that has an improving move,
+/// going back to the first neighbourhood after every improvement.
+/// Returns when the solution is a local optimum for all neighbourhoods or when stopped.
+pub fn variable_neighbourhood_descent<N, T>(
+    solution: &mut T,
+    strategy: HillClimbingStrategy,
+    small_rng: &mut SmallRng,
+    stop_signal: &StopSignal,
+) where
+    N: Neighbourhoods<T>,
+    T: Solution,
+{
+    let mut k = 0;
+    while k < N::COUNT && !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        if N::improve(k, solution, strategy, small_rng) {
+            k = 0;
+        } else {
+            k += 1;
+        }
+    }
+}
+
+/// Variable neighbourhood search, shakes the solution in the k-th neighbourhood and runs a local search on it.
+/// Continues from the result and goes back to the first neighbourhood if it is better, otherwise tries the next neighbourhood.
+/// Runs until stopped, except for the descent variant which returns in a local optimum of all neighbourhoods.
+pub fn variable_neighbourhood_search<N, T>(
+    solution: &mut T,
+    variant: VnsVariant,
+    strategy: HillClimbingStrategy,
+    shake_strength: u32,
+    process_name: &str,
+    stop_signal: StopSignal,
+) where
+    N: Neighbourhoods<T>,
+    T: Solution,
+{
+    // Print some info
+    println!(
+        "{} - Running {} variable neighbourhood search over {} neighbourhoods from cost: {:.4}",
+        process_name,
+        variant,
+        N::COUNT,
+        solution.get_cost()
+    );
+
+    let mut small_rng = SmallRng::from_entropy();
+
+    // Local search used after shaking
+    let local_search = |solution: &mut T, small_rng: &mut SmallRng| match variant {
+        VnsVariant::Basic => {
+            while !stop_signal.load(std::sync::atomic::Ordering::Relaxed)
+                && N::improve(0, solution, strategy, small_rng)
+            {}
+        }
+        VnsVariant::General | VnsVariant::Descent => {
+            variable_neighbourhood_descent::<N, T>(solution, strategy, small_rng, &stop_signal)
+        }
+    };
+
+    // Start from a local optimum
+    local_search(solution, &mut small_rng);
+    if let VnsVariant::Descent = variant {
+        println!("{} - Final cost: {}", process_name, solution.get_cost());
+        return;
+    }
+
+    // Main loop, runs until stopped
+    let mut k = 0;
+    let mut it: u32 = 0;
+    while !stop_signal.load(std::sync::atomic::Ordering::Relaxed) {
+        // Shake in the k-th neighbourhood and improve
+        let mut candidate = solution.clone();
+        for _ in 0..shake_strength {
+            N::shake(k, &mut candidate);
+        }
+        local_search(&mut candidate, &mut small_rng);
+
+        // Move or try the next neighbourhood, wrapping around after the last one
+        if candidate.get_cost() < solution.get_cost() - FLOAT_PRECISION {
+            *solution = candidate;
+            k = 0;
+        } else {
+            k = (k + 1) % N::COUNT;
+        }
+
+        // print cost every so often
+        if it % REPORT_STATUS_EVERY_ITERATION == 0 {
+            println!(
+                " {} - Iteration {} - Best cost: {:.4} Neighbourhood: {}",
+                process_name,
+                it,
+                solution.get_cost(),
+                k
+            );
+        }
+        it = it.wrapping_add(1);
+    }
+
+    // Print final cost
+    println!("{} - Final cost: {}", process_name, solution.get_cost());
+}
+
+/// Variants of variable neighbourhood search
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+pub enum VnsVariant {
+    /// Shaking followed by hill climbing in the first neighbourhood
+    Basic,
+    /// Shaking followed by variable neighbourhood descent
+    General,
+    /// Only variable neighbourhood descent, without shaking
+    Descent,
+}
@@ -1,5 +1,9 @@
+// Periodic checks are written as `x % n == 0` throughout, which newer clippy versions would rewrite to `is_multiple_of`
+#![allow(clippy::manual_is_multiple_of)]
+
 mod examples;
 mod heuristics;
+mod moves;
 mod solution;
 
 use std::fs;
@@ -9,10 +13,38 @@ use std::sync::Arc;
 
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::Select;
-use heuristics::{setup_simulated_annealing, setup_tempering};
+use heuristics::variable_neighbourhood_search::Neighbourhoods;
+use heuristics::{
+    setup_adaptive_large_neighbourhood_search, setup_branch_and_bound, setup_cma_es,
+    setup_constrained_simulated_annealing, setup_constructive_search, setup_differential_evolution,
+    setup_edge_assembly_crossover, setup_genetic_algorithm, setup_grasp, setup_hill_climbing,
+    setup_island_model, setup_iterated_local_search, setup_late_acceptance,
+    setup_memetic_algorithm, setup_monte_carlo_tree_search,
+    setup_multi_objective_simulated_annealing, setup_nsga_ii, setup_population_annealing,
+    setup_portfolio, setup_simulated_annealing, setup_tempering,
+    setup_variable_neighbourhood_search,
+};
 
-use crate::examples::tsp::{Tsp2OptMove, TspInstanceReader, TspSolution};
-use crate::solution::InstanceReader;
+use crate::examples::continuous::{ContinuousInstanceReader, ContinuousSolution};
+use crate::examples::cvrp::{
+    CvrpCrossExchange, CvrpInstanceReader, CvrpRelocate, CvrpSolution, CvrpSwap, CvrpTwoOpt,
+    CvrpTwoOptStar,
+};
+use crate::examples::max_cut::{MaxCutFlipMove, MaxCutInstanceReader, MaxCutSolution};
+use crate::examples::tsp::{
+    Tsp2OptMove, TspCheapestInsertion, TspCycleCrossover, TspDoubleBridgeMove,
+    TspEdgeRecombinationCrossover, TspInstanceReader, TspNaiveMove,
+    TspNearestNeighbourConstruction, TspOrderCrossover, TspPartialTour,
+    TspPartiallyMappedCrossover, TspRandomRemoval, TspRegretInsertion, TspShawRemoval, TspSolution,
+    TspWorstRemoval,
+};
+use crate::examples::vrptw::{
+    VrptwInstanceReader, VrptwRelocate, VrptwSolution, VrptwSwap, VrptwTwoOpt, VrptwTwoOptStar,
+};
+use crate::moves::{AdaptiveMove, Weighted, WeightedMove};
+use crate::solution::{
+    Crossover, InstanceReader, LocalRandomMove, MoveGenerator, Solution, TourSolution,
+};
 
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter};
@@ -21,12 +53,78 @@ use strum_macros::{Display, EnumIter};
 const DATASET_PATH: &str = "./input/";
 
 /// Define solution type and move. Override these for your problem!
+/// Several moves can be mixed with `moves::WeightedMove`, e.g. `WeightedMove<(Weighted<Tsp2OptMove, 70>, Weighted<TspNaiveMove, 30>)>`,
+/// or with `moves::AdaptiveMove` to learn the weights during the run, e.g. `AdaptiveMove<(Tsp2OptMove, TspNaiveMove)>`
 type MoveType = Tsp2OptMove;
 type SolutionType = TspSolution;
 
+/// Neighbourhood used by the deterministic local searches. Override this for your problem!
+type MoveGeneratorType = Tsp2OptMove;
+
+/// Move used to perturb local optima, should not be easily undone by the local search. Override this for your problem!
+type PerturbationType = TspDoubleBridgeMove;
+
+/// Ordered neighbourhoods for variable neighbourhood search, a tuple of moves. Override this for your problem!
+type NeighbourhoodsType = (Tsp2OptMove, TspNaiveMove);
+
+/// Destroy and repair operators for adaptive large neighbourhood search, tuples of operators. Override these for your problem!
+type DestroyOperatorsType = (TspRandomRemoval, TspWorstRemoval, TspShawRemoval);
+type RepairOperatorsType = (
+    TspCheapestInsertion,
+    TspRegretInsertion<2>,
+    TspRegretInsertion<3>,
+);
+
+/// Randomised greedy construction used by GRASP. Override this for your problem!
+type ConstructionType = TspNearestNeighbourConstruction;
+
+/// Partial solution used by the constructive heuristics. Override this for your problem!
+type PartialSolutionType = TspPartialTour;
+
+/// Largest instance multi-objective simulated annealing runs on, as the costs of every objective are recomputed after every move
+const MAX_MOSA_CITIES: usize = 2_000;
+
 /// Problem instance reader to use
 const INSTANCE_READER: TspInstanceReader = TspInstanceReader {};
 
+/// Solution type and instance reader of the heuristics for real vectors. Override these for your continuous problem!
+type ContinuousSolutionType = ContinuousSolution;
+const CONTINUOUS_INSTANCE_READER: ContinuousInstanceReader = ContinuousInstanceReader {};
+
+/// Solution type, moves and instance reader used for vehicle routing instances (.vrp). Override these for your routing problem!
+type RoutingSolutionType = CvrpSolution;
+type RoutingNeighbourhoodsType = (
+    CvrpRelocate,
+    CvrpSwap,
+    CvrpTwoOpt,
+    CvrpTwoOptStar,
+    CvrpCrossExchange,
+);
+type RoutingMoveType = AdaptiveMove<(
+    CvrpRelocate,
+    CvrpSwap,
+    CvrpTwoOpt,
+    CvrpTwoOptStar,
+    CvrpCrossExchange,
+)>;
+type RoutingMoveGeneratorType = CvrpRelocate;
+type RoutingPerturbationType =
+    WeightedMove<(Weighted<CvrpCrossExchange, 3>, Weighted<CvrpTwoOptStar, 1>)>;
+const ROUTING_INSTANCE_READER: CvrpInstanceReader = CvrpInstanceReader {};
+
+/// Solution type, moves and instance reader used for routing instances with time windows (Solomon and Homberger format)
+type TimeWindowSolutionType = VrptwSolution;
+type TimeWindowNeighbourhoodsType = (VrptwRelocate, VrptwSwap, VrptwTwoOptStar, VrptwTwoOpt);
+type TimeWindowMoveType = AdaptiveMove<(VrptwRelocate, VrptwSwap, VrptwTwoOptStar, VrptwTwoOpt)>;
+type TimeWindowMoveGeneratorType = VrptwRelocate;
+type TimeWindowPerturbationType = VrptwTwoOptStar;
+const TIME_WINDOW_INSTANCE_READER: VrptwInstanceReader = VrptwInstanceReader {};
+
+/// Solution type, move and instance reader used for maximum cut instances (.maxcut), a maximisation problem. Override these for your maximisation problem!
+type MaximisationSolutionType = MaxCutSolution;
+type MaximisationMoveType = MaxCutFlipMove;
+const MAXIMISATION_INSTANCE_READER: MaxCutInstanceReader = MaxCutInstanceReader {};
+
 fn main() -> std::io::Result<()> {
     // Read all instances from the input folder
     let paths = fs::read_dir(DATASET_PATH)
@@ -44,7 +142,7 @@ fn main() -> std::io::Result<()> {
     let instance_path = &paths_vec[instance_selection];
     let instance_name = instance_path
         .split('/')
-        .last()
+        .next_back()
         .unwrap()
         .split('.')
         .next()
@@ -62,8 +160,68 @@ fn main() -> std::io::Result<()> {
     // Set up Ctrl+C handler
     let stop_signal: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
 
+    // Heuristics for real vectors read the instance as a continuous problem
+    if let Heuristics::DifferentialEvolution | Heuristics::CmaEs = heuristic {
+        let solution = CONTINUOUS_INSTANCE_READER.read_instance(instance_path, Some(instance_name));
+        match heuristic {
+            Heuristics::DifferentialEvolution => {
+                setup_differential_evolution::<ContinuousSolutionType>(
+                    instance_name,
+                    solution,
+                    stop_signal,
+                );
+            }
+            _ => setup_cma_es::<ContinuousSolutionType>(instance_name, solution, stop_signal),
+        }
+        return Ok(());
+    }
+
+    // Vehicle routing instances only run the heuristics that need nothing but moves
+    if instance_path.ends_with(".vrp") {
+        let solution = ROUTING_INSTANCE_READER.read_instance(instance_path, Some(instance_name));
+        run_move_heuristic::<
+            RoutingMoveGeneratorType,
+            RoutingMoveType,
+            RoutingNeighbourhoodsType,
+            RoutingPerturbationType,
+            RoutingSolutionType,
+        >(heuristic, instance_name, solution, stop_signal);
+        return Ok(());
+    }
+    // Instances with time windows are recognised by their sections, as Solomon and Homberger instances are plain text files
+    let contents = fs::read_to_string(instance_path).unwrap_or_default();
+    if TIME_WINDOW_INSTANCE_READER.recognises(&contents) {
+        let solution =
+            TIME_WINDOW_INSTANCE_READER.read_instance(instance_path, Some(instance_name));
+        run_move_heuristic::<
+            TimeWindowMoveGeneratorType,
+            TimeWindowMoveType,
+            TimeWindowNeighbourhoodsType,
+            TimeWindowPerturbationType,
+            TimeWindowSolutionType,
+        >(heuristic, instance_name, solution, stop_signal);
+        return Ok(());
+    }
+
+    // Maximisation instances only run simulated annealing, the other heuristics still assume minimisation
+    if instance_path.ends_with(".maxcut") {
+        let solution =
+            MAXIMISATION_INSTANCE_READER.read_instance(instance_path, Some(instance_name));
+        match heuristic {
+            Heuristics::SimulatedAnnealing => {
+                setup_simulated_annealing::<MaximisationMoveType, MaximisationSolutionType>(
+                    instance_name,
+                    solution,
+                    stop_signal,
+                );
+            }
+            _ => println!("Heuristic not implemented yet for this problem"),
+        }
+        return Ok(());
+    }
+
     // Setup starting solution
-    let solution = INSTANCE_READER.read_instance(&instance_path, Some(instance_name));
+    let solution = INSTANCE_READER.read_instance(instance_path, Some(instance_name));
 
     // Run the selected heuristic
     match heuristic {
@@ -74,9 +232,152 @@ fn main() -> std::io::Result<()> {
                 stop_signal,
             );
         }
+        Heuristics::ConstrainedSimulatedAnnealing => {
+            setup_constrained_simulated_annealing::<MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
         Heuristics::Tempering => {
             setup_tempering::<MoveType, SolutionType>(instance_name, solution, stop_signal);
         }
+        Heuristics::LateAcceptance => {
+            setup_late_acceptance::<MoveType, SolutionType>(instance_name, solution, stop_signal);
+        }
+        Heuristics::HillClimbing => {
+            setup_hill_climbing::<MoveGeneratorType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::IteratedLocalSearch => {
+            setup_iterated_local_search::<
+                MoveGeneratorType,
+                MoveType,
+                PerturbationType,
+                SolutionType,
+            >(instance_name, solution, stop_signal);
+        }
+        Heuristics::AdaptiveLargeNeighbourhoodSearch => {
+            setup_adaptive_large_neighbourhood_search::<
+                DestroyOperatorsType,
+                RepairOperatorsType,
+                SolutionType,
+            >(instance_name, solution, stop_signal);
+        }
+        Heuristics::GeneticAlgorithm
+        | Heuristics::IslandModel
+        | Heuristics::MemeticAlgorithm
+        | Heuristics::NsgaIi => {
+            // Every crossover needs its own instance of the heuristic, so it is chosen here
+            let crossovers = Crossovers::iter().collect::<Vec<_>>();
+            let crossover_choice = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Select a crossover")
+                .items(&crossovers)
+                .default(0)
+                .interact()
+                .unwrap();
+            match crossovers[crossover_choice] {
+                Crossovers::Order => run_crossover_heuristic::<TspOrderCrossover>(
+                    heuristic,
+                    instance_name,
+                    solution,
+                    stop_signal,
+                ),
+                Crossovers::PartiallyMapped => {
+                    run_crossover_heuristic::<TspPartiallyMappedCrossover>(
+                        heuristic,
+                        instance_name,
+                        solution,
+                        stop_signal,
+                    )
+                }
+                Crossovers::Cycle => run_crossover_heuristic::<TspCycleCrossover>(
+                    heuristic,
+                    instance_name,
+                    solution,
+                    stop_signal,
+                ),
+                Crossovers::EdgeRecombination => run_crossover_heuristic::<
+                    TspEdgeRecombinationCrossover,
+                >(
+                    heuristic, instance_name, solution, stop_signal
+                ),
+            }
+        }
+        Heuristics::PopulationAnnealing => {
+            setup_population_annealing::<MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::Grasp => {
+            setup_grasp::<ConstructionType, MoveGeneratorType, MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::ConstructiveSearch => {
+            setup_constructive_search::<PartialSolutionType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::MonteCarloTreeSearch => {
+            setup_monte_carlo_tree_search::<PartialSolutionType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::BranchAndBound => {
+            setup_branch_and_bound::<PartialSolutionType, MoveGeneratorType, MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::Portfolio => {
+            setup_portfolio::<
+                PartialSolutionType,
+                DestroyOperatorsType,
+                MoveGeneratorType,
+                MoveType,
+                PerturbationType,
+                RepairOperatorsType,
+                SolutionType,
+            >(instance_name, solution, stop_signal);
+        }
+        Heuristics::EdgeAssemblyCrossover => {
+            setup_edge_assembly_crossover::<SolutionType>(instance_name, solution, stop_signal);
+        }
+        Heuristics::VariableNeighbourhoodSearch => {
+            setup_variable_neighbourhood_search::<NeighbourhoodsType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::MultiObjectiveSimulatedAnnealing => {
+            // The longest edge is recomputed after every move, which takes too long on large tours
+            if solution.tour().len() > MAX_MOSA_CITIES {
+                println!(
+                    "Multi-objective simulated annealing rescans the tour after every move, choose an instance of at most {} cities",
+                    MAX_MOSA_CITIES
+                );
+                return Ok(());
+            }
+            setup_multi_objective_simulated_annealing::<MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
         _ => {
             println!("Heuristic not implemented yet");
         }
@@ -85,20 +386,128 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }
 
+/// Runs one of the population based heuristics with the given crossover
+fn run_crossover_heuristic<C: Crossover<SolutionType>>(
+    heuristic: &Heuristics,
+    instance_name: &str,
+    solution: SolutionType,
+    stop_signal: Arc<AtomicBool>,
+) {
+    match heuristic {
+        Heuristics::GeneticAlgorithm => {
+            setup_genetic_algorithm::<C, MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::IslandModel => {
+            setup_island_model::<C, MoveGeneratorType, MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::MemeticAlgorithm => {
+            setup_memetic_algorithm::<C, MoveGeneratorType, MoveType, SolutionType>(
+                instance_name,
+                solution,
+                stop_signal,
+            );
+        }
+        Heuristics::NsgaIi => {
+            setup_nsga_ii::<C, MoveType, SolutionType>(instance_name, solution, stop_signal);
+        }
+        _ => {
+            println!("Heuristic does not use a crossover");
+        }
+    }
+}
+
+/// Runs one of the heuristics that only need moves, for problems without crossovers or constructions
+fn run_move_heuristic<G, M, N, P, T>(
+    heuristic: &Heuristics,
+    instance_name: &str,
+    solution: T,
+    stop_signal: Arc<AtomicBool>,
+) where
+    G: MoveGenerator<T>,
+    M: LocalRandomMove<T>,
+    N: Neighbourhoods<T>,
+    P: LocalRandomMove<T>,
+    T: Solution + 'static,
+{
+    match heuristic {
+        Heuristics::SimulatedAnnealing => {
+            setup_simulated_annealing::<M, T>(instance_name, solution, stop_signal);
+        }
+        Heuristics::ConstrainedSimulatedAnnealing => {
+            setup_constrained_simulated_annealing::<M, T>(instance_name, solution, stop_signal);
+        }
+        Heuristics::Tempering => setup_tempering::<M, T>(instance_name, solution, stop_signal),
+        Heuristics::PopulationAnnealing => {
+            setup_population_annealing::<M, T>(instance_name, solution, stop_signal);
+        }
+        Heuristics::LateAcceptance => {
+            setup_late_acceptance::<M, T>(instance_name, solution, stop_signal);
+        }
+        Heuristics::HillClimbing => {
+            setup_hill_climbing::<G, T>(instance_name, solution, stop_signal);
+        }
+        Heuristics::IteratedLocalSearch => {
+            setup_iterated_local_search::<G, M, P, T>(instance_name, solution, stop_signal);
+        }
+        Heuristics::VariableNeighbourhoodSearch => {
+            setup_variable_neighbourhood_search::<N, T>(instance_name, solution, stop_signal);
+        }
+        _ => {
+            println!("Heuristic not implemented yet for this problem");
+        }
+    }
+}
+
 /// Gets the number of system threads
 fn get_thread_count() -> u32 {
     let num_cpus = std::thread::available_parallelism();
-    let num_cpus = num_cpus.unwrap_or(NonZeroUsize::new(1).unwrap()).get() as u32;
-    num_cpus
+    num_cpus.unwrap_or(NonZeroUsize::new(1).unwrap()).get() as u32
 }
 
 #[derive(EnumIter, Display, Clone, Copy, Debug)]
 enum Heuristics {
     SimulatedAnnealing,
+    ConstrainedSimulatedAnnealing,
     Tempering,
+    PopulationAnnealing,
+    LateAcceptance,
+    HillClimbing,
+    IteratedLocalSearch,
+    VariableNeighbourhoodSearch,
+    Grasp,
+    ConstructiveSearch,
+    MonteCarloTreeSearch,
+    BranchAndBound,
+    AdaptiveLargeNeighbourhoodSearch,
+    GeneticAlgorithm,
+    MemeticAlgorithm,
+    EdgeAssemblyCrossover,
+    IslandModel,
+    Portfolio,
     TabuSearch,
     AntColonyOptimization,
     ParticleSwarmOptimization,
+    DifferentialEvolution,
+    CmaEs,
+    NsgaIi,
+    MultiObjectiveSimulatedAnnealing,
+}
+
+/// Crossovers the population based heuristics can choose from. Override these for your problem!
+#[derive(EnumIter, Display, Clone, Copy, Debug)]
+enum Crossovers {
+    Order,
+    PartiallyMapped,
+    Cycle,
+    EdgeRecombination,
 }
 
 //Todo:
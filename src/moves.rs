@@ -0,0 +1,427 @@
+// Combinators that build a new move out of several existing moves.
+// As moves have no state of their own, the solution keeps the index of the move that was applied last and the statistics of the adaptive moves
+// per combinator type, so undo_last_move can be routed to the right move even when a heuristic alternates between several solutions.
+// The statistics are reset at the end of every run, when they are reported.
+// The combinators are only used when configured as a move type in main.rs.
+
+use std::{any::TypeId, marker::PhantomData};
+
+use crate::{
+    heuristics::adaptive_weights::{
+        AdaptiveWeights, SCORE_ACCEPTED, SCORE_IMPROVEMENT, SCORE_NEW_BEST,
+    },
+    solution::{LocalRandomMove, OptimisationSense, Solution},
+};
+
+/// State of the move combinators applied to a solution
+#[derive(Clone, Default)]
+pub struct CombinatorState {
+    /// Index of the move last applied by every combinator type
+    last_applied: Vec<(TypeId, usize)>,
+    /// Statistics of every adaptive move type
+    adaptive_states: Vec<(TypeId, AdaptiveState)>,
+}
+
+impl CombinatorState {
+    /// Remembers which move of the list was applied last by the combinator C
+    fn set_last_applied<C: 'static>(&mut self, k: usize) {
+        let id = TypeId::of::<C>();
+        match self.last_applied.iter_mut().find(|(t, _)| *t == id) {
+            Some(entry) => entry.1 = k,
+            None => self.last_applied.push((id, k)),
+        }
+    }
+
+    /// Which move of the list was applied last by the combinator C
+    fn last_applied<C: 'static>(&self) -> usize {
+        let id = TypeId::of::<C>();
+        self.last_applied
+            .iter()
+            .find(|(t, _)| *t == id)
+            .map(|(_, k)| *k)
+            .expect("Undoing a move that was never done")
+    }
+
+    /// Statistics of the adaptive move C, created if it has none yet
+    fn adaptive_state<C: 'static>(&mut self, count: usize) -> &mut AdaptiveState {
+        let id = TypeId::of::<C>();
+        let index = match self.adaptive_states.iter().position(|(t, _)| *t == id) {
+            Some(index) => index,
+            None => {
+                self.adaptive_states.push((id, AdaptiveState::new(count)));
+                self.adaptive_states.len() - 1
+            }
+        };
+        &mut self.adaptive_states[index].1
+    }
+
+    /// Forgets the statistics of the adaptive move C
+    fn reset_adaptive_state<C: 'static>(&mut self) {
+        let id = TypeId::of::<C>();
+        self.adaptive_states.retain(|(t, _)| *t != id);
+    }
+}
+
+/// Solution that can be used with the move combinators, it keeps their state so the state follows the solution
+pub trait CombinatorSolution: Solution {
+    fn combinator_state(&mut self) -> &mut CombinatorState;
+}
+
+/// List of random moves, implemented for tuples of moves.
+pub trait MoveList<T: Solution> {
+    /// Number of moves in the list
+    const COUNT: usize;
+
+    /// Applies a random move of the k-th move type
+    fn do_random_move(k: usize, solution: &mut T);
+
+    /// Undoes the last move of the k-th move type
+    fn undo_last_move(k: usize, solution: &mut T);
+
+    /// Prints and resets the statistics of all moves in the list
+    fn report_statistics(solution: &mut T, process_name: &str);
+
+    /// Resets the statistics of all moves in the list
+    fn reset_statistics(solution: &mut T);
+}
+
+/// Relative selection weights of a list of moves, implemented for tuples of weighted moves.
+pub trait MoveListWeights {
+    /// Relative weight of the k-th move
+    fn weight(k: usize) -> u32;
+}
+
+macro_rules! impl_move_list {
+    ($count:expr; $($index:tt => $name:ident),+) => {
+        impl<T: Solution, $($name: LocalRandomMove<T>),+> MoveList<T> for ($($name,)+) {
+            const COUNT: usize = $count;
+
+            fn do_random_move(k: usize, solution: &mut T) {
+                match k {
+                    $($index => $name::do_random_move(solution),)+
+                    _ => panic!("Move {} does not exist", k),
+                }
+            }
+
+            fn undo_last_move(k: usize, solution: &mut T) {
+                match k {
+                    $($index => $name::undo_last_move(solution),)+
+                    _ => panic!("Move {} does not exist", k),
+                }
+            }
+
+            fn report_statistics(solution: &mut T, process_name: &str) {
+                $($name::report_statistics(solution, process_name);)+
+            }
+
+            fn reset_statistics(solution: &mut T) {
+                $($name::reset_statistics(solution);)+
+            }
+        }
+
+        impl<$($name: MoveWeight),+> MoveListWeights for ($($name,)+) {
+            fn weight(k: usize) -> u32 {
+                match k {
+                    $($index => $name::WEIGHT,)+
+                    _ => panic!("Move {} does not exist", k),
+                }
+            }
+        }
+    };
+}
+
+impl_move_list!(1; 0 => A);
+impl_move_list!(2; 0 => A, 1 => B);
+impl_move_list!(3; 0 => A, 1 => B, 2 => C);
+impl_move_list!(4; 0 => A, 1 => B, 2 => C, 3 => D);
+impl_move_list!(5; 0 => A, 1 => B, 2 => C, 3 => D, 4 => E);
+
+/// Relative weight of a move inside a weighted move
+pub trait MoveWeight {
+    const WEIGHT: u32;
+}
+
+/// Move M with relative weight W, to be used in the list of a weighted move.
+pub struct Weighted<M, const W: u32>(PhantomData<M>);
+
+impl<M, const W: u32> MoveWeight for Weighted<M, W> {
+    const WEIGHT: u32 = W;
+}
+
+impl<T: Solution, M: LocalRandomMove<T>, const W: u32> LocalRandomMove<T> for Weighted<M, W> {
+    fn do_random_move(solution: &mut T) {
+        M::do_random_move(solution);
+    }
+
+    fn undo_last_move(solution: &mut T) {
+        M::undo_last_move(solution);
+    }
+
+    fn report_statistics(solution: &mut T, process_name: &str) {
+        M::report_statistics(solution, process_name);
+    }
+
+    fn reset_statistics(solution: &mut T) {
+        M::reset_statistics(solution);
+    }
+}
+
+/// Picks one of the moves in the list at random, proportional to their weights.
+/// For example `WeightedMove<(Weighted<Tsp2OptMove, 70>, Weighted<TspNaiveMove, 30>)>` does a 2-opt move 70% of the time.
+pub struct WeightedMove<L>(PhantomData<L>);
+
+impl<T, L> LocalRandomMove<T> for WeightedMove<L>
+where
+    T: CombinatorSolution,
+    L: MoveList<T> + MoveListWeights + 'static,
+{
+    fn do_random_move(solution: &mut T) {
+        // Roulette wheel selection over the weights
+        let total_weight: u32 = (0..L::COUNT).map(L::weight).sum();
+        let mut remaining = rand::random::<u32>() % total_weight.max(1);
+        let mut k = 0;
+        while k < L::COUNT - 1 && remaining >= L::weight(k) {
+            remaining -= L::weight(k);
+            k += 1;
+        }
+
+        solution.combinator_state().set_last_applied::<Self>(k);
+        L::do_random_move(k, solution);
+    }
+
+    fn undo_last_move(solution: &mut T) {
+        let k = solution.combinator_state().last_applied::<Self>();
+        L::undo_last_move(k, solution);
+    }
+
+    fn report_statistics(solution: &mut T, process_name: &str) {
+        L::report_statistics(solution, process_name);
+    }
+
+    fn reset_statistics(solution: &mut T) {
+        L::reset_statistics(solution);
+    }
+}
+
+/// Number of moves after which the weights are updated
+const SEGMENT_LENGTH: u32 = 1_000;
+
+/// Move that was applied but not yet known to be accepted or undone
+#[derive(Clone)]
+struct PendingMove {
+    k: usize,
+    sense: OptimisationSense,
+    cost_before: f64,
+    cost_after: f64,
+}
+
+/// Weights and scores of an adaptive move on a single solution
+#[derive(Clone)]
+struct AdaptiveState {
+    weights: AdaptiveWeights,
+    /// Best cost after any scored move, none before the first one
+    best_cost: Option<f64>,
+    pending: Option<PendingMove>,
+}
+
+impl AdaptiveState {
+    fn new(count: usize) -> Self {
+        AdaptiveState {
+            weights: AdaptiveWeights::new(count, SEGMENT_LENGTH),
+            best_cost: None,
+            pending: None,
+        }
+    }
+
+    /// Scores the pending move, which was accepted if it was not undone
+    fn finish_pending(&mut self, accepted: bool) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+
+        let score = if !accepted {
+            0.0
+        } else if self
+            .best_cost
+            .is_none_or(|best_cost| pending.sense.is_better(pending.cost_after, best_cost))
+        {
+            self.best_cost = Some(pending.cost_after);
+            SCORE_NEW_BEST
+        } else if pending
+            .sense
+            .is_better(pending.cost_after, pending.cost_before)
+        {
+            SCORE_IMPROVEMENT
+        } else {
+            SCORE_ACCEPTED
+        };
+        self.weights.add_score(pending.k, score);
+    }
+}
+
+/// Picks one of the moves in the list by roulette wheel selection, adapting the weights to how well each move performs.
+/// A move scores when it leads to a new best solution, improves the current solution or is accepted, and nothing when undone.
+/// Every segment the weights move towards the average scores, the final weights are printed at the end of a run and then reset.
+pub struct AdaptiveMove<L>(PhantomData<L>);
+
+impl<T, L> LocalRandomMove<T> for AdaptiveMove<L>
+where
+    T: CombinatorSolution,
+    L: MoveList<T> + 'static,
+{
+    fn do_random_move(solution: &mut T) {
+        // The previous move was not undone, so it was accepted
+        let state = solution.combinator_state().adaptive_state::<Self>(L::COUNT);
+        state.finish_pending(true);
+        let k = state.weights.select(&mut rand::thread_rng());
+
+        // Do the move outside of the state, as moves in the list may be adaptive themselves
+        let cost_before = solution.get_cost();
+        solution.combinator_state().set_last_applied::<Self>(k);
+        L::do_random_move(k, solution);
+        let cost_after = solution.get_cost();
+
+        solution
+            .combinator_state()
+            .adaptive_state::<Self>(L::COUNT)
+            .pending = Some(PendingMove {
+            k,
+            sense: solution.sense(),
+            cost_before,
+            cost_after,
+        });
+    }
+
+    fn undo_last_move(solution: &mut T) {
+        let combinator_state = solution.combinator_state();
+        combinator_state
+            .adaptive_state::<Self>(L::COUNT)
+            .finish_pending(false);
+        let k = combinator_state.last_applied::<Self>();
+        L::undo_last_move(k, solution);
+    }
+
+    fn report_statistics(solution: &mut T, process_name: &str) {
+        let state = solution.combinator_state().adaptive_state::<Self>(L::COUNT);
+        println!(
+            "{} - Adaptive move weights: {:.3?}, selection probabilities: {:.3?}, times used: {:?}",
+            process_name,
+            state.weights.weights(),
+            state.weights.selection_probabilities(),
+            state.weights.total_uses()
+        );
+        solution.combinator_state().reset_adaptive_state::<Self>();
+        L::report_statistics(solution, process_name);
+    }
+
+    fn reset_statistics(solution: &mut T) {
+        solution.combinator_state().reset_adaptive_state::<Self>();
+        L::reset_statistics(solution);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::heuristics::simulated_annealing::determine_start_and_end_temp;
+
+    use super::*;
+
+    /// Counter that moves add to, remembering the last amount so it can be undone
+    #[derive(Clone, Default)]
+    struct Counter {
+        value: i64,
+        last_amount: i64,
+        combinator_state: CombinatorState,
+    }
+
+    impl Solution for Counter {
+        fn get_cost(&mut self) -> f64 {
+            self.value as f64
+        }
+
+        fn write_solution(&self, _file_path: &str) {}
+    }
+
+    impl CombinatorSolution for Counter {
+        fn combinator_state(&mut self) -> &mut CombinatorState {
+            &mut self.combinator_state
+        }
+    }
+
+    /// Adds A to the counter
+    struct Add<const A: i64>;
+
+    impl<const A: i64> LocalRandomMove<Counter> for Add<A> {
+        fn do_random_move(solution: &mut Counter) {
+            solution.value += A;
+            solution.last_amount = A;
+        }
+
+        fn undo_last_move(solution: &mut Counter) {
+            assert_eq!(solution.last_amount, A, "Undo routed to the wrong move");
+            solution.value -= A;
+        }
+    }
+
+    /// Applies the move to many pairs of solutions and undoes it in the opposite order, which alternates between them
+    fn check_interleaved_undo<M: LocalRandomMove<Counter>>() {
+        for _ in 0..1000 {
+            let (mut a, mut b) = (Counter::default(), Counter::default());
+            M::do_random_move(&mut a);
+            M::do_random_move(&mut b);
+            M::undo_last_move(&mut a);
+            M::undo_last_move(&mut b);
+            assert_eq!((a.value, b.value), (0, 0));
+        }
+    }
+
+    #[test]
+    fn weighted_move_undoes_interleaved_solutions() {
+        check_interleaved_undo::<WeightedMove<(Weighted<Add<1>, 1>, Weighted<Add<10>, 1>)>>();
+    }
+
+    #[test]
+    fn adaptive_move_undoes_interleaved_solutions() {
+        check_interleaved_undo::<AdaptiveMove<(Add<1>, Add<10>)>>();
+    }
+
+    #[test]
+    fn adaptive_best_cost_follows_the_optimisation_sense() {
+        for (sense, costs, best_cost) in [
+            (OptimisationSense::Minimise, [3.0, 1.0, 2.0], 1.0),
+            (OptimisationSense::Maximise, [1.0, 3.0, 2.0], 3.0),
+        ] {
+            let mut state = AdaptiveState::new(1);
+            let mut cost_before = 0.0;
+            for cost_after in costs {
+                state.pending = Some(PendingMove {
+                    k: 0,
+                    sense,
+                    cost_before,
+                    cost_after,
+                });
+                state.finish_pending(true);
+                cost_before = cost_after;
+            }
+            assert_eq!(state.best_cost, Some(best_cost));
+        }
+    }
+
+    #[test]
+    fn adaptive_statistics_are_reset_per_run() {
+        type Adaptive = AdaptiveMove<(Add<1>, Add<10>)>;
+        let mut counter = Counter::default();
+
+        // Moves that determine the temperature are forgotten
+        determine_start_and_end_temp::<Adaptive, Counter>(100, &mut counter, false);
+        assert!(counter.combinator_state.adaptive_states.is_empty());
+
+        // Moves of a run are forgotten once reported
+        for _ in 0..100 {
+            Adaptive::do_random_move(&mut counter);
+        }
+        assert!(!counter.combinator_state.adaptive_states.is_empty());
+        Adaptive::report_statistics(&mut counter, "test");
+        assert!(counter.combinator_state.adaptive_states.is_empty());
+    }
+}
@@ -1,15 +1,54 @@
+use std::{
+    hash::{Hash, Hasher},
+    ops::ControlFlow,
+};
+
 pub trait Solution: Clone + Send {
     /// Returns the cost of the solution. Could be recomputed from scratch or done more smartly based on previous cost.
     fn get_cost(&mut self) -> f64;
 
     /// Writes solution to a file. Useful for submitting solutions to online judges and to read back later.
     fn write_solution(&self, file_path: &str);
+
+    /// Whether the cost should be minimised or maximised. Override this for maximisation instead of negating the cost,
+    /// so the reported costs and the names of the output files are the real objective values.
+    fn sense(&self) -> OptimisationSense {
+        OptimisationSense::Minimise
+    }
+
+    /// How much the solution violates the constraints of the problem, 0 if it is feasible. The cost should not include it.
+    /// Override this to let heuristics with constraint handling explore infeasible solutions, it should be as cheap as get_cost.
+    fn constraint_violation(&mut self) -> f64 {
+        0.0
+    }
     
     // /// Reads a solution from a file. Overrides previously saved solution. Instance should have been read beforehand.
     // /// Note that write and read should be compatible, i.e. the same format, and idempotent, i.e. read(write(x)) == x.
     // fn read_override_solution(file_path: &str) -> Self;
 }
 
+/// Whether a lower or a higher cost is better
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptimisationSense {
+    Minimise,
+    Maximise,
+}
+
+impl OptimisationSense {
+    /// How much better the new cost is than the old cost, negative if it is worse
+    pub fn improvement(&self, old_cost: f64, new_cost: f64) -> f64 {
+        match self {
+            OptimisationSense::Minimise => old_cost - new_cost,
+            OptimisationSense::Maximise => new_cost - old_cost,
+        }
+    }
+
+    /// Whether cost a is strictly better than cost b
+    pub fn is_better(&self, a: f64, b: f64) -> bool {
+        self.improvement(b, a) > 0.0
+    }
+}
+
 pub trait LocalRandomMove<T: Solution> {
     /// Modifies the solution in place to a random neighboring solution.
     /// For efficiency, the move should update the cost function in the process instead of recomputing it from scratch.
@@ -18,11 +57,153 @@ pub trait LocalRandomMove<T: Solution> {
 
     /// Undoes the last move done by do_random_move. Should reset the cost function as well if not recomputed from scratch.
     fn undo_last_move(solution: &mut T);
+
+    /// Prints statistics gathered by the move during the run, if any, and resets them for the next run.
+    /// Called by heuristics at the end of a run with the final solution.
+    fn report_statistics(_solution: &mut T, _process_name: &str) {}
+
+    /// Forgets the statistics gathered so far, e.g. after moves that only sampled the neighbourhood.
+    fn reset_statistics(_solution: &mut T) {}
+}
+
+pub trait MoveGenerator<T: Solution> {
+    /// Representation of a single move, should contain enough information to apply it.
+    type Move;
+
+    /// Passes every possible move from the current solution to `visit`, stopping as soon as it breaks.
+    /// Moves are generated one at a time, as storing a quadratic neighbourhood is too expensive for large instances.
+    /// The outermost loop starts at position `start` (modulo its length) and wraps around, so callers can vary the order.
+    fn for_each_move(
+        solution: &T,
+        start: usize,
+        visit: impl FnMut(Self::Move) -> ControlFlow<Self::Move>,
+    ) -> ControlFlow<Self::Move>;
+
+    /// Returns the change in cost the move would cause, without applying it. Negative means an improvement.
+    fn evaluate_move(solution: &T, m: &Self::Move) -> f64;
+
+    /// Applies the move to the solution in place, updating the cost as well.
+    fn apply_move(solution: &mut T, m: &Self::Move);
+}
+
+/// Moves for path relinking, they bring a solution closer to a guiding solution
+pub trait RelinkingMoves<T: Solution + SolutionDistance>: MoveGenerator<T> {
+    /// Moves that each decrease the distance of the solution to the guide, empty if no such move exists
+    fn moves_towards(solution: &T, guide: &T) -> Vec<Self::Move>;
+}
+
+/// Randomised greedy construction, as used by GRASP
+pub trait GreedyConstruction<T: Solution> {
+    /// Builds a new solution for the instance of the given solution. Every step picks at random from the restricted candidate list,
+    /// the candidates within alpha of the way from the best to the worst candidate. Alpha 0 is pure greedy, 1 is fully random.
+    /// The cost of the new solution should be up to date.
+    fn construct(solution: &T, alpha: f64) -> T;
+}
+
+/// Partial solution built one step at a time, used by constructive heuristics such as beam search.
+/// A problem implements this next to its solution type, every partial solution can be extended until it is complete.
+pub trait PartialSolution<T: Solution>: Clone + Send + Sync {
+    /// A single construction step, e.g. the next city of a tour
+    type Extension: Clone + Hash + Send + Sync;
+
+    /// Partial solution without any steps for the instance of the given solution
+    fn empty(solution: &T) -> Self;
+
+    /// All ways to extend the partial solution by one step, empty once it is complete
+    fn extensions(&self) -> Vec<Self::Extension>;
+
+    /// Applies the extension in place
+    fn extend(&mut self, extension: &Self::Extension);
+
+    /// Whether every step has been made, so it can be turned into a solution
+    fn is_complete(&self) -> bool;
+
+    /// Score used to rank partial solutions, lower is better. Could be the cost so far or an estimate of the cost once complete.
+    /// For a complete partial solution it should be the cost of the solution.
+    fn score(&self) -> f64;
+
+    /// Score the partial solution would have after the extension, without applying it.
+    /// Override this when it can be computed without extending a copy.
+    fn score_extension(&self, extension: &Self::Extension) -> f64 {
+        let mut extended = self.clone();
+        extended.extend(extension);
+        extended.score()
+    }
+
+    /// Lower bound on the cost of every complete solution reachable from this partial solution, should never overestimate.
+    fn lower_bound(&self) -> f64;
+
+    /// Turns the complete partial solution into a solution, with its cost up to date
+    fn to_solution(&self) -> T;
+
+    /// Code identifying the extension in this partial solution, used to learn which extensions are good, e.g. by NRPA.
+    /// Defaults to a hash of the extension alone, override this to take the context into account, e.g. the edge a city is reached by.
+    fn extension_code(&self, extension: &Self::Extension) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        extension.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+pub trait SolutionDistance {
+    /// How different two solutions are, 0 if they are the same. Used to keep populations diverse.
+    fn distance_to(&self, other: &Self) -> f64;
+}
+
+/// Solution that visits every city exactly once, such as a travelling salesman tour.
+/// Allows heuristics that work directly on the edges of the tour.
+pub trait TourSolution: Solution {
+    /// Cities in the order they are visited
+    fn tour(&self) -> &[usize];
+
+    /// Length of the edge from city a to city b
+    fn edge_length(&self, a: usize, b: usize) -> f64;
+
+    /// Whether every edge has the same length in both directions. Heuristics that reverse parts of the tour
+    /// without looking at the edges inside, such as edge assembly crossover, assume it does.
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+
+    /// Creates a solution for the same instance visiting the cities in the given order, with its cost up to date
+    fn with_tour(&self, tour: Vec<usize>) -> Self;
+}
+
+/// Solution that is a vector of real numbers within box constraints, such as a point in the domain of a continuous function.
+/// Allows heuristics that work directly on the values, such as differential evolution.
+pub trait RealVectorSolution: Solution {
+    /// Values of the variables
+    fn values(&self) -> &[f64];
+
+    /// Lower and upper bound of every variable
+    fn bounds(&self) -> &[(f64, f64)];
+
+    /// Creates a solution for the same instance with the given values, which are within the bounds, with its cost up to date
+    fn with_values(&self, values: Vec<f64>) -> Self;
+}
+
+/// Solution with several objectives that are all minimised, such as the length and the longest edge of a tour.
+/// Allows multi-objective heuristics, which look for the Pareto front instead of a single best solution.
+pub trait MultiObjectiveSolution: Solution {
+    /// Cost of every objective, always in the same order. Should be up to date, like get_cost.
+    /// Multi-objective simulated annealing calls this after every move, so an objective recomputed from scratch makes it slow on large instances.
+    fn get_costs(&mut self) -> Vec<f64>;
+}
+
+pub trait Crossover<T: Solution> {
+    /// Creates a child that combines the two parents. The cost of the child should be up to date.
+    fn crossover(parent_a: &T, parent_b: &T) -> T;
+}
+
+pub trait DestroyOperator<T: Solution> {
+    /// Removes part of the solution, degree indicates how much, e.g. the number of elements to remove.
+    /// The removed elements should be kept in the solution so a repair operator can reinsert them, the cost should be updated as well.
+    fn destroy(solution: &mut T, degree: usize);
 }
 
-pub trait MoveGenerator<T: Solution, S> {
-    /// Generates a list of all possible moves from the current solution.
-    fn generate_moves(solution: &T) -> Vec<S>;
+pub trait RepairOperator<T: Solution> {
+    /// Reinserts all elements removed by the destroy operator, making the solution complete again.
+    fn repair(solution: &mut T);
 }
 
 pub trait InstanceReader<T: Solution> {